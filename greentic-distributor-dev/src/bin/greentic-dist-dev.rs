@@ -0,0 +1,166 @@
+use std::fs;
+use std::path::PathBuf;
+
+use clap::{Parser, Subcommand, ValueEnum};
+use greentic_distributor_client::{ComponentId, DistributorError, PackId, Version};
+use greentic_distributor_dev::{DevArtifact, DevConfig, DevDistributorSource, DevLayout};
+
+#[derive(Parser, Debug)]
+#[command(name = "greentic-dist-dev")]
+#[command(about = "Publish and prune artifacts in a local dev distributor layout")]
+struct Cli {
+    /// Dev distributor root directory
+    #[arg(long, global = true, default_value = ".greentic/dev")]
+    root: PathBuf,
+    /// Directory layout under the root
+    #[arg(long, global = true, value_enum, default_value_t = LayoutArg::Flat)]
+    layout: LayoutArg,
+    /// Pack path template for `--layout template`, e.g. `dist/{id}/{version}/{id}.{ext}`
+    #[arg(long, global = true)]
+    pack_template: Option<String>,
+    /// Component path template for `--layout template`
+    #[arg(long, global = true)]
+    component_template: Option<String>,
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Copy a built pack or component into the layout
+    Publish {
+        kind: KindArg,
+        id: String,
+        version: String,
+        file: PathBuf,
+    },
+    /// Remove a published pack or component
+    Remove {
+        kind: KindArg,
+        id: String,
+        version: String,
+    },
+    /// Keep only the newest versions of each pack/component
+    Prune {
+        #[arg(long, default_value_t = 1)]
+        keep: usize,
+    },
+    /// Regenerate index.json at the root from the current tree
+    Index,
+}
+
+#[derive(Clone, Copy, Debug, ValueEnum)]
+enum LayoutArg {
+    Flat,
+    ByIdAndVersion,
+    Template,
+    /// Detect the layout of the existing root, falling back to `flat`
+    Auto,
+}
+
+#[derive(Clone, Copy, Debug, ValueEnum)]
+enum KindArg {
+    Pack,
+    Component,
+}
+
+struct CliError {
+    code: i32,
+    message: String,
+}
+
+impl From<DistributorError> for CliError {
+    fn from(err: DistributorError) -> Self {
+        let code = if err.is_not_found() { 3 } else { 10 };
+        Self {
+            code,
+            message: err.to_string(),
+        }
+    }
+}
+
+fn main() {
+    if let Err(err) = run(Cli::parse()) {
+        eprintln!("{}", err.message);
+        std::process::exit(err.code);
+    }
+}
+
+fn run(cli: Cli) -> Result<(), CliError> {
+    let template = match (cli.pack_template, cli.component_template) {
+        (Some(pack), Some(component)) => Some(DevLayout::Template { pack, component }),
+        _ => None,
+    };
+    let layout = match cli.layout {
+        LayoutArg::Flat => DevLayout::Flat,
+        LayoutArg::ByIdAndVersion => DevLayout::ByIdAndVersion,
+        LayoutArg::Template => template.ok_or_else(|| CliError {
+            code: 2,
+            message: "--layout template requires --pack-template and --component-template".into(),
+        })?,
+        LayoutArg::Auto => {
+            let candidates: Vec<DevLayout> = template.into_iter().collect();
+            DevLayout::detect(&cli.root, &candidates).unwrap_or(DevLayout::Flat)
+        }
+    };
+    let source = DevDistributorSource::new(DevConfig {
+        root_dir: cli.root,
+        layout,
+        ..Default::default()
+    });
+    match cli.command {
+        Command::Publish {
+            kind,
+            id,
+            version,
+            file,
+        } => {
+            let bytes = fs::read(&file).map_err(|err| CliError {
+                code: 2,
+                message: format!("failed to read {}: {err}", file.display()),
+            })?;
+            let path = match artifact(kind, &id, &version)? {
+                DevArtifact::Pack { id, version } => source.publish_pack(&id, &version, &bytes)?,
+                DevArtifact::Component { id, version } => {
+                    source.publish_component(&id, &version, &bytes)?
+                }
+            };
+            println!("{}", path.display());
+        }
+        Command::Remove { kind, id, version } => {
+            source.remove(&artifact(kind, &id, &version)?)?;
+        }
+        Command::Prune { keep } => {
+            for removed in source.prune(keep)? {
+                println!("removed {} {}", removed.id(), removed.version());
+            }
+        }
+        Command::Index => {
+            let index = source.write_index()?;
+            println!(
+                "{} ({} packs, {} components)",
+                source.index_path().display(),
+                index.packs.len(),
+                index.components.len()
+            );
+        }
+    }
+    Ok(())
+}
+
+fn artifact(kind: KindArg, id: &str, version: &str) -> Result<DevArtifact, CliError> {
+    let invalid = |message: String| CliError { code: 2, message };
+    let version = Version::parse(version)
+        .map_err(|err| invalid(format!("invalid version `{version}`: {err}")))?;
+    Ok(match kind {
+        KindArg::Pack => DevArtifact::Pack {
+            id: PackId::new(id).map_err(|err| invalid(format!("invalid pack id `{id}`: {err}")))?,
+            version,
+        },
+        KindArg::Component => DevArtifact::Component {
+            id: ComponentId::new(id)
+                .map_err(|err| invalid(format!("invalid component id `{id}`: {err}")))?,
+            version,
+        },
+    })
+}
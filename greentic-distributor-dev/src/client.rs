@@ -0,0 +1,261 @@
+use std::collections::HashMap;
+use std::fs;
+use std::sync::Mutex;
+use std::time::SystemTime;
+
+use async_trait::async_trait;
+use chrono::{DateTime, SecondsFormat, Utc};
+use greentic_distributor_client::{
+    ArtifactLocation, CacheInfo, ComponentDigest, ComponentId, ComponentStatus, DistributorClient,
+    DistributorEnvironmentId, DistributorError, DistributorSource, PackId, PackStatusResponse,
+    ResolveComponentRequest, ResolveComponentResponse, SignatureSummary, TenantCtx, Version,
+};
+use semver::VersionReq;
+use serde_json::{Value, json};
+
+use crate::pack_dir::{Fingerprint, fingerprint};
+use crate::{DevArtifact, DevConfig, DevDistributorSource};
+
+/// [`DistributorClient`] answering from a local dev directory.
+///
+/// Components resolve to `ArtifactLocation::FilePath` with their sha256 digest and file
+/// timestamps. Requested versions may be exact (`1.2.3`), a semver requirement (`^1.2`)
+/// or `latest`. Packs report `pending` until `warm_pack` has read and verified the newest
+/// version, `ready` afterwards (until its bytes change), and `failed` if warming failed.
+/// Status checks only re-hash a warmed pack when its files changed size or mtime, or the
+/// index records a different digest. Tenant and environment are ignored.
+pub struct DevDistributorClient {
+    source: DevDistributorSource,
+    warmed: Mutex<HashMap<String, WarmState>>,
+}
+
+#[derive(Clone, Debug)]
+enum WarmState {
+    Ready {
+        version: Version,
+        digest: String,
+        /// Stat snapshot taken before warming; `None` if the path could not be read.
+        fingerprint: Option<Fingerprint>,
+    },
+    Failed {
+        version: Version,
+        reason: String,
+    },
+}
+
+impl DevDistributorClient {
+    pub fn new(cfg: DevConfig) -> Self {
+        Self::from_source(DevDistributorSource::new(cfg))
+    }
+
+    pub fn from_source(source: DevDistributorSource) -> Self {
+        Self {
+            source,
+            warmed: Mutex::new(HashMap::new()),
+        }
+    }
+
+    pub fn source(&self) -> &DevDistributorSource {
+        &self.source
+    }
+
+    /// Versions of `id` present on disk, oldest first.
+    fn versions(&self, pack: bool, id: &str) -> Vec<Version> {
+        let mut versions: Vec<Version> = self
+            .source
+            .artifacts()
+            .into_iter()
+            .filter(|(_, artifact)| {
+                matches!(artifact, DevArtifact::Pack { .. }) == pack && artifact.id() == id
+            })
+            .map(|(_, artifact)| artifact.version().clone())
+            .collect();
+        versions.sort();
+        versions
+    }
+
+    fn pick_version(
+        &self,
+        pack: bool,
+        id: &str,
+        requested: &str,
+    ) -> Result<Version, DistributorError> {
+        let requested = requested.trim();
+        if let Ok(version) = Version::parse(requested) {
+            return Ok(version);
+        }
+        let req = if requested.is_empty() || requested == "latest" {
+            VersionReq::STAR
+        } else {
+            VersionReq::parse(requested).map_err(|err| {
+                DistributorError::Other(format!("invalid version `{requested}`: {err}"))
+            })?
+        };
+        self.versions(pack, id)
+            .into_iter()
+            .rev()
+            .find(|version| req.matches(version))
+            .ok_or(DistributorError::NotFound)
+    }
+
+    /// Whether a warmed pack still has the digest it was warmed with.
+    ///
+    /// Trusts the digest while the files are unchanged and the index (if it lists the
+    /// pack) agrees; otherwise re-reads and verifies the pack.
+    fn still_warm(
+        &self,
+        id: &PackId,
+        version: &Version,
+        digest: &str,
+        warmed: Option<&Fingerprint>,
+    ) -> bool {
+        let path = self.source.pack_path(id, version);
+        let unchanged = warmed.is_some() && fingerprint(&path).ok().as_ref() == warmed;
+        let indexed = self
+            .source
+            .indexed_digest(&DevArtifact::Pack {
+                id: id.clone(),
+                version: version.clone(),
+            })
+            .ok()
+            .flatten();
+        if unchanged && indexed.is_none_or(|indexed| indexed == digest) {
+            return true;
+        }
+        self.source
+            .fetch_pack_artifact(id, version, Some(digest))
+            .is_ok()
+    }
+
+    fn latest_pack(&self, pack_id: &str) -> Result<(PackId, Version), DistributorError> {
+        let id = PackId::new(pack_id)
+            .map_err(|err| DistributorError::Other(format!("invalid pack id: {err}")))?;
+        let version = self.pick_version(true, pack_id, "latest")?;
+        Ok((id, version))
+    }
+}
+
+#[async_trait]
+impl DistributorClient for DevDistributorClient {
+    async fn resolve_component(
+        &self,
+        req: ResolveComponentRequest,
+    ) -> Result<ResolveComponentResponse, DistributorError> {
+        let id = ComponentId::new(&req.component_id)
+            .map_err(|err| DistributorError::Other(format!("invalid component id: {err}")))?;
+        let version = self.pick_version(false, id.as_str(), &req.version)?;
+        let fetched = self.source.fetch_component_artifact(&id, &version, None)?;
+        let path = self.source.component_path(&id, &version);
+        let meta = fs::metadata(&path)?;
+        let modified = meta.modified().ok();
+        let secret_requirements = self
+            .source
+            .secret_requirements(&DevArtifact::Component { id, version })?;
+
+        Ok(ResolveComponentResponse {
+            status: ComponentStatus::Ready,
+            digest: ComponentDigest(fetched.sha256),
+            artifact: ArtifactLocation::FilePath {
+                path: path.display().to_string(),
+            },
+            signature: SignatureSummary {
+                verified: false,
+                signer: String::new(),
+                extra: json!({ "reason": "unsigned local dev artifact" }),
+            },
+            cache: CacheInfo {
+                size_bytes: meta.len(),
+                last_used_utc: iso_utc(meta.accessed().ok().or(modified)),
+                last_refreshed_utc: iso_utc(modified),
+            },
+            secret_requirements,
+        })
+    }
+
+    async fn get_pack_status(
+        &self,
+        tenant: &TenantCtx,
+        env: &DistributorEnvironmentId,
+        pack_id: &str,
+    ) -> Result<Value, DistributorError> {
+        let status = self.get_pack_status_v2(tenant, env, pack_id).await?;
+        Ok(serde_json::to_value(status)?)
+    }
+
+    async fn get_pack_status_v2(
+        &self,
+        _tenant: &TenantCtx,
+        _env: &DistributorEnvironmentId,
+        pack_id: &str,
+    ) -> Result<PackStatusResponse, DistributorError> {
+        let (id, version) = self.latest_pack(pack_id)?;
+        let path = self.source.pack_path(&id, &version);
+        let warmed = self.warmed.lock().unwrap().get(pack_id).cloned();
+        let (status, extra) = match warmed {
+            Some(WarmState::Failed {
+                version: failed,
+                reason,
+            }) if failed == version => ("failed", json!({ "reason": reason })),
+            Some(WarmState::Ready {
+                version: ready,
+                digest,
+                fingerprint,
+            }) if ready == version
+                && self.still_warm(&id, &version, &digest, fingerprint.as_ref()) =>
+            {
+                ("ready", json!({ "digest": digest }))
+            }
+            _ => ("pending", json!({})),
+        };
+        let mut extra = extra;
+        extra["version"] = json!(version.to_string());
+        extra["path"] = json!(path.display().to_string());
+        let secret_requirements = self
+            .source
+            .secret_requirements(&DevArtifact::Pack { id, version })?;
+
+        Ok(PackStatusResponse {
+            status: status.into(),
+            secret_requirements,
+            extra,
+        })
+    }
+
+    async fn warm_pack(
+        &self,
+        _tenant: &TenantCtx,
+        _env: &DistributorEnvironmentId,
+        pack_id: &str,
+    ) -> Result<(), DistributorError> {
+        let (id, version) = self.latest_pack(pack_id)?;
+        // Taken first, so a change while reading makes the next status check re-hash.
+        let fingerprint = fingerprint(&self.source.pack_path(&id, &version)).ok();
+        let state = match self.source.fetch_pack_artifact(&id, &version, None) {
+            Ok(fetched) => WarmState::Ready {
+                version,
+                digest: fetched.sha256,
+                fingerprint,
+            },
+            Err(err) => {
+                self.warmed.lock().unwrap().insert(
+                    pack_id.to_string(),
+                    WarmState::Failed {
+                        version,
+                        reason: err.to_string(),
+                    },
+                );
+                return Err(err);
+            }
+        };
+        self.warmed
+            .lock()
+            .unwrap()
+            .insert(pack_id.to_string(), state);
+        Ok(())
+    }
+}
+
+fn iso_utc(time: Option<SystemTime>) -> String {
+    DateTime::<Utc>::from(time.unwrap_or(SystemTime::UNIX_EPOCH))
+        .to_rfc3339_opts(SecondsFormat::Secs, true)
+}
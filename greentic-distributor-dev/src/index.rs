@@ -0,0 +1,84 @@
+use std::fs;
+use std::io::ErrorKind;
+use std::path::Path;
+
+use greentic_distributor_client::{DistributorError, SecretRequirement, Version};
+use serde::{Deserialize, Serialize};
+
+/// File name of the optional manifest at the dev root.
+pub const INDEX_FILE: &str = "index.json";
+
+/// Current `index.json` schema version.
+pub const INDEX_SCHEMA_VERSION: u32 = 1;
+
+/// Optional `index.json` describing the packs/components in a dev directory.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DevIndex {
+    pub schema_version: u32,
+    #[serde(default)]
+    pub packs: Vec<DevIndexEntry>,
+    #[serde(default)]
+    pub components: Vec<DevIndexEntry>,
+}
+
+/// One pack or component listed in [`DevIndex`].
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DevIndexEntry {
+    pub id: String,
+    pub version: Version,
+    /// Digest of the served bytes, `sha256:<hex>` (a bare hex digest is also accepted).
+    pub sha256: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub secret_requirements: Vec<SecretRequirement>,
+}
+
+impl Default for DevIndex {
+    fn default() -> Self {
+        Self {
+            schema_version: INDEX_SCHEMA_VERSION,
+            packs: Vec::new(),
+            components: Vec::new(),
+        }
+    }
+}
+
+impl DevIndex {
+    /// Reads an index, returning `None` when the file does not exist.
+    pub fn load(path: &Path) -> Result<Option<Self>, DistributorError> {
+        let bytes = match fs::read(path) {
+            Ok(bytes) => bytes,
+            Err(err) if err.kind() == ErrorKind::NotFound => return Ok(None),
+            Err(err) => return Err(DistributorError::Io(err)),
+        };
+        let index: DevIndex = serde_json::from_slice(&bytes)
+            .map_err(|err| DistributorError::InvalidConfig(format!("{}: {err}", path.display())))?;
+        if index.schema_version != INDEX_SCHEMA_VERSION {
+            return Err(DistributorError::InvalidConfig(format!(
+                "{}: unsupported schema_version {}",
+                path.display(),
+                index.schema_version
+            )));
+        }
+        Ok(Some(index))
+    }
+
+    pub fn pack(&self, id: &str, version: &Version) -> Option<&DevIndexEntry> {
+        find(&self.packs, id, version)
+    }
+
+    pub fn component(&self, id: &str, version: &Version) -> Option<&DevIndexEntry> {
+        find(&self.components, id, version)
+    }
+}
+
+fn find<'a>(
+    entries: &'a [DevIndexEntry],
+    id: &str,
+    version: &Version,
+) -> Option<&'a DevIndexEntry> {
+    entries
+        .iter()
+        .find(|entry| entry.id == id && entry.version == *version)
+}
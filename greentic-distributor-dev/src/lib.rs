@@ -1,16 +1,66 @@
+use std::collections::BTreeMap;
 use std::fs;
-use std::io::ErrorKind;
+use std::io::{self, ErrorKind};
 use std::path::{Path, PathBuf};
 
+use pack_dir::PackDirCache;
+
+#[cfg(feature = "http-runtime")]
+use greentic_distributor_client::AsyncArtifactStream;
+
+use greentic_distributor_client::source_config::{DevLayoutSpec, DevSourceSpec, expand_template};
 use greentic_distributor_client::{
-    ComponentId, DistributorError, DistributorSource, PackId, Version,
+    ArtifactStream, ChainedDistributorSource, ComponentId, DistributorError, DistributorSource,
+    FetchedArtifact, HashingReader, PackId, SecretRequirement, SourceChainBuilder,
+    SourceChainConfig, Version,
 };
 
+mod client;
+mod index;
+mod pack_dir;
+mod watch;
+
+pub use client::DevDistributorClient;
+pub use index::{DevIndex, DevIndexEntry, INDEX_FILE, INDEX_SCHEMA_VERSION};
+pub use watch::{DevChange, DevChangeKind, DevWatcher, WatchOptions};
+
 /// Filesystem layout strategies for the dev distributor source.
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq, Eq)]
 pub enum DevLayout {
     Flat,
     ByIdAndVersion,
+    /// Custom paths relative to the root, e.g. `dist/{id}/{version}/{id}.{ext}`.
+    ///
+    /// Placeholders: `{id}`, `{version}`, `{major}`, `{minor}`, `{patch}` and `{ext}`
+    /// (`gtpack` or `wasm`). `packs_dir`/`components_dir` are ignored. Templates without a
+    /// version can still be served but their files are not reported by `artifacts`/`watch`.
+    Template {
+        pack: String,
+        component: String,
+    },
+}
+
+impl DevLayout {
+    /// Guesses the layout of an existing dev root using the default directory names.
+    ///
+    /// Tries `Flat`, `ByIdAndVersion` and then `candidates` (e.g. project templates),
+    /// returning whichever recognises the most artifacts, or `None` if none match.
+    pub fn detect(root: &Path, candidates: &[DevLayout]) -> Option<DevLayout> {
+        let builtin = [DevLayout::Flat, DevLayout::ByIdAndVersion];
+        let mut best: Option<(usize, &DevLayout)> = None;
+        for layout in builtin.iter().chain(candidates) {
+            let source = DevDistributorSource::new(DevConfig {
+                root_dir: root.to_path_buf(),
+                layout: layout.clone(),
+                ..Default::default()
+            });
+            let found = source.artifacts().len();
+            if found > 0 && best.is_none_or(|(count, _)| found > count) {
+                best = Some((found, layout));
+            }
+        }
+        best.map(|(_, layout)| layout.clone())
+    }
 }
 
 /// Configuration for [`DevDistributorSource`].
@@ -33,44 +83,430 @@ impl Default for DevConfig {
     }
 }
 
+impl From<&DevSourceSpec> for DevConfig {
+    fn from(spec: &DevSourceSpec) -> Self {
+        DevConfig {
+            root_dir: spec.root_dir.clone(),
+            packs_dir: spec.packs_dir.clone(),
+            components_dir: spec.components_dir.clone(),
+            layout: match &spec.layout {
+                DevLayoutSpec::Flat => DevLayout::Flat,
+                DevLayoutSpec::ByIdAndVersion => DevLayout::ByIdAndVersion,
+                DevLayoutSpec::Template { pack, component } => DevLayout::Template {
+                    pack: pack.clone(),
+                    component: component.clone(),
+                },
+            },
+        }
+    }
+}
+
+/// A pack or component identified from its location in the dev layout.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum DevArtifact {
+    Pack { id: PackId, version: Version },
+    Component { id: ComponentId, version: Version },
+}
+
+impl DevArtifact {
+    pub fn id(&self) -> &str {
+        match self {
+            DevArtifact::Pack { id, .. } => id.as_str(),
+            DevArtifact::Component { id, .. } => id.as_str(),
+        }
+    }
+
+    pub fn version(&self) -> &Version {
+        match self {
+            DevArtifact::Pack { version, .. } | DevArtifact::Component { version, .. } => version,
+        }
+    }
+}
+
+/// Builds a source chain from a declarative config, serving `dev` entries from disk.
+pub fn build_source_chain(
+    config: &SourceChainConfig,
+) -> Result<ChainedDistributorSource, DistributorError> {
+    SourceChainBuilder::new()
+        .with_dev_factory(|spec| Box::new(DevDistributorSource::new(DevConfig::from(spec))))
+        .build(config)
+}
+
 /// Serves packs/components directly from a local directory tree.
+///
+/// When the root holds an [`INDEX_FILE`], listed artifacts are verified against their
+/// recorded sha256 on read and streams carry the expected digest.
+///
+/// A directory found at a pack's layout path is treated as an unpacked pack and served
+/// as a deterministic gtpack zip, rebuilt only when the directory changes.
+#[derive(Clone)]
 pub struct DevDistributorSource {
     cfg: DevConfig,
+    pack_dirs: PackDirCache,
 }
 
 impl DevDistributorSource {
     pub fn new(cfg: DevConfig) -> Self {
-        Self { cfg }
+        Self {
+            cfg,
+            pack_dirs: PackDirCache::default(),
+        }
+    }
+
+    /// Watches the packs/components directories and emits debounced change batches.
+    ///
+    /// Hosts can use the reported ids/versions to invalidate and reload affected packs.
+    pub fn watch(&self, opts: WatchOptions) -> DevWatcher {
+        watch::spawn(self.clone(), opts)
+    }
+
+    /// Maps a file under the dev root back to the pack or component it serves.
+    pub fn artifact_for_path(&self, path: &Path) -> Option<DevArtifact> {
+        let rel = path.strip_prefix(self.root()).ok()?;
+        let rel = rel
+            .iter()
+            .map(|part| part.to_str())
+            .collect::<Option<Vec<_>>>()?
+            .join("/");
+        if let Some((id, version)) = match_template(Kind::Pack, &self.template(Kind::Pack), &rel)
+            && let Ok(id) = PackId::new(id)
+        {
+            return Some(DevArtifact::Pack { id, version });
+        }
+        if let Some((id, version)) =
+            match_template(Kind::Component, &self.template(Kind::Component), &rel)
+            && let Ok(id) = ComponentId::new(id)
+        {
+            return Some(DevArtifact::Component { id, version });
+        }
+        None
+    }
+
+    /// Writes pack bytes into the configured layout and returns the written path.
+    ///
+    /// The file is staged next to its destination and renamed into place, so readers and
+    /// watchers never observe a partial pack.
+    pub fn publish_pack(
+        &self,
+        pack_id: &PackId,
+        version: &Version,
+        bytes: &[u8],
+    ) -> Result<PathBuf, DistributorError> {
+        let path = self.pack_path(pack_id, version);
+        write_atomic(&path, bytes)?;
+        Ok(path)
+    }
+
+    /// Writes component bytes into the configured layout and returns the written path.
+    pub fn publish_component(
+        &self,
+        component_id: &ComponentId,
+        version: &Version,
+        bytes: &[u8],
+    ) -> Result<PathBuf, DistributorError> {
+        let path = self.component_path(component_id, version);
+        write_atomic(&path, bytes)?;
+        Ok(path)
+    }
+
+    /// Deletes a published artifact, returning `NotFound` when it is not present.
+    pub fn remove(&self, artifact: &DevArtifact) -> Result<(), DistributorError> {
+        let path = self.artifact_path(artifact);
+        let removed = if path.is_dir() {
+            fs::remove_dir_all(&path)
+        } else {
+            fs::remove_file(&path)
+        };
+        match removed {
+            Ok(()) => {}
+            Err(err) if err.kind() == ErrorKind::NotFound => {
+                return Err(DistributorError::NotFound);
+            }
+            Err(err) => return Err(DistributorError::Io(err)),
+        }
+        self.remove_empty_parents(&path, artifact);
+        Ok(())
+    }
+
+    /// Keeps the newest `keep_latest` versions of every pack/component and removes the rest.
+    ///
+    /// Returns the removed artifacts.
+    pub fn prune(&self, keep_latest: usize) -> Result<Vec<DevArtifact>, DistributorError> {
+        let mut groups: BTreeMap<(bool, String), Vec<DevArtifact>> = BTreeMap::new();
+        for (_, artifact) in self.artifacts() {
+            let is_pack = matches!(artifact, DevArtifact::Pack { .. });
+            groups
+                .entry((is_pack, artifact.id().to_string()))
+                .or_default()
+                .push(artifact);
+        }
+        let mut removed = Vec::new();
+        for mut versions in groups.into_values() {
+            versions.sort_by(|a, b| b.version().cmp(a.version()));
+            for artifact in versions.into_iter().skip(keep_latest) {
+                self.remove(&artifact)?;
+                removed.push(artifact);
+            }
+        }
+        Ok(removed)
+    }
+
+    pub fn index_path(&self) -> PathBuf {
+        self.root().join(INDEX_FILE)
+    }
+
+    /// Loads `index.json` from the root, if present.
+    pub fn index(&self) -> Result<Option<DevIndex>, DistributorError> {
+        DevIndex::load(&self.index_path())
+    }
+
+    /// Lists packs from the index, or from a freshly scanned tree when there is none.
+    pub fn list_packs(&self) -> Result<Vec<DevIndexEntry>, DistributorError> {
+        Ok(match self.index()? {
+            Some(index) => index.packs,
+            None => self.generate_index()?.packs,
+        })
+    }
+
+    /// Lists components from the index, or from a freshly scanned tree when there is none.
+    pub fn list_components(&self) -> Result<Vec<DevIndexEntry>, DistributorError> {
+        Ok(match self.index()? {
+            Some(index) => index.components,
+            None => self.generate_index()?.components,
+        })
+    }
+
+    /// Builds an index from the current tree, hashing every artifact.
+    ///
+    /// Descriptions and secret requirements are carried over from the existing index for
+    /// entries whose id and version are unchanged.
+    pub fn generate_index(&self) -> Result<DevIndex, DistributorError> {
+        let previous = self.index().ok().flatten().unwrap_or_default();
+        let mut index = DevIndex::default();
+        for (path, artifact) in self.artifacts() {
+            let (bytes, known, entries) = match &artifact {
+                DevArtifact::Pack { id, version } => (
+                    self.read_pack(&path)?,
+                    previous.pack(id.as_str(), version),
+                    &mut index.packs,
+                ),
+                DevArtifact::Component { id, version } => (
+                    self.read_file(&path)?,
+                    previous.component(id.as_str(), version),
+                    &mut index.components,
+                ),
+            };
+            entries.push(DevIndexEntry {
+                id: artifact.id().to_string(),
+                version: artifact.version().clone(),
+                sha256: FetchedArtifact::new(bytes, self.name(), None).sha256,
+                description: known.and_then(|entry| entry.description.clone()),
+                secret_requirements: known
+                    .map(|entry| entry.secret_requirements.clone())
+                    .unwrap_or_default(),
+            });
+        }
+        for entries in [&mut index.packs, &mut index.components] {
+            entries.sort_by(|a, b| (&a.id, &a.version).cmp(&(&b.id, &b.version)));
+        }
+        Ok(index)
+    }
+
+    /// Secret requirements for an artifact.
+    ///
+    /// Read from a `<artifact>.secrets.json` sidecar (a JSON array of requirements, e.g.
+    /// `echo-0.1.0.secrets.json` next to `echo-0.1.0.wasm`), falling back to the index.
+    pub fn secret_requirements(
+        &self,
+        artifact: &DevArtifact,
+    ) -> Result<Option<Vec<SecretRequirement>>, DistributorError> {
+        let sidecar = self.artifact_path(artifact).with_extension("secrets.json");
+        match fs::read(&sidecar) {
+            Ok(bytes) => {
+                let requirements = serde_json::from_slice(&bytes).map_err(|err| {
+                    DistributorError::InvalidConfig(format!("{}: {err}", sidecar.display()))
+                })?;
+                return Ok(Some(requirements));
+            }
+            Err(err) if err.kind() == ErrorKind::NotFound => {}
+            Err(err) => return Err(DistributorError::Io(err)),
+        }
+        let Some(index) = self.index()? else {
+            return Ok(None);
+        };
+        let entry = match artifact {
+            DevArtifact::Pack { id, version } => index.pack(id.as_str(), version),
+            DevArtifact::Component { id, version } => index.component(id.as_str(), version),
+        };
+        Ok(entry
+            .map(|entry| entry.secret_requirements.clone())
+            .filter(|requirements| !requirements.is_empty()))
+    }
+
+    /// Regenerates `index.json` from the tree and writes it atomically.
+    pub fn write_index(&self) -> Result<DevIndex, DistributorError> {
+        let index = self.generate_index()?;
+        let mut json = serde_json::to_vec_pretty(&index)?;
+        json.push(b'\n');
+        write_atomic(&self.index_path(), &json)?;
+        Ok(index)
+    }
+
+    /// Lists every pack/component currently present in the layout, including unpacked
+    /// pack directories.
+    pub fn artifacts(&self) -> Vec<(PathBuf, DevArtifact)> {
+        let is_pack_dir =
+            |path: &Path| matches!(self.artifact_for_path(path), Some(DevArtifact::Pack { .. }));
+        let mut files = Vec::new();
+        for dir in self.watch_roots() {
+            collect_files(&dir, &mut files, &is_pack_dir);
+        }
+        files.sort();
+        files
+            .into_iter()
+            .filter_map(|path| {
+                let artifact = self.artifact_for_path(&path)?;
+                Some((path, artifact))
+            })
+            .collect()
+    }
+
+    fn artifact_path(&self, artifact: &DevArtifact) -> PathBuf {
+        match artifact {
+            DevArtifact::Pack { id, version } => self.pack_path(id, version),
+            DevArtifact::Component { id, version } => self.component_path(id, version),
+        }
+    }
+
+    /// Drops the `{id}/{version}` directories left empty after a removal.
+    fn remove_empty_parents(&self, path: &Path, artifact: &DevArtifact) {
+        let base = match artifact {
+            DevArtifact::Pack { .. } => self.kind_root(Kind::Pack),
+            DevArtifact::Component { .. } => self.kind_root(Kind::Component),
+        };
+        let mut dir = path.parent();
+        while let Some(current) = dir {
+            if current == base || !current.starts_with(&base) || fs::remove_dir(current).is_err() {
+                break;
+            }
+            dir = current.parent();
+        }
+    }
+
+    fn watch_roots(&self) -> Vec<PathBuf> {
+        let mut roots = vec![self.kind_root(Kind::Pack)];
+        let components = self.kind_root(Kind::Component);
+        if !roots.contains(&components) {
+            roots.push(components);
+        }
+        roots
+    }
+
+    /// Layout template for one artifact kind, relative to the root.
+    fn template(&self, kind: Kind) -> String {
+        let dir = match kind {
+            Kind::Pack => &self.cfg.packs_dir,
+            Kind::Component => &self.cfg.components_dir,
+        };
+        let prefix = if dir.is_empty() {
+            String::new()
+        } else {
+            format!("{}/", dir.trim_end_matches('/'))
+        };
+        let template = match &self.cfg.layout {
+            DevLayout::Flat => format!("{prefix}{{id}}-{{version}}.{{ext}}"),
+            DevLayout::ByIdAndVersion => {
+                format!("{prefix}{{id}}/{{version}}/{}.{{ext}}", kind.stem())
+            }
+            DevLayout::Template { pack, component } => match kind {
+                Kind::Pack => pack.clone(),
+                Kind::Component => component.clone(),
+            },
+        };
+        template.replace("{ext}", kind.ext())
+    }
+
+    /// Deepest directory that holds every path the template can produce.
+    fn kind_root(&self, kind: Kind) -> PathBuf {
+        let template = self.template(kind);
+        let fixed = &template[..template.find('{').unwrap_or(template.len())];
+        match fixed.rfind('/') {
+            Some(idx) => self.root().join(&fixed[..idx]),
+            None => self.root().to_path_buf(),
+        }
+    }
+
+    pub(crate) fn pack_path(&self, pack_id: &PackId, version: &Version) -> PathBuf {
+        self.root().join(expand_template(
+            &self.template(Kind::Pack),
+            pack_id.as_str(),
+            version,
+        ))
     }
 
-    fn pack_path(&self, pack_id: &PackId, version: &Version) -> PathBuf {
-        match self.cfg.layout {
-            DevLayout::Flat => self
-                .root()
-                .join(&self.cfg.packs_dir)
-                .join(format!("{pack_id}-{version}.gtpack")),
-            DevLayout::ByIdAndVersion => self
-                .root()
-                .join(&self.cfg.packs_dir)
-                .join(pack_id.as_str())
-                .join(version.to_string())
-                .join("pack.gtpack"),
+    pub(crate) fn component_path(&self, component_id: &ComponentId, version: &Version) -> PathBuf {
+        self.root().join(expand_template(
+            &self.template(Kind::Component),
+            component_id.as_str(),
+            version,
+        ))
+    }
+
+    /// Digest recorded in the index for an artifact, if any.
+    pub(crate) fn indexed_digest(
+        &self,
+        artifact: &DevArtifact,
+    ) -> Result<Option<String>, DistributorError> {
+        let Some(index) = self.index()? else {
+            return Ok(None);
+        };
+        let entry = match artifact {
+            DevArtifact::Pack { id, version } => index.pack(id.as_str(), version),
+            DevArtifact::Component { id, version } => index.component(id.as_str(), version),
+        };
+        Ok(entry.map(|entry| entry.sha256.clone()))
+    }
+
+    fn verify_indexed(
+        &self,
+        artifact: &DevArtifact,
+        bytes: Vec<u8>,
+    ) -> Result<Vec<u8>, DistributorError> {
+        match self.indexed_digest(artifact)? {
+            Some(expected) => Ok(FetchedArtifact::new(bytes, self.name(), None)
+                .verify(Some(&expected))?
+                .bytes),
+            None => Ok(bytes),
         }
     }
 
-    fn component_path(&self, component_id: &ComponentId, version: &Version) -> PathBuf {
-        match self.cfg.layout {
-            DevLayout::Flat => self
-                .root()
-                .join(&self.cfg.components_dir)
-                .join(format!("{component_id}-{version}.wasm")),
-            DevLayout::ByIdAndVersion => self
-                .root()
-                .join(&self.cfg.components_dir)
-                .join(component_id.as_str())
-                .join(version.to_string())
-                .join("component.wasm"),
+    /// Opens an artifact for streaming along with its digest when known without reading
+    /// the file: the indexed one, or that of a pack directory zipped in memory. Other
+    /// files are never hashed up front; callers get their digest from `hashing()`.
+    fn open_artifact(
+        &self,
+        artifact: &DevArtifact,
+        path: &Path,
+    ) -> Result<(DevContent, Option<String>), DistributorError> {
+        let content = if path.is_dir() {
+            DevContent::Bytes(self.read_pack(path)?)
+        } else {
+            let (file, size) = self.open_file(path)?;
+            DevContent::File(file, size)
+        };
+        let digest = match (self.indexed_digest(artifact)?, &content) {
+            (Some(digest), _) => Some(digest),
+            (None, DevContent::Bytes(bytes)) => Some(hash_content(bytes.as_slice())?),
+            (None, DevContent::File(..)) => None,
+        };
+        Ok((content, digest))
+    }
+
+    fn read_pack(&self, path: &Path) -> Result<Vec<u8>, DistributorError> {
+        if path.is_dir() {
+            return Ok(self.pack_dirs.get_or_build(path)?.to_vec());
         }
+        self.read_file(path)
     }
 
     fn read_file(&self, path: &Path) -> Result<Vec<u8>, DistributorError> {
@@ -81,15 +517,205 @@ impl DevDistributorSource {
         }
     }
 
+    fn open_file(&self, path: &Path) -> Result<(fs::File, u64), DistributorError> {
+        let file = match fs::File::open(path) {
+            Ok(file) => file,
+            Err(err) if err.kind() == ErrorKind::NotFound => {
+                return Err(DistributorError::NotFound);
+            }
+            Err(err) => return Err(DistributorError::Io(err)),
+        };
+        let size = file.metadata()?.len();
+        Ok((file, size))
+    }
+
     fn root(&self) -> &Path {
         &self.cfg.root_dir
     }
 }
 
+fn hash_content(content: impl io::Read) -> Result<String, DistributorError> {
+    let mut reader = HashingReader::new(content);
+    io::copy(&mut reader, &mut io::sink())?;
+    reader.finish()
+}
+
+/// Opened artifact content, before it is wrapped in a sync or async stream.
+enum DevContent {
+    /// A pack directory zipped in memory.
+    Bytes(Vec<u8>),
+    File(fs::File, u64),
+}
+
+impl DevContent {
+    fn into_stream(self, digest: Option<String>) -> ArtifactStream {
+        let stream = match self {
+            DevContent::Bytes(bytes) => ArtifactStream::from_bytes(bytes),
+            DevContent::File(file, size) => ArtifactStream::new(file, Some(size)),
+        };
+        match digest {
+            Some(digest) => stream.with_digest(digest),
+            None => stream,
+        }
+    }
+
+    #[cfg(feature = "http-runtime")]
+    fn into_async_stream(self, digest: Option<String>) -> AsyncArtifactStream {
+        let stream = match self {
+            DevContent::Bytes(bytes) => AsyncArtifactStream::from_bytes(bytes),
+            DevContent::File(file, size) => {
+                AsyncArtifactStream::new(tokio::fs::File::from_std(file), Some(size))
+            }
+        };
+        match digest {
+            Some(digest) => stream.with_digest(digest),
+            None => stream,
+        }
+    }
+}
+
+/// Creates the parent directory, then writes through the client's atomic helper.
+fn write_atomic(path: &Path, bytes: &[u8]) -> Result<(), DistributorError> {
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    Ok(greentic_distributor_client::write_atomic(path, bytes)?)
+}
+
+/// Walks `dir`, treating directories accepted by `is_leaf` as single entries.
+fn collect_files(dir: &Path, files: &mut Vec<PathBuf>, is_leaf: &dyn Fn(&Path) -> bool) {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return;
+    };
+    for entry in entries.flatten() {
+        let path = entry.path();
+        match entry.file_type() {
+            Ok(kind) if kind.is_dir() && is_leaf(&path) => files.push(path),
+            Ok(kind) if kind.is_dir() => collect_files(&path, files, is_leaf),
+            Ok(_) => files.push(path),
+            Err(_) => {}
+        }
+    }
+}
+
+fn pack_artifact(id: &PackId, version: &Version) -> DevArtifact {
+    DevArtifact::Pack {
+        id: id.clone(),
+        version: version.clone(),
+    }
+}
+
+fn component_artifact(id: &ComponentId, version: &Version) -> DevArtifact {
+    DevArtifact::Component {
+        id: id.clone(),
+        version: version.clone(),
+    }
+}
+
+#[derive(Clone, Copy)]
+enum Kind {
+    Pack,
+    Component,
+}
+
+impl Kind {
+    fn ext(self) -> &'static str {
+        match self {
+            Kind::Pack => "gtpack",
+            Kind::Component => "wasm",
+        }
+    }
+
+    fn stem(self) -> &'static str {
+        match self {
+            Kind::Pack => "pack",
+            Kind::Component => "component",
+        }
+    }
+}
+
+/// Recovers `(id, version)` from a root-relative path using an expanded-`{ext}` template;
+/// `{id}` must be a valid id for `kind`.
+fn match_template(kind: Kind, template: &str, rel: &str) -> Option<(String, Version)> {
+    let mut vars = BTreeMap::new();
+    if !match_from(kind, template, rel, &mut vars) {
+        return None;
+    }
+    let number = |key: &str| vars.get(key).and_then(|value| value.parse::<u64>().ok());
+    let version = match vars.get("version") {
+        Some(version) => Version::parse(version).ok()?,
+        None => Version::new(number("major")?, number("minor")?, number("patch")?),
+    };
+    let parts = [
+        ("major", version.major),
+        ("minor", version.minor),
+        ("patch", version.patch),
+    ];
+    if parts
+        .iter()
+        .any(|(key, value)| vars.contains_key(key) && number(key) != Some(*value))
+    {
+        return None;
+    }
+    Some((vars.remove("id")?, version))
+}
+
+/// Backtracking matcher: placeholders bind to non-empty, `/`-free values that are valid
+/// for their kind, and repeated placeholders must bind to the same value.
+fn match_from<'t>(
+    kind: Kind,
+    template: &'t str,
+    input: &str,
+    vars: &mut BTreeMap<&'t str, String>,
+) -> bool {
+    let Some(start) = template.find('{') else {
+        return template == input;
+    };
+    let Some(end) = template[start..].find('}').map(|end| start + end) else {
+        return template == input;
+    };
+    let Some(input) = input.strip_prefix(&template[..start]) else {
+        return false;
+    };
+    let name = &template[start + 1..end];
+    let tail = &template[end + 1..];
+    if let Some(bound) = vars.get(name).cloned() {
+        return input
+            .strip_prefix(bound.as_str())
+            .is_some_and(|rest| match_from(kind, tail, rest, vars));
+    }
+    for (idx, ch) in input.char_indices() {
+        if ch == '/' {
+            break;
+        }
+        let value = &input[..idx + ch.len_utf8()];
+        if !placeholder_accepts(kind, name, value) {
+            continue;
+        }
+        vars.insert(name, value.to_string());
+        if match_from(kind, tail, &input[value.len()..], vars) {
+            return true;
+        }
+        vars.remove(name);
+    }
+    false
+}
+
+fn placeholder_accepts(kind: Kind, name: &str, value: &str) -> bool {
+    match (name, kind) {
+        ("id", Kind::Pack) => PackId::new(value).is_ok(),
+        ("id", Kind::Component) => ComponentId::new(value).is_ok(),
+        ("version", _) => Version::parse(value).is_ok(),
+        ("major" | "minor" | "patch", _) => value.bytes().all(|b| b.is_ascii_digit()),
+        _ => false,
+    }
+}
+
 impl DistributorSource for DevDistributorSource {
     fn fetch_pack(&self, pack_id: &PackId, version: &Version) -> Result<Vec<u8>, DistributorError> {
         let path = self.pack_path(pack_id, version);
-        self.read_file(&path)
+        let bytes = self.read_pack(&path)?;
+        self.verify_indexed(&pack_artifact(pack_id, version), bytes)
     }
 
     fn fetch_component(
@@ -98,6 +724,67 @@ impl DistributorSource for DevDistributorSource {
         version: &Version,
     ) -> Result<Vec<u8>, DistributorError> {
         let path = self.component_path(component_id, version);
-        self.read_file(&path)
+        let bytes = self.read_file(&path)?;
+        self.verify_indexed(&component_artifact(component_id, version), bytes)
+    }
+
+    fn open_pack(
+        &self,
+        pack_id: &PackId,
+        version: &Version,
+    ) -> Result<ArtifactStream, DistributorError> {
+        let path = self.pack_path(pack_id, version);
+        let (content, digest) = self.open_artifact(&pack_artifact(pack_id, version), &path)?;
+        Ok(content.into_stream(digest))
+    }
+
+    fn open_component(
+        &self,
+        component_id: &ComponentId,
+        version: &Version,
+    ) -> Result<ArtifactStream, DistributorError> {
+        let path = self.component_path(component_id, version);
+        let (content, digest) =
+            self.open_artifact(&component_artifact(component_id, version), &path)?;
+        Ok(content.into_stream(digest))
+    }
+
+    #[cfg(feature = "http-runtime")]
+    fn open_pack_async(
+        &self,
+        pack_id: &PackId,
+        version: &Version,
+    ) -> Result<AsyncArtifactStream, DistributorError> {
+        let path = self.pack_path(pack_id, version);
+        let (content, digest) = self.open_artifact(&pack_artifact(pack_id, version), &path)?;
+        Ok(content.into_async_stream(digest))
+    }
+
+    #[cfg(feature = "http-runtime")]
+    fn open_component_async(
+        &self,
+        component_id: &ComponentId,
+        version: &Version,
+    ) -> Result<AsyncArtifactStream, DistributorError> {
+        let path = self.component_path(component_id, version);
+        let (content, digest) =
+            self.open_artifact(&component_artifact(component_id, version), &path)?;
+        Ok(content.into_async_stream(digest))
+    }
+
+    fn name(&self) -> &str {
+        "dev"
+    }
+
+    fn pack_location(&self, pack_id: &PackId, version: &Version) -> Option<String> {
+        Some(self.pack_path(pack_id, version).display().to_string())
+    }
+
+    fn component_location(&self, component_id: &ComponentId, version: &Version) -> Option<String> {
+        Some(
+            self.component_path(component_id, version)
+                .display()
+                .to_string(),
+        )
     }
 }
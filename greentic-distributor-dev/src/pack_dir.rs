@@ -0,0 +1,103 @@
+use std::collections::HashMap;
+use std::fs;
+use std::io::{self, Cursor, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::SystemTime;
+
+use zip::write::SimpleFileOptions;
+use zip::{CompressionMethod, DateTime, ZipWriter};
+
+/// Stat-only snapshot of a file or directory tree; changes whenever an entry is added,
+/// removed, resized or touched.
+pub(crate) type Fingerprint = Vec<(PathBuf, Option<SystemTime>, u64)>;
+
+pub(crate) fn fingerprint(path: &Path) -> io::Result<Fingerprint> {
+    let mut entries = Vec::new();
+    fingerprint_into(path, &mut entries)?;
+    entries.sort();
+    Ok(entries)
+}
+
+fn fingerprint_into(path: &Path, entries: &mut Fingerprint) -> io::Result<()> {
+    let meta = fs::metadata(path)?;
+    entries.push((path.to_path_buf(), meta.modified().ok(), meta.len()));
+    if meta.is_dir() {
+        for entry in fs::read_dir(path)? {
+            fingerprint_into(&entry?.path(), entries)?;
+        }
+    }
+    Ok(())
+}
+
+/// Zips an unpacked pack directory into gtpack bytes.
+///
+/// Entries are sorted by their `/`-separated relative path and written with a fixed
+/// timestamp and permissions, so identical contents always produce identical bytes.
+pub(crate) fn build_archive(dir: &Path) -> io::Result<Vec<u8>> {
+    let mut files = Vec::new();
+    collect_relative(dir, dir, &mut files)?;
+    files.sort();
+
+    let options = SimpleFileOptions::default()
+        .compression_method(CompressionMethod::Deflated)
+        .last_modified_time(DateTime::default())
+        .unix_permissions(0o644);
+    let mut zip = ZipWriter::new(Cursor::new(Vec::new()));
+    for (name, path) in files {
+        zip.start_file(name, options).map_err(io::Error::other)?;
+        zip.write_all(&fs::read(&path)?)?;
+    }
+    Ok(zip.finish().map_err(io::Error::other)?.into_inner())
+}
+
+fn collect_relative(base: &Path, dir: &Path, files: &mut Vec<(String, PathBuf)>) -> io::Result<()> {
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.is_dir() {
+            collect_relative(base, &path, files)?;
+            continue;
+        }
+        let name = path
+            .strip_prefix(base)
+            .expect("walked path is under base")
+            .iter()
+            .map(|part| {
+                part.to_str().ok_or_else(|| {
+                    io::Error::new(
+                        io::ErrorKind::InvalidData,
+                        format!("non UTF-8 path in pack directory: {}", path.display()),
+                    )
+                })
+            })
+            .collect::<io::Result<Vec<_>>>()?
+            .join("/");
+        files.push((name, path));
+    }
+    Ok(())
+}
+
+type CachedArchive = (Fingerprint, Arc<Vec<u8>>);
+
+/// Built archives keyed by directory, reused until the directory fingerprint changes.
+#[derive(Clone, Default)]
+pub(crate) struct PackDirCache {
+    entries: Arc<Mutex<HashMap<PathBuf, CachedArchive>>>,
+}
+
+impl PackDirCache {
+    pub(crate) fn get_or_build(&self, dir: &Path) -> io::Result<Arc<Vec<u8>>> {
+        let current = fingerprint(dir)?;
+        if let Some((seen, bytes)) = self.entries.lock().unwrap().get(dir)
+            && *seen == current
+        {
+            return Ok(Arc::clone(bytes));
+        }
+        let bytes = Arc::new(build_archive(dir)?);
+        self.entries
+            .lock()
+            .unwrap()
+            .insert(dir.to_path_buf(), (current, Arc::clone(&bytes)));
+        Ok(bytes)
+    }
+}
@@ -0,0 +1,169 @@
+use std::collections::{BTreeMap, HashMap};
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, Receiver};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+use crate::pack_dir::{Fingerprint, fingerprint};
+use crate::{DevArtifact, DevDistributorSource};
+
+/// Polling and debounce settings for [`DevDistributorSource::watch`].
+#[derive(Clone, Debug)]
+pub struct WatchOptions {
+    /// How often the dev directories are rescanned.
+    pub poll_interval: Duration,
+    /// Quiet period required before a batch of changes is emitted.
+    pub debounce: Duration,
+}
+
+impl Default for WatchOptions {
+    fn default() -> Self {
+        Self {
+            poll_interval: Duration::from_millis(250),
+            debounce: Duration::from_millis(500),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DevChangeKind {
+    Added,
+    Modified,
+    Removed,
+}
+
+/// A pack or component that appeared, changed or disappeared in the dev layout.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DevChange {
+    pub kind: DevChangeKind,
+    pub artifact: DevArtifact,
+    pub path: PathBuf,
+}
+
+/// Handle to a background watch; stops polling when dropped.
+pub struct DevWatcher {
+    events: Receiver<Vec<DevChange>>,
+    stop: Arc<AtomicBool>,
+    handle: Option<JoinHandle<()>>,
+}
+
+impl DevWatcher {
+    /// Blocks until the next debounced batch of changes arrives.
+    pub fn recv(&self) -> Option<Vec<DevChange>> {
+        self.events.recv().ok()
+    }
+
+    /// Waits up to `timeout` for the next debounced batch of changes.
+    pub fn recv_timeout(&self, timeout: Duration) -> Option<Vec<DevChange>> {
+        self.events.recv_timeout(timeout).ok()
+    }
+
+    /// Returns a pending batch without blocking.
+    pub fn try_recv(&self) -> Option<Vec<DevChange>> {
+        self.events.try_recv().ok()
+    }
+}
+
+impl Drop for DevWatcher {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::SeqCst);
+        if let Some(handle) = self.handle.take() {
+            let _ = handle.join();
+        }
+    }
+}
+
+type Snapshot = HashMap<PathBuf, Fingerprint>;
+
+pub(crate) fn spawn(source: DevDistributorSource, opts: WatchOptions) -> DevWatcher {
+    let (tx, rx) = mpsc::channel();
+    let stop = Arc::new(AtomicBool::new(false));
+    let stop_flag = Arc::clone(&stop);
+    // Baseline is taken before returning so changes made right after `watch` are seen.
+    let mut previous = snapshot(&source);
+    let handle = thread::spawn(move || {
+        let mut pending: BTreeMap<PathBuf, DevChange> = BTreeMap::new();
+        let mut last_change = Instant::now();
+        while !stop_flag.load(Ordering::SeqCst) {
+            thread::sleep(opts.poll_interval);
+            let current = snapshot(&source);
+            let changes = diff(&source, &previous, &current);
+            previous = current;
+            if !changes.is_empty() {
+                last_change = Instant::now();
+                for change in changes {
+                    coalesce(&mut pending, change);
+                }
+            }
+            if !pending.is_empty() && last_change.elapsed() >= opts.debounce {
+                let batch = std::mem::take(&mut pending).into_values().collect();
+                if tx.send(batch).is_err() {
+                    break;
+                }
+            }
+        }
+    });
+    DevWatcher {
+        events: rx,
+        stop,
+        handle: Some(handle),
+    }
+}
+
+fn snapshot(source: &DevDistributorSource) -> Snapshot {
+    source
+        .artifacts()
+        .into_iter()
+        .filter_map(|(path, _)| {
+            let stamp = fingerprint(&path).ok()?;
+            Some((path, stamp))
+        })
+        .collect()
+}
+
+fn diff(source: &DevDistributorSource, previous: &Snapshot, current: &Snapshot) -> Vec<DevChange> {
+    let mut changes = Vec::new();
+    for (path, stamp) in current {
+        let kind = match previous.get(path) {
+            None => DevChangeKind::Added,
+            Some(old) if old != stamp => DevChangeKind::Modified,
+            Some(_) => continue,
+        };
+        if let Some(artifact) = source.artifact_for_path(path) {
+            changes.push(DevChange {
+                kind,
+                artifact,
+                path: path.clone(),
+            });
+        }
+    }
+    for path in previous.keys().filter(|path| !current.contains_key(*path)) {
+        if let Some(artifact) = source.artifact_for_path(path) {
+            changes.push(DevChange {
+                kind: DevChangeKind::Removed,
+                artifact,
+                path: path.clone(),
+            });
+        }
+    }
+    changes
+}
+
+/// Folds a new change into the pending batch so each path is reported once.
+fn coalesce(pending: &mut BTreeMap<PathBuf, DevChange>, change: DevChange) {
+    use DevChangeKind::*;
+    let Some(existing) = pending.remove(&change.path) else {
+        pending.insert(change.path.clone(), change);
+        return;
+    };
+    let kind = match (existing.kind, change.kind) {
+        // Created and deleted within one window: nothing to report.
+        (Added, Removed) => return,
+        (Added, _) => Added,
+        (Removed, Added) => Modified,
+        (_, kind) => kind,
+    };
+    pending.insert(change.path.clone(), DevChange { kind, ..change });
+}
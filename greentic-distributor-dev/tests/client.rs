@@ -0,0 +1,227 @@
+use std::fs;
+use std::path::Path;
+
+use greentic_distributor_client::{
+    ArtifactLocation, ComponentId, ComponentStatus, DistributorClient, DistributorEnvironmentId,
+    DistributorError, EnvId, PackId, ResolveComponentRequest, TenantCtx, TenantId, Version,
+};
+use greentic_distributor_dev::{DevConfig, DevDistributorClient, DevDistributorSource};
+use serde_json::json;
+use tempfile::tempdir;
+
+const ECHO_SHA256: &str = "sha256:092c79e8f80e559e404bcf660c48f3522b67aba9ff1484b0367e1a4ddef7431d";
+
+fn tenant() -> TenantCtx {
+    TenantCtx::new(
+        EnvId::try_from("dev").unwrap(),
+        TenantId::try_from("tenant-a").unwrap(),
+    )
+}
+
+fn env() -> DistributorEnvironmentId {
+    DistributorEnvironmentId::from("env-1")
+}
+
+fn request(component_id: &str, version: &str) -> ResolveComponentRequest {
+    ResolveComponentRequest {
+        tenant: tenant(),
+        environment_id: env(),
+        pack_id: "dev.local.hello-flow".into(),
+        component_id: component_id.into(),
+        version: version.into(),
+        extra: json!({}),
+    }
+}
+
+fn client(root: &Path) -> DevDistributorClient {
+    let source = DevDistributorSource::new(DevConfig {
+        root_dir: root.to_path_buf(),
+        ..Default::default()
+    });
+    let component_id = ComponentId::try_from("dev.greentic.echo").unwrap();
+    for version in ["0.1.0", "0.2.0", "1.0.0"] {
+        source
+            .publish_component(&component_id, &Version::parse(version).unwrap(), b"echo")
+            .unwrap();
+    }
+    DevDistributorClient::from_source(source)
+}
+
+#[tokio::test]
+async fn resolves_component_to_local_file() {
+    let root = tempdir().unwrap();
+    let client = client(root.path());
+    fs::write(
+        root.path()
+            .join("components/dev.greentic.echo-0.2.0.secrets.json"),
+        br#"[{"key": "api_token", "required": true, "description": "Echo API token"}]"#,
+    )
+    .unwrap();
+
+    let resp = client
+        .resolve_component(request("dev.greentic.echo", "0.2.0"))
+        .await
+        .unwrap();
+
+    assert_eq!(resp.status, ComponentStatus::Ready);
+    assert_eq!(resp.digest.0, ECHO_SHA256);
+    let expected = root.path().join("components/dev.greentic.echo-0.2.0.wasm");
+    assert_eq!(
+        resp.artifact,
+        ArtifactLocation::FilePath {
+            path: expected.display().to_string()
+        }
+    );
+    assert_eq!(resp.cache.size_bytes, 4);
+    assert!(resp.cache.last_refreshed_utc.ends_with('Z'));
+    assert!(!resp.signature.verified);
+    let secrets = resp.secret_requirements.unwrap();
+    assert_eq!(secrets[0].description.as_deref(), Some("Echo API token"));
+}
+
+#[tokio::test]
+async fn resolves_version_requirements_and_latest() {
+    let root = tempdir().unwrap();
+    let client = client(root.path());
+
+    let caret = client
+        .resolve_component(request("dev.greentic.echo", "^0.1"))
+        .await
+        .unwrap();
+    assert!(matches!(
+        caret.artifact,
+        ArtifactLocation::FilePath { path } if path.ends_with("dev.greentic.echo-0.1.0.wasm")
+    ));
+    let latest = client
+        .resolve_component(request("dev.greentic.echo", "latest"))
+        .await
+        .unwrap();
+    assert!(matches!(
+        latest.artifact,
+        ArtifactLocation::FilePath { path } if path.ends_with("dev.greentic.echo-1.0.0.wasm")
+    ));
+    assert!(latest.secret_requirements.is_none());
+
+    let missing = client
+        .resolve_component(request("dev.greentic.echo", "^2"))
+        .await
+        .unwrap_err();
+    assert!(matches!(missing, DistributorError::NotFound));
+}
+
+#[tokio::test]
+async fn pack_status_moves_from_pending_to_ready() {
+    let root = tempdir().unwrap();
+    let client = client(root.path());
+    let pack_id = PackId::try_from("dev.local.hello-flow").unwrap();
+    let version = Version::parse("0.3.0").unwrap();
+
+    let err = client
+        .get_pack_status_v2(&tenant(), &env(), pack_id.as_str())
+        .await
+        .unwrap_err();
+    assert!(matches!(err, DistributorError::NotFound));
+
+    client
+        .source()
+        .publish_pack(&pack_id, &version, b"pack")
+        .unwrap();
+    let status = client
+        .get_pack_status_v2(&tenant(), &env(), pack_id.as_str())
+        .await
+        .unwrap();
+    assert_eq!(status.status, "pending");
+    assert_eq!(status.extra["version"], "0.3.0");
+
+    client
+        .warm_pack(&tenant(), &env(), pack_id.as_str())
+        .await
+        .unwrap();
+    let status = client
+        .get_pack_status(&tenant(), &env(), pack_id.as_str())
+        .await
+        .unwrap();
+    assert_eq!(status["status"], "ready");
+
+    // Changing the bytes on disk invalidates the warm state.
+    client
+        .source()
+        .publish_pack(&pack_id, &version, b"pack-2")
+        .unwrap();
+    let status = client
+        .get_pack_status_v2(&tenant(), &env(), pack_id.as_str())
+        .await
+        .unwrap();
+    assert_eq!(status.status, "pending");
+}
+
+#[tokio::test]
+async fn pack_status_rehashes_only_when_stat_or_index_changes() {
+    let root = tempdir().unwrap();
+    let client = client(root.path());
+    let pack_id = PackId::try_from("dev.local.hello-flow").unwrap();
+    let version = Version::parse("0.3.0").unwrap();
+    let path = client
+        .source()
+        .publish_pack(&pack_id, &version, b"pack-a")
+        .unwrap();
+    client
+        .warm_pack(&tenant(), &env(), pack_id.as_str())
+        .await
+        .unwrap();
+    // Same size and mtime: the warmed digest is trusted without reading the pack.
+    let modified = fs::metadata(&path).unwrap().modified().unwrap();
+    fs::write(&path, b"pack-b").unwrap();
+    fs::File::options()
+        .write(true)
+        .open(&path)
+        .unwrap()
+        .set_modified(modified)
+        .unwrap();
+    let status = client
+        .get_pack_status_v2(&tenant(), &env(), pack_id.as_str())
+        .await
+        .unwrap();
+    assert_eq!(status.status, "ready");
+
+    // An index entry with another digest forces a re-hash, which fails verification.
+    client.source().write_index().unwrap();
+    let status = client
+        .get_pack_status_v2(&tenant(), &env(), pack_id.as_str())
+        .await
+        .unwrap();
+    assert_eq!(status.status, "pending");
+}
+
+#[tokio::test]
+async fn failed_warm_is_reported() {
+    let root = tempdir().unwrap();
+    let client = client(root.path());
+    let pack_id = PackId::try_from("dev.local.hello-flow").unwrap();
+    client
+        .source()
+        .publish_pack(&pack_id, &Version::parse("0.1.0").unwrap(), b"pack")
+        .unwrap();
+    fs::write(
+        root.path().join("index.json"),
+        br#"{"schema_version": 1, "packs": [{"id": "dev.local.hello-flow", "version": "0.1.0", "sha256": "sha256:00"}]}"#,
+    )
+    .unwrap();
+
+    let err = client
+        .warm_pack(&tenant(), &env(), pack_id.as_str())
+        .await
+        .unwrap_err();
+    assert!(matches!(err, DistributorError::DigestMismatch { .. }));
+    let status = client
+        .get_pack_status_v2(&tenant(), &env(), pack_id.as_str())
+        .await
+        .unwrap();
+    assert_eq!(status.status, "failed");
+    assert!(
+        status.extra["reason"]
+            .as_str()
+            .unwrap()
+            .contains("digest mismatch")
+    );
+}
@@ -1,9 +1,10 @@
 use std::fs;
 
 use greentic_distributor_client::{
-    ComponentId, DistributorError, DistributorSource, PackId, Version,
+    ChainedDistributorSource, ComponentId, DistributorError, DistributorSource, FetchedArtifact,
+    PackId, SourceChainConfig, Version,
 };
-use greentic_distributor_dev::{DevConfig, DevDistributorSource, DevLayout};
+use greentic_distributor_dev::{DevConfig, DevDistributorSource, DevLayout, build_source_chain};
 use tempfile::tempdir;
 
 #[test]
@@ -95,3 +96,147 @@ fn returns_not_found_when_missing() {
     let err = source.fetch_pack(&pack_id, &version).unwrap_err();
     assert!(matches!(err, DistributorError::NotFound));
 }
+
+#[test]
+fn chain_diagnostics_include_probed_paths() {
+    let root = tempdir().unwrap();
+    let cfg = DevConfig {
+        root_dir: root.path().to_path_buf(),
+        ..Default::default()
+    };
+    let chained = ChainedDistributorSource::new(vec![Box::new(DevDistributorSource::new(cfg))])
+        .with_miss_diagnostics(true);
+    let pack_id = PackId::try_from("dev.missing").unwrap();
+    let version = Version::parse("0.1.0").unwrap();
+
+    let err = chained.fetch_pack(&pack_id, &version).unwrap_err();
+    let expected_path = root.path().join("packs").join("dev.missing-0.1.0.gtpack");
+    assert_eq!(
+        err.to_string(),
+        format!(
+            "no source could serve the request: dev({}): missing",
+            expected_path.display()
+        )
+    );
+}
+
+#[test]
+fn streams_component_from_disk() {
+    let root = tempdir().unwrap();
+    let components_dir = root.path().join("components");
+    fs::create_dir_all(&components_dir).unwrap();
+    fs::write(
+        components_dir.join("dev.greentic.echo-0.1.0.wasm"),
+        b"streamed-component",
+    )
+    .unwrap();
+
+    let cfg = DevConfig {
+        root_dir: root.path().to_path_buf(),
+        ..Default::default()
+    };
+    let source = DevDistributorSource::new(cfg);
+    let component_id = ComponentId::try_from("dev.greentic.echo").unwrap();
+    let version = Version::parse("0.1.0").unwrap();
+
+    // Without an index the file is not hashed up front; `hashing()` reports its digest.
+    let expected = FetchedArtifact::new(b"streamed-component".to_vec(), "dev", None).sha256;
+    let stream = source.open_component(&component_id, &version).unwrap();
+    assert_eq!(stream.size, Some(18));
+    assert_eq!(stream.digest, None);
+    let mut reader = stream.hashing();
+    let mut out = Vec::new();
+    std::io::copy(&mut reader, &mut out).unwrap();
+    assert_eq!(out, b"streamed-component");
+    assert_eq!(reader.finish().unwrap(), expected);
+
+    let pack_id = PackId::try_from("dev.missing").unwrap();
+    let err = source.open_pack(&pack_id, &version).unwrap_err();
+    assert!(matches!(err, DistributorError::NotFound));
+}
+
+#[cfg(feature = "http-runtime")]
+#[tokio::test]
+async fn streams_component_asynchronously_and_hashes_served_bytes() {
+    let root = tempdir().unwrap();
+    let components_dir = root.path().join("components");
+    fs::create_dir_all(&components_dir).unwrap();
+    let path = components_dir.join("dev.greentic.echo-0.1.0.wasm");
+    fs::write(&path, b"streamed-component").unwrap();
+
+    let source = DevDistributorSource::new(DevConfig {
+        root_dir: root.path().to_path_buf(),
+        ..Default::default()
+    });
+    let component_id = ComponentId::try_from("dev.greentic.echo").unwrap();
+    let version = Version::parse("0.1.0").unwrap();
+
+    let stream = source
+        .open_component_async(&component_id, &version)
+        .unwrap();
+    assert_eq!(stream.size, Some(18));
+    let mut reader = stream.hashing();
+    let mut out = Vec::new();
+    tokio::io::copy(&mut reader, &mut out).await.unwrap();
+    assert_eq!(out, b"streamed-component");
+    assert!(reader.finish().is_ok());
+
+    // A file rewritten after opening reports the digest of the bytes actually served.
+    let stream = source
+        .open_component_async(&component_id, &version)
+        .unwrap();
+    fs::write(&path, b"rebuilt-component!").unwrap();
+    let mut reader = stream.hashing();
+    let mut out = Vec::new();
+    tokio::io::copy(&mut reader, &mut out).await.unwrap();
+    assert_eq!(
+        reader.finish().unwrap(),
+        FetchedArtifact::new(out, "dev", None).sha256
+    );
+}
+
+#[test]
+fn builds_chain_from_declarative_config() {
+    let root = tempdir().unwrap();
+    let nested = root.path().join("nested");
+    let pack_dir = nested
+        .join("packs")
+        .join("dev.local.hello-flow")
+        .join("0.1.0");
+    fs::create_dir_all(&pack_dir).unwrap();
+    fs::write(pack_dir.join("pack.gtpack"), b"from-config").unwrap();
+
+    let config = SourceChainConfig::from_toml_str(&format!(
+        r#"
+miss_diagnostics = true
+
+[[sources]]
+kind = "dev"
+name = "flat"
+root_dir = "{flat}"
+
+[[sources]]
+kind = "dev"
+name = "nested"
+root_dir = "{nested}"
+layout = "by_id_and_version"
+"#,
+        flat = root.path().join("flat").display(),
+        nested = nested.display(),
+    ))
+    .unwrap();
+    let chain = build_source_chain(&config).unwrap();
+
+    let pack_id = PackId::try_from("dev.local.hello-flow").unwrap();
+    let version = Version::parse("0.1.0").unwrap();
+    assert_eq!(
+        chain.fetch_pack(&pack_id, &version).unwrap(),
+        b"from-config"
+    );
+
+    let missing = PackId::try_from("dev.missing").unwrap();
+    let err = chain.fetch_pack(&missing, &version).unwrap_err();
+    let msg = err.to_string();
+    assert!(msg.contains("flat("), "unexpected error: {msg}");
+    assert!(msg.contains("nested("), "unexpected error: {msg}");
+}
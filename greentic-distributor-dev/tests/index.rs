@@ -0,0 +1,138 @@
+use std::fs;
+use std::process::Command;
+
+use assert_cmd::assert::OutputAssertExt;
+use greentic_distributor_client::{
+    ComponentId, DistributorError, DistributorSource, PackId, Version,
+};
+use greentic_distributor_dev::{DevConfig, DevDistributorSource, DevIndex, INDEX_FILE};
+use serde_json::json;
+use tempfile::tempdir;
+
+const HELLO_SHA256: &str =
+    "sha256:b94d27b9934d3e08a52e52d7da7dabfac484efe37a5380ee9088f7ace2efcde9";
+
+fn published(root: &std::path::Path) -> DevDistributorSource {
+    let source = DevDistributorSource::new(DevConfig {
+        root_dir: root.to_path_buf(),
+        ..Default::default()
+    });
+    source
+        .publish_pack(
+            &PackId::try_from("dev.local.hello-flow").unwrap(),
+            &Version::parse("0.1.0").unwrap(),
+            b"hello world",
+        )
+        .unwrap();
+    source
+        .publish_component(
+            &ComponentId::try_from("dev.greentic.echo").unwrap(),
+            &Version::parse("0.1.0").unwrap(),
+            b"component",
+        )
+        .unwrap();
+    source
+}
+
+#[test]
+fn lists_from_tree_without_index() {
+    let root = tempdir().unwrap();
+    let source = published(root.path());
+
+    assert!(source.index().unwrap().is_none());
+    let packs = source.list_packs().unwrap();
+    assert_eq!(packs.len(), 1);
+    assert_eq!(packs[0].id, "dev.local.hello-flow");
+    assert_eq!(packs[0].sha256, HELLO_SHA256);
+    assert_eq!(source.list_components().unwrap()[0].id, "dev.greentic.echo");
+}
+
+#[test]
+fn regenerating_keeps_metadata_and_verifies_reads() {
+    let root = tempdir().unwrap();
+    let source = published(root.path());
+    let pack_id = PackId::try_from("dev.local.hello-flow").unwrap();
+    let version = Version::parse("0.1.0").unwrap();
+
+    fs::write(
+        root.path().join(INDEX_FILE),
+        serde_json::to_vec(&json!({
+            "schema_version": 1,
+            "packs": [{
+                "id": "dev.local.hello-flow",
+                "version": "0.1.0",
+                "sha256": "0000",
+                "description": "Hello flow",
+                "secret_requirements": [{"key": "api_token", "required": true}]
+            }]
+        }))
+        .unwrap(),
+    )
+    .unwrap();
+
+    // The hand-written digest is wrong, so reads are rejected.
+    let err = source.fetch_pack(&pack_id, &version).unwrap_err();
+    assert!(matches!(err, DistributorError::DigestMismatch { .. }));
+    // Unlisted artifacts are still served.
+    source
+        .fetch_component(
+            &ComponentId::try_from("dev.greentic.echo").unwrap(),
+            &version,
+        )
+        .unwrap();
+
+    let index = source.write_index().unwrap();
+    let pack = &index.packs[0];
+    assert_eq!(pack.sha256, HELLO_SHA256);
+    assert_eq!(pack.description.as_deref(), Some("Hello flow"));
+    assert_eq!(pack.secret_requirements.len(), 1);
+    assert_eq!(index.components.len(), 1);
+    assert_eq!(
+        DevIndex::load(&root.path().join(INDEX_FILE))
+            .unwrap()
+            .unwrap(),
+        index
+    );
+
+    assert_eq!(
+        source.fetch_pack(&pack_id, &version).unwrap(),
+        b"hello world"
+    );
+    let stream = source.open_pack(&pack_id, &version).unwrap();
+    assert_eq!(stream.digest.as_deref(), Some(HELLO_SHA256));
+    assert_eq!(source.list_packs().unwrap(), index.packs);
+}
+
+#[test]
+fn rejects_unknown_schema_version() {
+    let root = tempdir().unwrap();
+    let source = published(root.path());
+    fs::write(root.path().join(INDEX_FILE), br#"{"schema_version": 2}"#).unwrap();
+
+    let err = source.list_packs().unwrap_err();
+    assert!(matches!(err, DistributorError::InvalidConfig(_)));
+}
+
+#[test]
+fn cli_regenerates_index() {
+    let root = tempdir().unwrap();
+    published(root.path());
+
+    let output = Command::new(assert_cmd::cargo::cargo_bin!("greentic-dist-dev"))
+        .args(["--root", root.path().to_str().unwrap(), "index"])
+        .assert()
+        .success()
+        .get_output()
+        .stdout
+        .clone();
+
+    assert!(
+        String::from_utf8(output)
+            .unwrap()
+            .contains("1 packs, 1 components")
+    );
+    let index = DevIndex::load(&root.path().join(INDEX_FILE))
+        .unwrap()
+        .unwrap();
+    assert_eq!(index.packs[0].sha256, HELLO_SHA256);
+}
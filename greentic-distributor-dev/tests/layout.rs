@@ -0,0 +1,161 @@
+use std::fs;
+use std::path::Path;
+
+use greentic_distributor_client::{
+    ComponentId, DistributorError, DistributorSource, PackId, Version,
+};
+use greentic_distributor_dev::{DevArtifact, DevConfig, DevDistributorSource, DevLayout};
+use tempfile::tempdir;
+
+fn monorepo_layout() -> DevLayout {
+    DevLayout::Template {
+        pack: "dist/{id}/{version}/{id}.{ext}".into(),
+        component: "target/wasm32-wasip2/release/{id}.{ext}".into(),
+    }
+}
+
+fn write(path: &Path, bytes: &[u8]) {
+    fs::create_dir_all(path.parent().unwrap()).unwrap();
+    fs::write(path, bytes).unwrap();
+}
+
+#[test]
+fn serves_from_template_layout() {
+    let root = tempdir().unwrap();
+    write(
+        &root
+            .path()
+            .join("dist/dev.local.hello-flow/0.1.0/dev.local.hello-flow.gtpack"),
+        b"pack-bytes",
+    );
+    write(
+        &root
+            .path()
+            .join("target/wasm32-wasip2/release/dev.greentic.echo.wasm"),
+        b"component-bytes",
+    );
+    let source = DevDistributorSource::new(DevConfig {
+        root_dir: root.path().to_path_buf(),
+        layout: monorepo_layout(),
+        ..Default::default()
+    });
+    let pack_id = PackId::try_from("dev.local.hello-flow").unwrap();
+    let component_id = ComponentId::try_from("dev.greentic.echo").unwrap();
+    let version = Version::parse("0.1.0").unwrap();
+
+    assert_eq!(
+        source.fetch_pack(&pack_id, &version).unwrap(),
+        b"pack-bytes"
+    );
+    assert!(matches!(
+        source.fetch_pack(&pack_id, &Version::parse("0.2.0").unwrap()),
+        Err(DistributorError::NotFound)
+    ));
+    // The component template has no version, so any requested version resolves to it.
+    assert_eq!(
+        source.fetch_component(&component_id, &version).unwrap(),
+        b"component-bytes"
+    );
+
+    // Only the versioned pack can be identified from its path.
+    let listed: Vec<DevArtifact> = source
+        .artifacts()
+        .into_iter()
+        .map(|(_, artifact)| artifact)
+        .collect();
+    assert_eq!(
+        listed,
+        vec![DevArtifact::Pack {
+            id: pack_id,
+            version,
+        }]
+    );
+}
+
+#[test]
+fn template_placeholders_for_version_parts() {
+    let root = tempdir().unwrap();
+    let source = DevDistributorSource::new(DevConfig {
+        root_dir: root.path().to_path_buf(),
+        layout: DevLayout::Template {
+            pack: "packs/v{major}/{id}-{major}.{minor}.{patch}.{ext}".into(),
+            component: "wasm/{id}@{version}.{ext}".into(),
+        },
+        ..Default::default()
+    });
+    let pack_id = PackId::try_from("dev.local.hello-flow").unwrap();
+    let version = Version::parse("2.3.4").unwrap();
+
+    let path = source.publish_pack(&pack_id, &version, b"pack").unwrap();
+    assert_eq!(
+        path,
+        root.path()
+            .join("packs/v2/dev.local.hello-flow-2.3.4.gtpack")
+    );
+    assert_eq!(
+        source.artifact_for_path(&path),
+        Some(DevArtifact::Pack {
+            id: pack_id.clone(),
+            version: version.clone(),
+        })
+    );
+    // `{major}` must agree everywhere it appears.
+    assert_eq!(
+        source.artifact_for_path(
+            &root
+                .path()
+                .join("packs/v1/dev.local.hello-flow-2.3.4.gtpack")
+        ),
+        None
+    );
+    assert_eq!(
+        source.artifact_for_path(&root.path().join("wasm/dev.greentic.echo@1.0.0-rc.1.wasm")),
+        Some(DevArtifact::Component {
+            id: ComponentId::try_from("dev.greentic.echo").unwrap(),
+            version: Version::parse("1.0.0-rc.1").unwrap(),
+        })
+    );
+    // `{id}` in the component template only binds valid component ids.
+    assert!(ComponentId::try_from("dev greentic echo").is_err());
+    assert_eq!(
+        source.artifact_for_path(&root.path().join("wasm/dev greentic echo@1.0.0.wasm")),
+        None
+    );
+}
+
+#[test]
+fn detects_existing_layouts() {
+    let flat = tempdir().unwrap();
+    write(
+        &flat.path().join("packs/dev.local.hello-flow-0.1.0.gtpack"),
+        b"pack",
+    );
+    assert_eq!(DevLayout::detect(flat.path(), &[]), Some(DevLayout::Flat));
+
+    let nested = tempdir().unwrap();
+    write(
+        &nested
+            .path()
+            .join("components/dev.greentic.echo/0.1.0/component.wasm"),
+        b"component",
+    );
+    assert_eq!(
+        DevLayout::detect(nested.path(), &[]),
+        Some(DevLayout::ByIdAndVersion)
+    );
+
+    let monorepo = tempdir().unwrap();
+    write(
+        &monorepo
+            .path()
+            .join("dist/dev.local.hello-flow/0.1.0/dev.local.hello-flow.gtpack"),
+        b"pack",
+    );
+    assert_eq!(
+        DevLayout::detect(monorepo.path(), &[monorepo_layout()]),
+        Some(monorepo_layout())
+    );
+
+    let empty = tempdir().unwrap();
+    assert_eq!(DevLayout::detect(empty.path(), &[monorepo_layout()]), None);
+}
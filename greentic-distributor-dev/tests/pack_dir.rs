@@ -0,0 +1,121 @@
+use std::fs;
+use std::io::{Cursor, Read};
+use std::path::Path;
+use std::thread;
+use std::time::Duration;
+
+use greentic_distributor_client::{DistributorSource, PackId, Version};
+use greentic_distributor_dev::{DevArtifact, DevConfig, DevDistributorSource};
+use tempfile::tempdir;
+
+fn write(path: &Path, bytes: &[u8]) {
+    fs::create_dir_all(path.parent().unwrap()).unwrap();
+    fs::write(path, bytes).unwrap();
+}
+
+fn source(root: &Path) -> DevDistributorSource {
+    DevDistributorSource::new(DevConfig {
+        root_dir: root.to_path_buf(),
+        ..Default::default()
+    })
+}
+
+fn ids() -> (PackId, Version) {
+    (
+        PackId::try_from("dev.local.hello-flow").unwrap(),
+        Version::parse("0.1.0").unwrap(),
+    )
+}
+
+#[test]
+fn zips_unpacked_pack_deterministically() {
+    let (pack_id, version) = ids();
+    let first = tempdir().unwrap();
+    let dir = first.path().join("packs/dev.local.hello-flow-0.1.0.gtpack");
+    write(&dir.join("manifest.cbor"), b"manifest");
+    write(&dir.join("flows/main.ygtc"), b"flow");
+    write(&dir.join("assets/logo.svg"), b"<svg/>");
+
+    // Same contents written in a different order, later, in another root.
+    thread::sleep(Duration::from_millis(20));
+    let second = tempdir().unwrap();
+    let other = second
+        .path()
+        .join("packs/dev.local.hello-flow-0.1.0.gtpack");
+    write(&other.join("assets/logo.svg"), b"<svg/>");
+    write(&other.join("flows/main.ygtc"), b"flow");
+    write(&other.join("manifest.cbor"), b"manifest");
+
+    let a = source(first.path())
+        .fetch_pack_artifact(&pack_id, &version, None)
+        .unwrap();
+    let b = source(second.path())
+        .fetch_pack_artifact(&pack_id, &version, None)
+        .unwrap();
+    assert_eq!(a.sha256, b.sha256);
+    assert_eq!(a.bytes, b.bytes);
+
+    let mut archive = zip::ZipArchive::new(Cursor::new(a.bytes)).unwrap();
+    let names: Vec<&str> = archive.file_names().collect();
+    assert_eq!(
+        names,
+        vec!["assets/logo.svg", "flows/main.ygtc", "manifest.cbor"]
+    );
+    let mut flow = String::new();
+    archive
+        .by_name("flows/main.ygtc")
+        .unwrap()
+        .read_to_string(&mut flow)
+        .unwrap();
+    assert_eq!(flow, "flow");
+}
+
+#[test]
+fn rebuilds_when_directory_changes() {
+    let (pack_id, version) = ids();
+    let root = tempdir().unwrap();
+    let dir = root.path().join("packs/dev.local.hello-flow-0.1.0.gtpack");
+    write(&dir.join("manifest.cbor"), b"manifest");
+    let source = source(root.path());
+
+    let before = source.fetch_pack(&pack_id, &version).unwrap();
+    assert_eq!(source.fetch_pack(&pack_id, &version).unwrap(), before);
+
+    write(&dir.join("flows/main.ygtc"), b"flow");
+    let after = source.fetch_pack(&pack_id, &version).unwrap();
+    assert_ne!(after, before);
+
+    let mut streamed = Vec::new();
+    let mut stream = source.open_pack(&pack_id, &version).unwrap();
+    assert_eq!(stream.size, Some(after.len() as u64));
+    stream.read_to_end(&mut streamed).unwrap();
+    assert_eq!(streamed, after);
+}
+
+#[test]
+fn lists_pack_directory_as_one_artifact() {
+    let (pack_id, version) = ids();
+    let root = tempdir().unwrap();
+    let dir = root.path().join("packs/dev.local.hello-flow-0.1.0.gtpack");
+    write(&dir.join("flows/main.ygtc"), b"flow");
+    let source = source(root.path());
+
+    assert_eq!(
+        source.artifacts(),
+        vec![(
+            dir.clone(),
+            DevArtifact::Pack {
+                id: pack_id.clone(),
+                version: version.clone(),
+            }
+        )]
+    );
+
+    source
+        .remove(&DevArtifact::Pack {
+            id: pack_id,
+            version,
+        })
+        .unwrap();
+    assert!(!dir.exists());
+}
@@ -0,0 +1,253 @@
+use std::fs;
+use std::process::Command;
+
+use assert_cmd::assert::OutputAssertExt;
+use greentic_distributor_client::{
+    ComponentId, DistributorError, DistributorSource, PackId, Version,
+};
+use greentic_distributor_dev::{DevArtifact, DevConfig, DevDistributorSource, DevLayout};
+use tempfile::tempdir;
+
+fn nested_source(root: &std::path::Path) -> DevDistributorSource {
+    DevDistributorSource::new(DevConfig {
+        root_dir: root.to_path_buf(),
+        layout: DevLayout::ByIdAndVersion,
+        ..Default::default()
+    })
+}
+
+#[test]
+fn publishes_into_layout_and_serves_back() {
+    let root = tempdir().unwrap();
+    let source = nested_source(root.path());
+    let pack_id = PackId::try_from("dev.local.hello-flow").unwrap();
+    let component_id = ComponentId::try_from("dev.greentic.echo").unwrap();
+    let version = Version::parse("0.1.0").unwrap();
+
+    let pack_path = source.publish_pack(&pack_id, &version, b"pack").unwrap();
+    let component_path = source
+        .publish_component(&component_id, &version, b"component")
+        .unwrap();
+
+    assert_eq!(
+        pack_path,
+        root.path()
+            .join("packs/dev.local.hello-flow/0.1.0/pack.gtpack")
+    );
+    assert_eq!(
+        component_path,
+        root.path()
+            .join("components/dev.greentic.echo/0.1.0/component.wasm")
+    );
+    assert_eq!(source.fetch_pack(&pack_id, &version).unwrap(), b"pack");
+    assert_eq!(
+        source.fetch_component(&component_id, &version).unwrap(),
+        b"component"
+    );
+
+    // Republishing replaces the bytes without leaving staging files behind.
+    source.publish_pack(&pack_id, &version, b"pack-2").unwrap();
+    assert_eq!(source.fetch_pack(&pack_id, &version).unwrap(), b"pack-2");
+    let entries: Vec<_> = fs::read_dir(pack_path.parent().unwrap())
+        .unwrap()
+        .map(|entry| entry.unwrap().file_name())
+        .collect();
+    assert_eq!(entries, vec!["pack.gtpack"]);
+}
+
+#[test]
+fn remove_deletes_artifact_and_empty_directories() {
+    let root = tempdir().unwrap();
+    let source = nested_source(root.path());
+    let pack_id = PackId::try_from("dev.local.hello-flow").unwrap();
+    let version = Version::parse("0.1.0").unwrap();
+    source.publish_pack(&pack_id, &version, b"pack").unwrap();
+
+    let artifact = DevArtifact::Pack {
+        id: pack_id.clone(),
+        version: version.clone(),
+    };
+    source.remove(&artifact).unwrap();
+
+    assert!(matches!(
+        source.fetch_pack(&pack_id, &version),
+        Err(DistributorError::NotFound)
+    ));
+    assert!(!root.path().join("packs/dev.local.hello-flow").exists());
+    assert!(root.path().join("packs").exists());
+    assert!(matches!(
+        source.remove(&artifact),
+        Err(DistributorError::NotFound)
+    ));
+}
+
+#[test]
+fn prune_keeps_latest_versions_per_id() {
+    let root = tempdir().unwrap();
+    let source = DevDistributorSource::new(DevConfig {
+        root_dir: root.path().to_path_buf(),
+        ..Default::default()
+    });
+    let pack_id = PackId::try_from("dev.local.hello-flow").unwrap();
+    let component_id = ComponentId::try_from("dev.greentic.echo").unwrap();
+    for version in ["0.1.0", "0.10.0", "0.2.0"] {
+        let version = Version::parse(version).unwrap();
+        source.publish_pack(&pack_id, &version, b"pack").unwrap();
+    }
+    source
+        .publish_component(&component_id, &Version::parse("1.0.0").unwrap(), b"c")
+        .unwrap();
+
+    let removed = source.prune(2).unwrap();
+
+    assert_eq!(
+        removed,
+        vec![DevArtifact::Pack {
+            id: pack_id,
+            version: Version::parse("0.1.0").unwrap(),
+        }]
+    );
+    let remaining: Vec<String> = source
+        .artifacts()
+        .into_iter()
+        .map(|(_, artifact)| format!("{}@{}", artifact.id(), artifact.version()))
+        .collect();
+    assert_eq!(
+        remaining,
+        vec![
+            "dev.greentic.echo@1.0.0",
+            "dev.local.hello-flow@0.10.0",
+            "dev.local.hello-flow@0.2.0",
+        ]
+    );
+}
+
+#[test]
+fn concurrent_publishes_of_one_artifact_do_not_collide() {
+    let root = tempdir().unwrap();
+    let source = DevDistributorSource::new(DevConfig {
+        root_dir: root.path().to_path_buf(),
+        ..Default::default()
+    });
+    let id = ComponentId::try_from("dev.greentic.echo").unwrap();
+    let version = Version::parse("0.1.0").unwrap();
+    let payloads: Vec<Vec<u8>> = (0..8).map(|n| vec![n; 64 * 1024]).collect();
+
+    std::thread::scope(|scope| {
+        for payload in &payloads {
+            let (source, id, version) = (&source, &id, &version);
+            scope.spawn(move || {
+                for _ in 0..16 {
+                    source.publish_component(id, version, payload).unwrap();
+                }
+            });
+        }
+    });
+
+    let published = source.fetch_component(&id, &version).unwrap();
+    assert!(payloads.contains(&published));
+    let names: Vec<_> = fs::read_dir(root.path().join("components"))
+        .unwrap()
+        .map(|entry| entry.unwrap().file_name())
+        .collect();
+    assert_eq!(
+        names,
+        vec![std::ffi::OsString::from("dev.greentic.echo-0.1.0.wasm")]
+    );
+}
+
+#[test]
+fn cli_publishes_and_prunes() {
+    let root = tempdir().unwrap();
+    let built = root.path().join("echo.wasm");
+    fs::write(&built, b"wasm").unwrap();
+    let dev_root = root.path().join("dev");
+
+    for version in ["0.1.0", "0.2.0"] {
+        let output = Command::new(assert_cmd::cargo::cargo_bin!("greentic-dist-dev"))
+            .args(["--root", dev_root.to_str().unwrap()])
+            .args(["publish", "component", "dev.greentic.echo", version])
+            .arg(&built)
+            .assert()
+            .success()
+            .get_output()
+            .stdout
+            .clone();
+        let printed = String::from_utf8(output).unwrap();
+        assert!(
+            printed
+                .trim()
+                .ends_with(&format!("dev.greentic.echo-{version}.wasm"))
+        );
+    }
+
+    Command::new(assert_cmd::cargo::cargo_bin!("greentic-dist-dev"))
+        .args(["--root", dev_root.to_str().unwrap(), "prune", "--keep", "1"])
+        .assert()
+        .success();
+    assert!(
+        !dev_root
+            .join("components/dev.greentic.echo-0.1.0.wasm")
+            .exists()
+    );
+    assert_eq!(
+        fs::read(dev_root.join("components/dev.greentic.echo-0.2.0.wasm")).unwrap(),
+        b"wasm"
+    );
+
+    let stderr = Command::new(assert_cmd::cargo::cargo_bin!("greentic-dist-dev"))
+        .args(["--root", dev_root.to_str().unwrap()])
+        .args(["remove", "component", "dev.greentic.echo", "0.1.0"])
+        .assert()
+        .code(3)
+        .get_output()
+        .stderr
+        .clone();
+    assert!(!String::from_utf8(stderr).unwrap().is_empty());
+}
+
+#[test]
+fn cli_publishes_with_template_and_detected_layouts() {
+    let root = tempdir().unwrap();
+    let built = root.path().join("echo.wasm");
+    fs::write(&built, b"wasm").unwrap();
+    let dev_root = root.path().join("dev");
+    let templates = [
+        "--pack-template",
+        "dist/{id}/{version}/{id}.{ext}",
+        "--component-template",
+        "dist/{id}/{version}/{id}.{ext}",
+    ];
+
+    Command::new(assert_cmd::cargo::cargo_bin!("greentic-dist-dev"))
+        .args(["--root", dev_root.to_str().unwrap(), "--layout", "template"])
+        .args(templates)
+        .args(["publish", "component", "dev.greentic.echo", "0.1.0"])
+        .arg(&built)
+        .assert()
+        .success();
+    assert!(
+        dev_root
+            .join("dist/dev.greentic.echo/0.1.0/dev.greentic.echo.wasm")
+            .is_file()
+    );
+
+    // `auto` picks the template candidate because it recognises the published file.
+    Command::new(assert_cmd::cargo::cargo_bin!("greentic-dist-dev"))
+        .args(["--root", dev_root.to_str().unwrap(), "--layout", "auto"])
+        .args(templates)
+        .args(["remove", "component", "dev.greentic.echo", "0.1.0"])
+        .assert()
+        .success();
+    assert!(
+        !dev_root
+            .join("dist/dev.greentic.echo/0.1.0/dev.greentic.echo.wasm")
+            .exists()
+    );
+
+    Command::new(assert_cmd::cargo::cargo_bin!("greentic-dist-dev"))
+        .args(["--root", dev_root.to_str().unwrap(), "--layout", "template"])
+        .args(["prune"])
+        .assert()
+        .code(2);
+}
@@ -0,0 +1,136 @@
+use std::fs;
+use std::path::Path;
+use std::time::Duration;
+
+use greentic_distributor_client::{ComponentId, PackId, Version};
+use greentic_distributor_dev::{
+    DevArtifact, DevChangeKind, DevConfig, DevDistributorSource, DevLayout, DevWatcher,
+    WatchOptions,
+};
+use tempfile::tempdir;
+
+fn fast_watch(source: &DevDistributorSource) -> DevWatcher {
+    source.watch(WatchOptions {
+        poll_interval: Duration::from_millis(20),
+        debounce: Duration::from_millis(100),
+    })
+}
+
+fn next_batch(watcher: &DevWatcher) -> Vec<(DevChangeKind, DevArtifact)> {
+    watcher
+        .recv_timeout(Duration::from_secs(10))
+        .expect("watcher emitted no changes")
+        .into_iter()
+        .map(|change| (change.kind, change.artifact))
+        .collect()
+}
+
+#[test]
+fn parses_artifacts_from_layout_paths() {
+    let root = tempdir().unwrap();
+    let flat = DevDistributorSource::new(DevConfig {
+        root_dir: root.path().to_path_buf(),
+        ..Default::default()
+    });
+    assert_eq!(
+        flat.artifact_for_path(
+            &root
+                .path()
+                .join("packs/dev.local.hello-flow-0.1.0-rc.1.gtpack")
+        ),
+        Some(DevArtifact::Pack {
+            id: PackId::try_from("dev.local.hello-flow").unwrap(),
+            version: Version::parse("0.1.0-rc.1").unwrap(),
+        })
+    );
+    assert_eq!(
+        flat.artifact_for_path(&root.path().join("components/notes.txt")),
+        None
+    );
+
+    let nested = DevDistributorSource::new(DevConfig {
+        root_dir: root.path().to_path_buf(),
+        layout: DevLayout::ByIdAndVersion,
+        ..Default::default()
+    });
+    assert_eq!(
+        nested.artifact_for_path(
+            &root
+                .path()
+                .join("components/dev.greentic.echo/1.2.3/component.wasm")
+        ),
+        Some(DevArtifact::Component {
+            id: ComponentId::try_from("dev.greentic.echo").unwrap(),
+            version: Version::parse("1.2.3").unwrap(),
+        })
+    );
+}
+
+#[test]
+fn emits_added_modified_and_removed_events() {
+    let root = tempdir().unwrap();
+    let components_dir = root.path().join("components");
+    fs::create_dir_all(&components_dir).unwrap();
+    let source = DevDistributorSource::new(DevConfig {
+        root_dir: root.path().to_path_buf(),
+        ..Default::default()
+    });
+    let watcher = fast_watch(&source);
+    let component = DevArtifact::Component {
+        id: ComponentId::try_from("dev.greentic.echo").unwrap(),
+        version: Version::parse("0.1.0").unwrap(),
+    };
+    let path = components_dir.join("dev.greentic.echo-0.1.0.wasm");
+
+    write(&path, b"v1");
+    assert_eq!(
+        next_batch(&watcher),
+        vec![(DevChangeKind::Added, component.clone())]
+    );
+
+    write(&path, b"version-2");
+    assert_eq!(
+        next_batch(&watcher),
+        vec![(DevChangeKind::Modified, component.clone())]
+    );
+
+    fs::remove_file(&path).unwrap();
+    assert_eq!(
+        next_batch(&watcher),
+        vec![(DevChangeKind::Removed, component)]
+    );
+}
+
+#[test]
+fn debounces_bursts_into_one_batch() {
+    let root = tempdir().unwrap();
+    let source = DevDistributorSource::new(DevConfig {
+        root_dir: root.path().to_path_buf(),
+        layout: DevLayout::ByIdAndVersion,
+        ..Default::default()
+    });
+    let watcher = fast_watch(&source);
+
+    let pack_dir = root.path().join("packs/dev.local.hello-flow/0.1.0");
+    fs::create_dir_all(&pack_dir).unwrap();
+    for body in [&b"a"[..], b"bb", b"ccc"] {
+        write(&pack_dir.join("pack.gtpack"), body);
+    }
+    write(&pack_dir.join("README.md"), b"ignored");
+
+    assert_eq!(
+        next_batch(&watcher),
+        vec![(
+            DevChangeKind::Added,
+            DevArtifact::Pack {
+                id: PackId::try_from("dev.local.hello-flow").unwrap(),
+                version: Version::parse("0.1.0").unwrap(),
+            }
+        )]
+    );
+    assert!(watcher.recv_timeout(Duration::from_millis(300)).is_none());
+}
+
+fn write(path: &Path, bytes: &[u8]) {
+    fs::write(path, bytes).unwrap();
+}
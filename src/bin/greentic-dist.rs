@@ -3,7 +3,9 @@ use greentic_distributor_client::dist_cli;
 #[tokio::main]
 async fn main() {
     if let Err(err) = dist_cli::run_from_env().await {
-        eprintln!("{}", err.message);
+        if !err.message.is_empty() {
+            eprintln!("{}", err.message);
+        }
         std::process::exit(err.code);
     }
 }
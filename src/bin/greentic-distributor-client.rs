@@ -8,7 +8,9 @@ async fn main() {
         );
     }
     if let Err(err) = dist_cli::run_from_env().await {
-        eprintln!("{}", err.message);
+        if !err.message.is_empty() {
+            eprintln!("{}", err.message);
+        }
         std::process::exit(err.code);
     }
 }
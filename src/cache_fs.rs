@@ -0,0 +1,196 @@
+use std::fs;
+use std::io::{self, Write};
+use std::path::Path;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+#[cfg(any(
+    feature = "oci-components",
+    feature = "pack-fetch",
+    feature = "runner-api"
+))]
+pub(crate) const LOCK_FILENAME: &str = ".lock";
+
+static TMP_COUNTER: AtomicU64 = AtomicU64::new(0);
+
+/// Writes `data` to `path` through a temp file in the same directory and a rename, so
+/// readers (and other processes) only ever see a missing or complete file.
+///
+/// The temp file name is unique to the call, so concurrent writers never share one. The
+/// file and, on Unix, its directory are synced, so a crash never leaves an empty file
+/// behind the rename.
+pub fn write_atomic(path: &Path, data: &[u8]) -> io::Result<()> {
+    write_staged(path, data, None)
+}
+
+/// Like [`write_atomic`], but the file is readable only by the current user (mode 0600
+/// on Unix) from the moment it is created.
+#[cfg(feature = "dist-client")]
+pub(crate) fn write_private(path: &Path, data: &[u8]) -> io::Result<()> {
+    write_staged(path, data, Some(0o600))
+}
+
+fn write_staged(path: &Path, data: &[u8], mode: Option<u32>) -> io::Result<()> {
+    let dir = path.parent().unwrap_or_else(|| Path::new("."));
+    let name = path
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    let tmp = dir.join(format!(
+        ".{name}.{}.{}.tmp",
+        std::process::id(),
+        TMP_COUNTER.fetch_add(1, Ordering::Relaxed)
+    ));
+    // `create_new` never reuses a leftover file, whose permissions could be looser.
+    let mut options = fs::OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    if let Some(mode) = mode {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(mode);
+    }
+    #[cfg(not(unix))]
+    let _ = mode;
+    let mut file = options.open(&tmp)?;
+    let result = (|| {
+        file.write_all(data)?;
+        file.sync_all()?;
+        drop(file);
+        fs::rename(&tmp, path)
+    })();
+    if result.is_err() {
+        let _ = fs::remove_file(&tmp);
+    }
+    result?;
+    #[cfg(unix)]
+    fs::File::open(dir)?.sync_all()?;
+    Ok(())
+}
+
+/// Exclusive advisory lock on one cache entry directory, released on drop.
+///
+/// Serializes commits of the same digest across processes and tasks: fetchers download
+/// unlocked, then take the lock only around writing the entry and re-check for a commit
+/// that landed first. `acquire` blocks the calling thread, so it must never be held across
+/// an `.await`.
+#[cfg(any(
+    feature = "oci-components",
+    feature = "pack-fetch",
+    feature = "runner-api"
+))]
+#[derive(Debug)]
+pub(crate) struct CacheLock {
+    _file: fs::File,
+}
+
+#[cfg(any(
+    feature = "oci-components",
+    feature = "pack-fetch",
+    feature = "runner-api"
+))]
+impl CacheLock {
+    /// Creates `dir` if needed and blocks until its lock is held.
+    ///
+    /// Eviction deletes an entry while holding its lock, so a lock won after waiting may
+    /// belong to a removed entry; the entry is then recreated and locked again.
+    pub(crate) fn acquire(dir: &Path) -> io::Result<Self> {
+        let path = dir.join(LOCK_FILENAME);
+        loop {
+            fs::create_dir_all(dir)?;
+            let file = match fs::OpenOptions::new()
+                .create(true)
+                .truncate(false)
+                .write(true)
+                .open(&path)
+            {
+                Ok(file) => file,
+                Err(err) if err.kind() == io::ErrorKind::NotFound => continue,
+                Err(err) => return Err(err),
+            };
+            file.lock()?;
+            if is_same_file(&file, &path) {
+                return Ok(Self { _file: file });
+            }
+        }
+    }
+}
+
+#[cfg(all(
+    unix,
+    any(
+        feature = "oci-components",
+        feature = "pack-fetch",
+        feature = "runner-api"
+    )
+))]
+pub(crate) fn is_same_file(file: &fs::File, path: &Path) -> bool {
+    use std::os::unix::fs::MetadataExt;
+    match (file.metadata(), fs::metadata(path)) {
+        (Ok(held), Ok(current)) => held.dev() == current.dev() && held.ino() == current.ino(),
+        _ => false,
+    }
+}
+
+#[cfg(all(
+    not(unix),
+    any(
+        feature = "oci-components",
+        feature = "pack-fetch",
+        feature = "runner-api"
+    )
+))]
+pub(crate) fn is_same_file(_file: &fs::File, path: &Path) -> bool {
+    // Eviction moves the whole entry aside, so a lock file still at `path` is ours.
+    path.exists()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn atomic_write_replaces_without_leftovers() {
+        let temp = tempfile::tempdir().unwrap();
+        let path = temp.path().join("component.wasm");
+        write_atomic(&path, b"first").unwrap();
+        write_atomic(&path, b"second").unwrap();
+        assert_eq!(fs::read(&path).unwrap(), b"second");
+        let names: Vec<_> = fs::read_dir(temp.path())
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name())
+            .collect();
+        assert_eq!(names, vec![std::ffi::OsString::from("component.wasm")]);
+    }
+
+    #[cfg(any(
+        feature = "oci-components",
+        feature = "pack-fetch",
+        feature = "runner-api"
+    ))]
+    #[test]
+    fn lock_serializes_holders() {
+        let temp = tempfile::tempdir().unwrap();
+        use std::sync::{Arc, Barrier};
+
+        let dir = Arc::new(temp.path().join("entry"));
+        let log = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let barrier = Arc::new(Barrier::new(2));
+        let handles: Vec<_> = (0..2)
+            .map(|id| {
+                let (dir, log, barrier) = (dir.clone(), log.clone(), barrier.clone());
+                std::thread::spawn(move || {
+                    barrier.wait();
+                    let _lock = CacheLock::acquire(&dir).unwrap();
+                    log.lock().unwrap().push((id, "start"));
+                    std::thread::sleep(std::time::Duration::from_millis(50));
+                    log.lock().unwrap().push((id, "end"));
+                })
+            })
+            .collect();
+        for handle in handles {
+            handle.join().unwrap();
+        }
+        let log = log.lock().unwrap();
+        assert_eq!(log[0].0, log[1].0);
+        assert_eq!(log[2].0, log[3].0);
+    }
+}
@@ -0,0 +1,403 @@
+use std::fs::{self, File};
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+use serde::Serialize;
+
+use crate::cache_fs::{LOCK_FILENAME, is_same_file};
+
+const LAST_ACCESS_FILENAME: &str = ".last_access";
+const PINNED_FILENAME: &str = ".pinned";
+
+/// Environment variable with the maximum cache size (`10G`, `512M`, bytes).
+pub const CACHE_MAX_SIZE_ENV: &str = "GREENTIC_DIST_CACHE_MAX_SIZE";
+
+/// Environment variable with the maximum entry age since last access (`30d`, `12h`, seconds).
+pub const CACHE_MAX_AGE_ENV: &str = "GREENTIC_DIST_CACHE_MAX_AGE";
+
+/// Bounds for a cache root; `None` leaves that dimension unbounded.
+///
+/// Entries are `<root>/<sha256 hex>/` directories. Pruning first removes entries not accessed
+/// within `max_age`, then least-recently-used entries until the root fits in
+/// `max_size_bytes`. Pinned entries and entries locked by an in-flight fetch are never
+/// removed, so a root may stay above `max_size_bytes` if pins alone exceed it.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct CachePolicy {
+    pub max_size_bytes: Option<u64>,
+    pub max_age: Option<Duration>,
+}
+
+impl CachePolicy {
+    /// Policy from `GREENTIC_DIST_CACHE_MAX_SIZE`/`GREENTIC_DIST_CACHE_MAX_AGE`; unset or
+    /// unparsable values are unbounded.
+    pub fn from_env() -> Self {
+        let var = |name| std::env::var(name).ok().filter(|v: &String| !v.is_empty());
+        Self {
+            max_size_bytes: var(CACHE_MAX_SIZE_ENV).and_then(|v| parse_size(&v).ok()),
+            max_age: var(CACHE_MAX_AGE_ENV).and_then(|v| parse_age(&v).ok()),
+        }
+    }
+
+    pub fn is_unbounded(&self) -> bool {
+        self.max_size_bytes.is_none() && self.max_age.is_none()
+    }
+}
+
+/// Outcome of pruning one cache root.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize)]
+pub struct PruneReport {
+    /// Digests of the removed entries, oldest access first.
+    pub removed: Vec<String>,
+    pub freed_bytes: u64,
+    pub remaining_bytes: u64,
+}
+
+/// Parses a size such as `1048576`, `512K`, `100MB`, `2G` or `1TiB` (binary multiples).
+pub fn parse_size(value: &str) -> Result<u64, String> {
+    let value = value.trim();
+    let split = value
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(value.len());
+    let (number, unit) = value.split_at(split);
+    let number: u64 = number
+        .parse()
+        .map_err(|_| format!("invalid size `{value}`"))?;
+    let shift = match unit.trim().to_ascii_uppercase().as_str() {
+        "" | "B" => 0,
+        "K" | "KB" | "KIB" => 10,
+        "M" | "MB" | "MIB" => 20,
+        "G" | "GB" | "GIB" => 30,
+        "T" | "TB" | "TIB" => 40,
+        _ => return Err(format!("invalid size unit in `{value}`")),
+    };
+    number
+        .checked_mul(1 << shift)
+        .ok_or_else(|| format!("size `{value}` is too large"))
+}
+
+/// Parses an age such as `3600`, `90s`, `30m`, `12h`, `30d` or `2w`.
+pub fn parse_age(value: &str) -> Result<Duration, String> {
+    let value = value.trim();
+    let split = value
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(value.len());
+    let (number, unit) = value.split_at(split);
+    let number: u64 = number
+        .parse()
+        .map_err(|_| format!("invalid age `{value}`"))?;
+    let seconds = match unit.trim() {
+        "" | "s" => 1,
+        "m" => 60,
+        "h" => 60 * 60,
+        "d" => 24 * 60 * 60,
+        "w" => 7 * 24 * 60 * 60,
+        _ => return Err(format!("invalid age unit in `{value}`")),
+    };
+    number
+        .checked_mul(seconds)
+        .map(Duration::from_secs)
+        .ok_or_else(|| format!("age `{value}` is too large"))
+}
+
+/// Records an access to the entry at `dir` for LRU ordering. Best effort.
+pub(crate) fn touch(dir: &Path) {
+    let _ = File::options()
+        .create(true)
+        .truncate(false)
+        .write(true)
+        .open(dir.join(LAST_ACCESS_FILENAME))
+        .and_then(|file| file.set_modified(SystemTime::now()));
+}
+
+#[cfg(feature = "dist-client")]
+pub(crate) fn set_pinned(dir: &Path, pinned: bool) -> io::Result<()> {
+    let marker = dir.join(PINNED_FILENAME);
+    if pinned {
+        File::create(marker).map(|_| ())
+    } else {
+        match fs::remove_file(marker) {
+            Err(err) if err.kind() != io::ErrorKind::NotFound => Err(err),
+            _ => Ok(()),
+        }
+    }
+}
+
+pub(crate) fn is_pinned(dir: &Path) -> bool {
+    dir.join(PINNED_FILENAME).exists()
+}
+
+struct Entry {
+    digest: String,
+    dir: PathBuf,
+    size: u64,
+    last_access: SystemTime,
+    pinned: bool,
+}
+
+/// Applies `policy` to the entries under `root`, never removing the entry for `keep`.
+pub(crate) fn prune(
+    root: &Path,
+    policy: &CachePolicy,
+    keep: Option<&str>,
+) -> io::Result<PruneReport> {
+    let keep = keep.map(|digest| digest.strip_prefix("sha256:").unwrap_or(digest));
+    let mut entries = Vec::new();
+    let read_dir = match fs::read_dir(root) {
+        Ok(read_dir) => read_dir,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(PruneReport::default()),
+        Err(err) => return Err(err),
+    };
+    for entry in read_dir {
+        let entry = entry?;
+        if !entry.file_type()?.is_dir() {
+            continue;
+        }
+        let Some(name) = entry.file_name().to_str().map(str::to_string) else {
+            continue;
+        };
+        let dir = entry.path();
+        let last_access = fs::metadata(dir.join(LAST_ACCESS_FILENAME))
+            .or_else(|_| entry.metadata())
+            .and_then(|meta| meta.modified())
+            .unwrap_or(SystemTime::UNIX_EPOCH);
+        entries.push(Entry {
+            pinned: is_pinned(&dir) || keep == Some(name.as_str()),
+            digest: format!("sha256:{name}"),
+            size: dir_size(&dir),
+            dir,
+            last_access,
+        });
+    }
+    entries.sort_by_key(|entry| entry.last_access);
+
+    let mut report = PruneReport {
+        remaining_bytes: entries.iter().map(|entry| entry.size).sum(),
+        ..PruneReport::default()
+    };
+    let now = SystemTime::now();
+    for entry in entries.iter().filter(|entry| !entry.pinned) {
+        let expired = policy.max_age.is_some_and(|max_age| {
+            now.duration_since(entry.last_access)
+                .is_ok_and(|age| age > max_age)
+        });
+        let oversized = policy
+            .max_size_bytes
+            .is_some_and(|max| report.remaining_bytes > max);
+        if !(expired || oversized) || !remove_unlocked(&entry.dir)? {
+            continue;
+        }
+        report.removed.push(entry.digest.clone());
+        report.freed_bytes += entry.size;
+        report.remaining_bytes -= entry.size;
+    }
+    Ok(report)
+}
+
+/// Removes `dir` unless a fetch currently holds its lock; returns whether it was removed.
+///
+/// The lock is held until the entry is gone, so a fetch that takes it meanwhile never
+/// commits into a directory that is about to be deleted.
+pub(crate) fn remove_unlocked(dir: &Path) -> io::Result<bool> {
+    let path = dir.join(LOCK_FILENAME);
+    let lock = match File::options()
+        .create(true)
+        .truncate(false)
+        .write(true)
+        .open(&path)
+    {
+        Ok(file) => file,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(false),
+        Err(err) => return Err(err),
+    };
+    // A lock file another eviction already unlinked no longer guards the entry.
+    if lock.try_lock().is_err() || !is_same_file(&lock, &path) {
+        return Ok(false);
+    }
+    let removed = remove_locked_entry(dir);
+    drop(lock);
+    // The tombstone, if any, is only deletable once the lock file is closed.
+    let removed = removed.and_then(|tombstone| match tombstone {
+        Some(tombstone) => fs::remove_dir_all(tombstone),
+        None => Ok(()),
+    });
+    match removed {
+        Ok(()) => Ok(true),
+        Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(false),
+        Err(err) => Err(err),
+    }
+}
+
+/// Deletes the entry's contents and then the lock file, which an open lock does not
+/// prevent on Unix.
+#[cfg(unix)]
+fn remove_locked_entry(dir: &Path) -> io::Result<Option<PathBuf>> {
+    for entry in fs::read_dir(dir)? {
+        let entry = entry?;
+        if entry.file_name() == LOCK_FILENAME {
+            continue;
+        }
+        if entry.file_type()?.is_dir() {
+            fs::remove_dir_all(entry.path())?;
+        } else {
+            fs::remove_file(entry.path())?;
+        }
+    }
+    match fs::remove_file(dir.join(LOCK_FILENAME)) {
+        Err(err) if err.kind() != io::ErrorKind::NotFound => return Err(err),
+        _ => {}
+    }
+    match fs::remove_dir(dir) {
+        // A fetch recreated the entry after the lock file went; it now owns the directory.
+        Err(err) if err.kind() != io::ErrorKind::DirectoryNotEmpty => Err(err),
+        _ => Ok(None),
+    }
+}
+
+/// Moves the entry aside to a tombstone while the lock is held; an open lock file cannot
+/// be deleted in place here, so the caller deletes the tombstone after unlocking.
+#[cfg(not(unix))]
+fn remove_locked_entry(dir: &Path) -> io::Result<Option<PathBuf>> {
+    let name = dir
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    let tombstone = dir.with_file_name(format!(".{name}.{}.removed", std::process::id()));
+    fs::rename(dir, &tombstone)?;
+    Ok(Some(tombstone))
+}
+
+fn dir_size(dir: &Path) -> u64 {
+    let Ok(read_dir) = fs::read_dir(dir) else {
+        return 0;
+    };
+    read_dir
+        .flatten()
+        .filter_map(|entry| {
+            let meta = entry.path().symlink_metadata().ok()?;
+            Some(if meta.is_dir() {
+                dir_size(&entry.path())
+            } else if meta.is_file() {
+                meta.len()
+            } else {
+                0
+            })
+        })
+        .sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(root: &Path, name: &str, size: usize, accessed_secs_ago: u64) {
+        let dir = root.join(name);
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("component.wasm"), vec![0u8; size]).unwrap();
+        touch(&dir);
+        File::options()
+            .write(true)
+            .open(dir.join(LAST_ACCESS_FILENAME))
+            .unwrap()
+            .set_modified(SystemTime::now() - Duration::from_secs(accessed_secs_ago))
+            .unwrap();
+    }
+
+    #[test]
+    fn parses_sizes_and_ages() {
+        assert_eq!(parse_size("1024"), Ok(1024));
+        assert_eq!(parse_size("512K"), Ok(512 * 1024));
+        assert_eq!(parse_size("2GB"), Ok(2 << 30));
+        assert!(parse_size("12X").is_err());
+        assert_eq!(parse_age("90"), Ok(Duration::from_secs(90)));
+        assert_eq!(parse_age("2d"), Ok(Duration::from_secs(2 * 86_400)));
+        assert!(parse_age("d").is_err());
+    }
+
+    #[test]
+    fn evicts_least_recently_used_unpinned_entries() {
+        let temp = tempfile::tempdir().unwrap();
+        let root = temp.path();
+        entry(root, "aa", 100, 400);
+        entry(root, "bb", 100, 300);
+        entry(root, "cc", 100, 200);
+        entry(root, "dd", 100, 100);
+        File::create(root.join("aa").join(PINNED_FILENAME)).unwrap();
+
+        let policy = CachePolicy {
+            max_size_bytes: Some(250),
+            max_age: None,
+        };
+        let report = prune(root, &policy, None).unwrap();
+        assert_eq!(report.removed, vec!["sha256:bb", "sha256:cc"]);
+        assert_eq!(report.freed_bytes, 200);
+        assert_eq!(report.remaining_bytes, 200);
+        assert!(root.join("aa").exists());
+        assert!(root.join("dd").exists());
+
+        let report = prune(
+            root,
+            &CachePolicy {
+                max_size_bytes: None,
+                max_age: Some(Duration::from_secs(50)),
+            },
+            Some("sha256:dd"),
+        )
+        .unwrap();
+        assert!(report.removed.is_empty());
+        assert!(root.join("dd").exists());
+    }
+
+    #[test]
+    fn skips_entries_locked_by_a_fetch() {
+        let temp = tempfile::tempdir().unwrap();
+        entry(temp.path(), "aa", 100, 100);
+        let _lock = crate::cache_fs::CacheLock::acquire(&temp.path().join("aa")).unwrap();
+
+        let policy = CachePolicy {
+            max_size_bytes: Some(0),
+            max_age: None,
+        };
+        let report = prune(temp.path(), &policy, None).unwrap();
+        assert!(report.removed.is_empty());
+        assert!(temp.path().join("aa").exists());
+    }
+
+    #[test]
+    fn never_deletes_an_entry_under_a_fetch_that_holds_its_lock() {
+        use std::sync::atomic::{AtomicBool, Ordering};
+
+        let temp = tempfile::tempdir().unwrap();
+        let dir = temp.path().join("aa");
+        let policy = CachePolicy {
+            max_size_bytes: Some(0),
+            max_age: None,
+        };
+        struct Done<'a>(&'a AtomicBool);
+        impl Drop for Done<'_> {
+            fn drop(&mut self) {
+                self.0.store(true, Ordering::Relaxed);
+            }
+        }
+
+        let done = AtomicBool::new(false);
+        std::thread::scope(|scope| {
+            scope.spawn(|| {
+                while !done.load(Ordering::Relaxed) {
+                    prune(temp.path(), &policy, None).unwrap();
+                }
+            });
+            // Stops the pruner even if an assertion below fails.
+            let _done = Done(&done);
+            for _ in 0..500 {
+                let lock = crate::cache_fs::CacheLock::acquire(&dir).unwrap();
+                fs::write(dir.join("component.wasm"), b"wasm").unwrap();
+                std::thread::yield_now();
+                assert_eq!(fs::read(dir.join("component.wasm")).unwrap(), b"wasm");
+                drop(lock);
+                // Give the pruner a chance to take the lock between fetches.
+                std::thread::yield_now();
+            }
+        });
+    }
+}
@@ -0,0 +1,330 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::registry_auth::{is_registry_key, normalize_registry};
+
+/// Environment variable overriding the credentials file location.
+pub const CREDENTIALS_ENV: &str = "GREENTIC_DIST_CREDENTIALS";
+
+/// Default credentials file: `GREENTIC_DIST_CREDENTIALS`, else
+/// `$GREENTIC_HOME/credentials.json`, else `<config dir>/greentic/credentials.json`.
+pub fn default_credentials_path() -> PathBuf {
+    if let Some(path) = std::env::var_os(CREDENTIALS_ENV).filter(|v| !v.is_empty()) {
+        return PathBuf::from(path);
+    }
+    if let Ok(root) = std::env::var("GREENTIC_HOME") {
+        return PathBuf::from(root).join("credentials.json");
+    }
+    if let Some(config) = dirs_next::config_dir() {
+        return config.join("greentic").join("credentials.json");
+    }
+    PathBuf::from(".greentic").join("credentials.json")
+}
+
+/// A secret saved by `greentic-dist auth login`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum StoredCredential {
+    Basic { username: String, password: String },
+    Token(String),
+}
+
+/// Credentials saved per target in a Docker-config compatible JSON file.
+///
+/// Registry logins are keyed by host (`ghcr.io`) under `auths`, exactly as `docker login`
+/// writes them (`auth` for username/password, `identitytoken` for tokens), so the file can
+/// also be used as `DOCKER_CONFIG`. Greentic store/repo logins use `store://` or `repo://`
+/// targets, optionally followed by a prefix (`store://acme`); a reference uses the longest
+/// target it starts with. The file is written with `0600` permissions on Unix.
+#[derive(Clone, Debug)]
+pub struct CredentialStore {
+    path: PathBuf,
+    file: CredentialsFile,
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+struct CredentialsFile {
+    #[serde(default)]
+    auths: BTreeMap<String, AuthEntry>,
+    #[serde(flatten)]
+    other: serde_json::Map<String, serde_json::Value>,
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+struct AuthEntry {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    auth: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    identitytoken: Option<String>,
+    #[serde(flatten)]
+    other: serde_json::Map<String, serde_json::Value>,
+}
+
+impl AuthEntry {
+    fn credential(&self) -> Option<StoredCredential> {
+        if let Some(token) = &self.identitytoken {
+            return Some(StoredCredential::Token(token.clone()));
+        }
+        let decoded = STANDARD.decode(self.auth.as_ref()?.trim()).ok()?;
+        let decoded = String::from_utf8(decoded).ok()?;
+        let (username, password) = decoded.split_once(':')?;
+        Some(StoredCredential::Basic {
+            username: username.to_string(),
+            password: password.to_string(),
+        })
+    }
+}
+
+impl CredentialStore {
+    /// Opens the store at `path`; a missing file is an empty store.
+    pub fn open(path: impl Into<PathBuf>) -> Result<Self, CredentialsError> {
+        let path = path.into();
+        let file = match fs::read(&path) {
+            Ok(bytes) => {
+                serde_json::from_slice(&bytes).map_err(|source| CredentialsError::Parse {
+                    path: path.clone(),
+                    source,
+                })?
+            }
+            Err(err) if err.kind() == ErrorKind::NotFound => CredentialsFile::default(),
+            Err(source) => return Err(CredentialsError::Io { path, source }),
+        };
+        Ok(Self { path, file })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn get(&self, target: &str) -> Option<StoredCredential> {
+        if !is_registry_key(target) || is_service_target(target) {
+            return self.file.auths.get(target)?.credential();
+        }
+        let registry = normalize_registry(target);
+        self.file
+            .auths
+            .iter()
+            .find(|(key, _)| is_registry_key(key) && normalize_registry(key) == registry)?
+            .1
+            .credential()
+    }
+
+    /// Token for a `store://`/`repo://` reference, from the longest matching target.
+    ///
+    /// A target only covers whole path segments: `store://acme` matches `store://acme/echo`
+    /// and `store://acme@1.0`, never `store://acme-evil/echo`.
+    pub fn token_for_reference(&self, reference: &str) -> Option<String> {
+        self.file
+            .auths
+            .iter()
+            .filter(|(target, _)| is_service_target(target) && target_covers(target, reference))
+            .max_by_key(|(target, _)| target.len())
+            .and_then(|(_, entry)| match entry.credential()? {
+                StoredCredential::Token(token) => Some(token),
+                StoredCredential::Basic { password, .. } => Some(password),
+            })
+    }
+
+    /// Stored targets with their credentials, sorted by target.
+    pub fn entries(&self) -> Vec<(String, StoredCredential)> {
+        self.file
+            .auths
+            .iter()
+            .filter_map(|(target, entry)| Some((target.clone(), entry.credential()?)))
+            .collect()
+    }
+
+    /// Saves `credential` for `target`.
+    ///
+    /// Registry targets are stored under their host (`https://ghcr.io/` becomes `ghcr.io`),
+    /// replacing logins saved under any other spelling of it, so lookups never see a stale
+    /// duplicate.
+    pub fn set(&mut self, target: &str, credential: StoredCredential) {
+        let key = if is_registry_key(target) && !is_service_target(target) {
+            let registry = normalize_registry(target);
+            self.file.auths.retain(|key, _| {
+                key == &registry || !(is_registry_key(key) && normalize_registry(key) == registry)
+            });
+            registry
+        } else {
+            target.to_string()
+        };
+        let entry = self.file.auths.entry(key).or_default();
+        match credential {
+            StoredCredential::Basic { username, password } => {
+                entry.auth = Some(STANDARD.encode(format!("{username}:{password}")));
+                entry.identitytoken = None;
+            }
+            StoredCredential::Token(token) => {
+                entry.auth = None;
+                entry.identitytoken = Some(token);
+            }
+        }
+    }
+
+    /// Removes `target`, returning whether it was present.
+    ///
+    /// A registry target also removes every key naming the same host, as registry lookups
+    /// do: `https://ghcr.io` removes a login saved as `ghcr.io` or `https://ghcr.io/`.
+    pub fn remove(&mut self, target: &str) -> bool {
+        if is_service_target(target) {
+            return self.file.auths.remove(target).is_some();
+        }
+        let registry = normalize_registry(target);
+        let before = self.file.auths.len();
+        self.file
+            .auths
+            .retain(|key, _| !(is_registry_key(key) && normalize_registry(key) == registry));
+        self.file.auths.len() != before
+    }
+
+    /// Writes the store atomically, readable only by the current user.
+    pub fn save(&self) -> Result<(), CredentialsError> {
+        let io_err = |source| CredentialsError::Io {
+            path: self.path.clone(),
+            source,
+        };
+        if let Some(parent) = self.path.parent().filter(|p| !p.as_os_str().is_empty()) {
+            fs::create_dir_all(parent).map_err(io_err)?;
+        }
+        let bytes =
+            serde_json::to_vec_pretty(&self.file).map_err(|source| CredentialsError::Parse {
+                path: self.path.clone(),
+                source,
+            })?;
+        crate::cache_fs::write_private(&self.path, &bytes).map_err(io_err)
+    }
+}
+
+/// Whether `target` names a Greentic store/repo rather than an OCI registry host.
+pub(crate) fn is_service_target(target: &str) -> bool {
+    target.starts_with("store://") || target.starts_with("repo://")
+}
+
+fn target_covers(target: &str, reference: &str) -> bool {
+    let Some(rest) = reference.strip_prefix(target) else {
+        return false;
+    };
+    target.ends_with('/') || rest.is_empty() || rest.starts_with(['/', '@'])
+}
+
+#[derive(Debug, Error)]
+pub enum CredentialsError {
+    #[error("credentials file {}: {source}", path.display())]
+    Io {
+        path: PathBuf,
+        #[source]
+        source: std::io::Error,
+    },
+    #[error("invalid credentials file {}: {source}", path.display())]
+    Parse {
+        path: PathBuf,
+        #[source]
+        source: serde_json::Error,
+    },
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn concurrent_saves_stage_privately_and_leave_no_temp_files() {
+        let temp = tempfile::tempdir().unwrap();
+        let path = temp.path().join("credentials.json");
+        std::thread::scope(|scope| {
+            for n in 0..8 {
+                let path = &path;
+                scope.spawn(move || {
+                    let mut store = CredentialStore::open(path.clone()).unwrap();
+                    store.set("ghcr.io", StoredCredential::Token(format!("token-{n}")));
+                    for _ in 0..8 {
+                        store.save().unwrap();
+                    }
+                });
+            }
+        });
+        let names: Vec<_> = fs::read_dir(temp.path())
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name())
+            .collect();
+        assert_eq!(names, vec![std::ffi::OsString::from("credentials.json")]);
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = fs::metadata(&path).unwrap().permissions().mode();
+            assert_eq!(mode & 0o777, 0o600);
+        }
+    }
+
+    #[test]
+    fn service_tokens_match_whole_segments_only() {
+        let temp = tempfile::tempdir().unwrap();
+        let mut store = CredentialStore::open(temp.path().join("credentials.json")).unwrap();
+        store.set("store://acme", StoredCredential::Token("acme".into()));
+        assert_eq!(store.token_for_reference("store://acme-evil/echo"), None);
+
+        store.set("store://", StoredCredential::Token("any".into()));
+        let token = |reference| store.token_for_reference(reference);
+        assert_eq!(token("store://acme/echo").as_deref(), Some("acme"));
+        assert_eq!(token("store://acme@1.0.0").as_deref(), Some("acme"));
+        assert_eq!(token("store://acme").as_deref(), Some("acme"));
+        assert_eq!(token("store://acme-evil/echo").as_deref(), Some("any"));
+        assert_eq!(token("repo://acme/echo"), None);
+    }
+
+    #[test]
+    fn removing_a_registry_matches_any_spelling_of_its_host() {
+        let temp = tempfile::tempdir().unwrap();
+        let mut store = CredentialStore::open(temp.path().join("credentials.json")).unwrap();
+        let token = |value: &str| StoredCredential::Token(value.into());
+        store.set("https://ghcr.io/", token("a"));
+        store.set("ghcr.io", token("b"));
+        store.set("quay.io", token("c"));
+        store.set("store://ghcr.io", token("d"));
+
+        assert!(store.remove("https://ghcr.io"));
+        assert!(!store.remove("GHCR.io"));
+        let targets: Vec<_> = store.entries().into_iter().map(|(t, _)| t).collect();
+        assert_eq!(targets, vec!["quay.io", "store://ghcr.io"]);
+    }
+
+    #[test]
+    fn setting_a_registry_replaces_other_spellings_of_its_host() {
+        let temp = tempfile::tempdir().unwrap();
+        let path = temp.path().join("credentials.json");
+        // A file written by an older release, keyed as the user typed the target.
+        fs::write(
+            &path,
+            r#"{"auths": {"https://ghcr.io/": {"identitytoken": "old"}}}"#,
+        )
+        .unwrap();
+        let mut store = CredentialStore::open(path).unwrap();
+        store.set("ghcr.io", StoredCredential::Token("new".into()));
+        store.set("https://Quay.io", StoredCredential::Token("quay".into()));
+
+        let targets: Vec<_> = store.entries().into_iter().map(|(t, _)| t).collect();
+        assert_eq!(targets, vec!["ghcr.io", "quay.io"]);
+        assert_eq!(
+            store.get("https://ghcr.io"),
+            Some(StoredCredential::Token("new".into()))
+        );
+        let auth = crate::RegistryAuthOptions {
+            credentials_store: Some(store.path().to_path_buf()),
+            use_env: false,
+            use_docker_config: false,
+            ..Default::default()
+        };
+        store.save().unwrap();
+        assert_eq!(
+            auth.resolve("ghcr.io"),
+            crate::RegistryCredentials::IdentityToken("new".into())
+        );
+    }
+}
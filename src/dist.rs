@@ -1,19 +1,66 @@
+use crate::cache_fs::write_atomic;
+use crate::cache_policy::{self, CachePolicy, PruneReport};
+use crate::credentials::{CredentialStore, CredentialsError, default_credentials_path};
 use crate::oci_components::{
-    ComponentResolveOptions, DefaultRegistryClient, OciComponentResolver, default_cache_root,
+    ComponentResolveOptions, DefaultRegistryClient, OciComponentError, OciComponentResolver,
 };
+use crate::overrides::{ComponentOverrides, OVERRIDES_ENV, OverridesError, STRICT_ENV};
+use crate::registry_auth::RegistryAuthOptions;
+use crate::store::{
+    ArtifactKind, BlobStore, StoreMetadata, compute_digest, default_store_root, is_sha256_digest,
+    normalize_digest,
+};
+use futures_util::StreamExt;
 use oci_distribution::Reference;
-use reqwest::Url;
-use sha2::{Digest, Sha256};
+use reqwest::{StatusCode, Url};
+use semver::VersionReq;
 use std::fs;
 use std::path::{Path, PathBuf};
 use thiserror::Error;
 
+/// Environment variable with the store API base URL (`DistOptions::store_url`).
+pub const STORE_URL_ENV: &str = "GREENTIC_DIST_STORE_URL";
+
+/// Environment variable with the repo API base URL (`DistOptions::repo_url`).
+pub const REPO_URL_ENV: &str = "GREENTIC_DIST_REPO_URL";
+
+/// Environment variable (`1`) setting `DistOptions::require_integrity`.
+pub const REQUIRE_INTEGRITY_ENV: &str = "GREENTIC_DIST_REQUIRE_INTEGRITY";
+
+/// `schema_version` written by [`DistClient::lock`]; newer lockfiles are refused.
+pub const LOCKFILE_SCHEMA_VERSION: u64 = 1;
+
+/// Default `DistOptions::pull_concurrency`.
+pub const DEFAULT_PULL_CONCURRENCY: usize = 8;
+
+/// Media type recorded for components fetched over HTTP(S) or ingested from files.
+const WASM_MEDIA_TYPE: &str = "application/wasm";
+
 #[derive(Clone, Debug)]
 pub struct DistOptions {
     pub cache_dir: PathBuf,
     pub allow_tags: bool,
     pub offline: bool,
     pub allow_insecure_local_http: bool,
+    /// Overrides file mapping references or component ids to local builds.
+    pub overrides_file: Option<PathBuf>,
+    /// Production mode: fail instead of applying an override.
+    pub strict: bool,
+    /// Credentials saved by `greentic-dist auth login`; `None` disables stored logins.
+    pub credentials_file: Option<PathBuf>,
+    /// Store API base URL resolving `store://` references.
+    pub store_url: Option<String>,
+    /// Repo API base URL resolving `repo://` references.
+    pub repo_url: Option<String>,
+    /// Size/age bounds enforced on `cache_dir` after each write.
+    pub cache_policy: CachePolicy,
+    /// Lockfile pulls refuse entries without a digest and fetch only by that digest.
+    pub frozen: bool,
+    /// Maximum lock entries fetched at once by `pull_lock`.
+    pub pull_concurrency: usize,
+    /// Refuse HTTP(S) fetches with no expected digest (a `#sha256=<hex>` URL fragment, a
+    /// lock entry digest or a store/repo resolution).
+    pub require_integrity: bool,
 }
 
 impl Default for DistOptions {
@@ -21,11 +68,24 @@ impl Default for DistOptions {
         let offline = std::env::var("GREENTIC_DIST_OFFLINE").is_ok_and(|v| v == "1");
         let allow_insecure_local_http =
             std::env::var("GREENTIC_DIST_ALLOW_INSECURE_LOCAL_HTTP").is_ok_and(|v| v == "1");
+        let overrides_file = std::env::var_os(OVERRIDES_ENV)
+            .filter(|v| !v.is_empty())
+            .map(PathBuf::from);
+        let strict = std::env::var(STRICT_ENV).is_ok_and(|v| v == "1");
         Self {
-            cache_dir: default_cache_root(),
+            cache_dir: default_store_root(),
             allow_tags: true,
             offline,
             allow_insecure_local_http,
+            overrides_file,
+            strict,
+            credentials_file: Some(default_credentials_path()),
+            store_url: std::env::var(STORE_URL_ENV).ok().filter(|v| !v.is_empty()),
+            repo_url: std::env::var(REPO_URL_ENV).ok().filter(|v| !v.is_empty()),
+            cache_policy: CachePolicy::from_env(),
+            frozen: false,
+            pull_concurrency: DEFAULT_PULL_CONCURRENCY,
+            require_integrity: std::env::var(REQUIRE_INTEGRITY_ENV).is_ok_and(|v| v == "1"),
         }
     }
 }
@@ -46,8 +106,164 @@ pub enum ArtifactSource {
     Oci(String),
     Repo(String),
     Store(String),
+    /// `reference` was served from the local override at `path`.
+    Overridden {
+        reference: String,
+        path: PathBuf,
+    },
+}
+
+/// Lockfile written by `greentic-dist lock` and read by [`DistClient::pull_lock`].
+///
+/// The format is described by `docs/lockfile.schema.json`.
+#[derive(Clone, Debug, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct Lockfile {
+    pub schema_version: u64,
+    pub components: Vec<LockedComponent>,
 }
 
+/// One pinned component: the reference as requested and what it resolved to.
+///
+/// Entries tracking a semver range also keep the OCI `repository` and `version` constraint
+/// that `reference` was selected from.
+#[derive(Clone, Debug, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct LockedComponent {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub repository: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub version: Option<String>,
+    pub reference: String,
+    pub digest: String,
+    pub media_type: String,
+    pub size_bytes: u64,
+    pub source: LockSource,
+}
+
+/// How a locked reference is fetched.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LockSource {
+    Digest,
+    Http,
+    File,
+    Oci,
+    Repo,
+    Store,
+}
+
+/// A lock entry with a movable reference or a version constraint, as reported by
+/// [`DistClient::outdated`] and [`DistClient::update_lock`].
+#[derive(Clone, Debug, PartialEq, Eq, serde::Serialize)]
+pub struct LockDrift {
+    /// Position of the entry in the lockfile.
+    pub index: usize,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    /// The entry's reference, or `repository@version` for an unpinned constraint.
+    pub reference: String,
+    /// Digest recorded in the lockfile; `None` for a constraint not pinned yet.
+    pub locked: Option<String>,
+    /// Digest the reference (or highest matching tag) resolves to now.
+    pub current: String,
+    /// Tag reference selected by the entry's `version` constraint.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub selected: Option<String>,
+    pub moved: bool,
+}
+
+/// Outcome of [`DistClient::verify_lock`].
+#[derive(Clone, Debug, Default, PartialEq, Eq, serde::Serialize)]
+pub struct LockVerification {
+    /// Locked digests whose cached artifact re-hashed as recorded.
+    pub verified: Vec<String>,
+    /// References locked without a digest.
+    pub unpinned: Vec<String>,
+    /// Locked digests with no cache entry.
+    pub missing: Vec<LockIssue>,
+    /// Cached artifacts whose content no longer matches their digest.
+    pub mismatched: Vec<LockIssue>,
+    /// Cached components that no lock entry refers to.
+    pub extra: Vec<String>,
+}
+
+impl LockVerification {
+    /// Whether every lock entry is pinned and cached intact; `extra` entries are allowed
+    /// because the store is shared between lockfiles.
+    pub fn is_ok(&self) -> bool {
+        self.unpinned.is_empty() && self.missing.is_empty() && self.mismatched.is_empty()
+    }
+}
+
+/// A lock entry that failed verification.
+#[derive(Clone, Debug, PartialEq, Eq, serde::Serialize)]
+pub struct LockIssue {
+    pub reference: String,
+    pub digest: String,
+    /// Digest of the cached bytes, for mismatches.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub actual: Option<String>,
+}
+
+/// Progress of one lock entry during [`DistClient::pull_lock_with_progress`]; `index` is
+/// the entry's position in the lockfile.
+///
+/// `Started` names the entry as written in the lockfile (a version range for unpinned
+/// entries); later events carry the reference it resolved to, unless resolving failed.
+#[derive(Clone, Debug, PartialEq, Eq, serde::Serialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum PullEvent {
+    Started {
+        index: usize,
+        reference: String,
+    },
+    /// Bytes downloaded so far. Only HTTP(S) downloads emit these, including store/repo
+    /// entries the API serves over HTTPS; OCI pulls, cache hits and local files go
+    /// straight from `Started` to `Finished`.
+    Bytes {
+        index: usize,
+        reference: String,
+        received: u64,
+        total: Option<u64>,
+    },
+    Finished {
+        index: usize,
+        reference: String,
+        digest: String,
+        cache_path: Option<PathBuf>,
+        fetched: bool,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        overridden: Option<PathBuf>,
+    },
+    Failed {
+        index: usize,
+        reference: String,
+        error: String,
+    },
+}
+
+impl PullEvent {
+    fn finished(index: usize, reference: &str, resolved: &ResolvedArtifact) -> Self {
+        PullEvent::Finished {
+            index,
+            reference: reference.to_string(),
+            digest: resolved.digest.clone(),
+            cache_path: resolved.cache_path.clone(),
+            fetched: resolved.fetched,
+            overridden: match &resolved.source {
+                ArtifactSource::Overridden { path, .. } => Some(path.clone()),
+                _ => None,
+            },
+        }
+    }
+}
+
+/// Receives `(received, total)` byte counts while an artifact downloads.
+type ByteProgress<'a> = &'a (dyn Fn(u64, Option<u64>) + Send + Sync);
+
+fn no_progress(_received: u64, _total: Option<u64>) {}
+
 pub struct DistClient {
     cache: ComponentCache,
     oci: OciComponentResolver<DefaultRegistryClient>,
@@ -69,6 +285,11 @@ impl DistClient {
             allow_tags: opts.allow_tags,
             offline: opts.offline,
             cache_dir: opts.cache_dir.clone(),
+            auth: RegistryAuthOptions {
+                credentials_store: opts.credentials_file.clone(),
+                ..Default::default()
+            },
+            cache_policy: opts.cache_policy.clone(),
             ..Default::default()
         };
         let http = reqwest::Client::builder()
@@ -76,7 +297,7 @@ impl DistClient {
             .build()
             .expect("failed to build http client");
         Self {
-            cache: ComponentCache::new(opts.cache_dir.clone()),
+            cache: ComponentCache::new(opts.cache_dir.clone(), opts.cache_policy.clone()),
             oci: OciComponentResolver::new(oci_opts),
             http,
             opts,
@@ -84,6 +305,66 @@ impl DistClient {
     }
 
     pub async fn resolve_ref(&self, reference: &str) -> Result<ResolvedArtifact, DistError> {
+        if let Some(resolved) = self.resolve_override(reference)? {
+            return Ok(resolved);
+        }
+        self.resolve_remote(reference, &no_progress).await
+    }
+
+    /// Serves `digest` from the cache, else fetches `reference` pinned to it (OCI refs are
+    /// rewritten to `repository@digest`) and fails unless the fetched digest matches.
+    async fn pull_pinned(
+        &self,
+        reference: &str,
+        digest: &str,
+        progress: ByteProgress<'_>,
+    ) -> Result<ResolvedArtifact, DistError> {
+        let digest = normalize_digest(digest);
+        if let Some(path) = self.cache.existing_component(&digest) {
+            return Ok(ResolvedArtifact {
+                digest,
+                cache_path: Some(path),
+                fetched: false,
+                source: ArtifactSource::Digest,
+            });
+        }
+        let fetched = match classify_reference(reference)? {
+            RefKind::Digest(_) => {
+                return Err(DistError::CacheMiss {
+                    reference: reference.to_string(),
+                });
+            }
+            RefKind::Oci(oci) => {
+                let parsed =
+                    Reference::try_from(oci.as_str()).map_err(|_| DistError::InvalidReference {
+                        reference: reference.to_string(),
+                    })?;
+                let pinned = Reference::with_digest(
+                    parsed.registry().to_string(),
+                    parsed.repository().to_string(),
+                    digest.clone(),
+                );
+                self.pull_oci(&pinned.whole()).await?
+            }
+            RefKind::Http(url) => self.fetch_http(&url, Some(&digest), progress).await?,
+            _ => self.resolve_remote(reference, progress).await?,
+        };
+        if fetched.digest != digest {
+            return Err(DistError::DigestMismatch {
+                reference: reference.to_string(),
+                expected: digest,
+                actual: fetched.digest,
+            });
+        }
+        Ok(fetched)
+    }
+
+    /// Resolves `reference` without consulting the overrides file.
+    async fn resolve_remote(
+        &self,
+        reference: &str,
+        progress: ByteProgress<'_>,
+    ) -> Result<ResolvedArtifact, DistError> {
         match classify_reference(reference)? {
             RefKind::Digest(digest) => Ok(ResolvedArtifact {
                 cache_path: self.cache.existing_component(&digest),
@@ -91,11 +372,17 @@ impl DistClient {
                 fetched: false,
                 source: ArtifactSource::Digest,
             }),
-            RefKind::Http(url) => self.fetch_http(&url).await,
+            RefKind::Http(url) => self.fetch_http(&url, None, progress).await,
             RefKind::File(path) => self.ingest_file(&path).await,
             RefKind::Oci(reference) => self.pull_oci(&reference).await,
-            RefKind::Repo(target) => Err(DistError::AuthRequired { target }),
-            RefKind::Store(target) => Err(DistError::AuthRequired { target }),
+            RefKind::Repo(reference) => {
+                self.resolve_service(&reference, ServiceKind::Repo, progress)
+                    .await
+            }
+            RefKind::Store(reference) => {
+                self.resolve_service(&reference, ServiceKind::Store, progress)
+                    .await
+            }
         }
     }
 
@@ -121,89 +408,726 @@ impl DistClient {
     }
 
     pub async fn pull_lock(&self, lock_path: &Path) -> Result<Vec<ResolvedArtifact>, DistError> {
+        self.pull_lock_with_progress(lock_path, &|_| {}).await
+    }
+
+    /// Pulls every lock entry, up to `DistOptions::pull_concurrency` at a time, reporting
+    /// progress through `on_event`.
+    ///
+    /// Entries sharing a digest (or, when unpinned, a reference or version range) are fetched
+    /// once and reported under the first entry's index. Version ranges are resolved inside
+    /// each entry's concurrent job, so a range that cannot be resolved is reported as `Failed`
+    /// like any other entry. Every entry is attempted; results keep lock order and the first
+    /// failing entry's error is returned.
+    pub async fn pull_lock_with_progress(
+        &self,
+        lock_path: &Path,
+        on_event: &(dyn Fn(PullEvent) + Send + Sync),
+    ) -> Result<Vec<ResolvedArtifact>, DistError> {
         let contents = fs::read_to_string(lock_path)?;
         let entries = parse_lockfile(&contents)?;
-        let mut resolved = Vec::with_capacity(entries.len());
-        for entry in entries {
-            let reference = entry
-                .reference
-                .clone()
-                .ok_or_else(|| DistError::InvalidInput("lock entry missing ref".into()))?;
-            let digest = if let Some(d) = entry.digest.clone() {
-                d
-            } else {
-                if self.opts.offline {
-                    return Err(DistError::Offline {
-                        reference: reference.clone(),
+        let mut slots = Vec::with_capacity(entries.len());
+        let mut jobs: Vec<LockJob> = Vec::new();
+        for (index, entry) in entries.into_iter().enumerate() {
+            let digest = entry.digest.as_deref().map(normalize_digest);
+            let label = entry.label();
+            let job = match jobs
+                .iter()
+                .position(|job| job.key() == digest.as_deref().unwrap_or(&label))
+            {
+                Some(job) => job,
+                None => {
+                    jobs.push(LockJob {
+                        index,
+                        label,
+                        entry,
+                        digest,
                     });
+                    jobs.len() - 1
                 }
-                self.resolve_ref(&reference).await?.digest
             };
+            slots.push(job);
+        }
+
+        let concurrency = self.opts.pull_concurrency.max(1);
+        let mut outcomes: Vec<_> = jobs.iter().map(|_| None).collect();
+        let finished: Vec<_> = futures_util::stream::iter(jobs.into_iter().enumerate())
+            .map(|(position, job)| self.pull_lock_job(position, job, on_event))
+            .buffer_unordered(concurrency)
+            .collect()
+            .await;
+        for (position, outcome) in finished {
+            outcomes[position] = Some(outcome);
+        }
+
+        let mut resolved = Vec::with_capacity(slots.len());
+        for job in slots {
+            match &outcomes[job] {
+                Some(Ok(artifact)) => resolved.push(artifact.clone()),
+                _ => return Err(outcomes[job].take().expect("every job ran").unwrap_err()),
+            }
+        }
+        Ok(resolved)
+    }
+
+    /// Runs one job between its `Started` and `Finished`/`Failed` events, resolving the
+    /// entry's reference (which may query the registry for a version range) inside it.
+    async fn pull_lock_job(
+        &self,
+        position: usize,
+        job: LockJob,
+        on_event: &(dyn Fn(PullEvent) + Send + Sync),
+    ) -> (usize, Result<ResolvedArtifact, DistError>) {
+        let LockJob {
+            index,
+            label,
+            entry,
+            digest,
+        } = job;
+        on_event(PullEvent::Started {
+            index,
+            reference: label.clone(),
+        });
+        let reference = match self.entry_reference(&entry).await {
+            Ok(reference) => reference,
+            Err(err) => {
+                on_event(PullEvent::Failed {
+                    index,
+                    reference: label,
+                    error: err.to_string(),
+                });
+                return (position, Err(err));
+            }
+        };
+        let progress = |received, total| {
+            on_event(PullEvent::Bytes {
+                index,
+                reference: reference.clone(),
+                received,
+                total,
+            })
+        };
+        let outcome = match self.resolve_override(&reference) {
+            Ok(Some(resolved)) => Ok(resolved),
+            Ok(None) => {
+                self.pull_lock_entry(&reference, digest.as_deref(), &progress)
+                    .await
+            }
+            Err(err) => Err(err),
+        };
+        on_event(match &outcome {
+            Ok(resolved) => PullEvent::finished(index, &reference, resolved),
+            Err(err) => PullEvent::Failed {
+                index,
+                reference,
+                error: err.to_string(),
+            },
+        });
+        (position, outcome)
+    }
 
-            let cache_key = entry.digest.clone().unwrap_or_else(|| reference.clone());
-            let resolved_item = if let Ok(item) = self.ensure_cached(&cache_key).await {
-                item
+    /// Fetches one deduplicated lock entry; `progress` sees `(received, total)` byte counts.
+    async fn pull_lock_entry(
+        &self,
+        reference: &str,
+        digest: Option<&str>,
+        progress: ByteProgress<'_>,
+    ) -> Result<ResolvedArtifact, DistError> {
+        // A pinned entry is fetched by its digest, never by re-resolving a tag that may
+        // have moved since the lock was written.
+        let resolved = match digest {
+            Some(digest) => self.pull_pinned(reference, digest, progress).await?,
+            None if self.opts.frozen => {
+                return Err(DistError::NotPinned {
+                    reference: reference.to_string(),
+                });
+            }
+            None if self.opts.offline => {
+                return Err(DistError::Offline {
+                    reference: reference.to_string(),
+                });
+            }
+            None => self.resolve_remote(reference, progress).await?,
+        };
+        if !resolved
+            .cache_path
+            .as_ref()
+            .is_some_and(|path| path.exists())
+        {
+            return Err(DistError::CacheMiss {
+                reference: reference.to_string(),
+            });
+        }
+        Ok(resolved)
+    }
+
+    /// Checks the cache against a lockfile without touching the network: every entry must
+    /// carry a digest whose cached artifact still hashes as recorded.
+    pub fn verify_lock(&self, lock_path: &Path) -> Result<LockVerification, DistError> {
+        let contents = fs::read_to_string(lock_path)?;
+        let mut report = LockVerification::default();
+        let mut locked = std::collections::BTreeSet::new();
+        for entry in parse_lockfile(&contents)? {
+            let reference = entry.label();
+            let Some(digest) = entry.digest.as_deref().map(normalize_digest) else {
+                report.unpinned.push(reference);
+                continue;
+            };
+            locked.insert(digest.clone());
+            let path = self.cache.component_path(&digest)?;
+            let bytes = match fs::read(&path) {
+                Ok(bytes) => bytes,
+                Err(err) if err.kind() == std::io::ErrorKind::NotFound => {
+                    report.missing.push(LockIssue {
+                        reference,
+                        digest,
+                        actual: None,
+                    });
+                    continue;
+                }
+                Err(err) => return Err(err.into()),
+            };
+            let metadata = self.cache.store.metadata(&digest);
+            // Entries keyed by an OCI manifest digest record the artifact's own digest;
+            // ones cached before that was recorded can only be checked for presence.
+            let expected = match &metadata {
+                Some(metadata) if metadata.content_digest.is_some() => {
+                    metadata.content_digest.clone()
+                }
+                Some(metadata) if metadata.manifest_digest.is_some() => None,
+                _ => Some(digest.clone()),
+            };
+            let actual = compute_digest(&bytes);
+            if expected.is_some_and(|expected| expected != actual) {
+                report.mismatched.push(LockIssue {
+                    reference,
+                    digest,
+                    actual: Some(actual),
+                });
             } else {
-                self.ensure_cached(&reference).await?
+                report.verified.push(digest);
+            }
+        }
+        report.extra = self
+            .cache
+            .store
+            .list()
+            .into_iter()
+            .filter(|digest| !locked.contains(digest) && self.cache.is_component(digest))
+            .collect();
+        Ok(report)
+    }
+
+    /// Resolves and caches every reference, pinning tags to the digests they point at now.
+    ///
+    /// Local overrides are ignored: a lockfile records what the references resolve to
+    /// remotely, and `pull_lock` still applies overrides when the lock is consumed.
+    pub async fn lock(&self, references: &[String]) -> Result<Lockfile, DistError> {
+        let mut components = Vec::with_capacity(references.len());
+        for reference in references {
+            components.push(self.lock_component(reference).await?);
+        }
+        Ok(Lockfile {
+            schema_version: LOCKFILE_SCHEMA_VERSION,
+            components,
+        })
+    }
+
+    /// Re-resolves every digest-pinned lock entry whose reference can move (OCI tags and
+    /// `store://`/`repo://` versions), and every version-constrained entry, without
+    /// downloading artifacts.
+    pub async fn outdated(&self, lock_path: &Path) -> Result<Vec<LockDrift>, DistError> {
+        let contents = fs::read_to_string(lock_path)?;
+        let mut drift = Vec::new();
+        for (index, entry) in parse_lockfile(&contents)?.into_iter().enumerate() {
+            if let Some(entry_drift) = self.entry_drift(index, &entry).await? {
+                drift.push(entry_drift);
+            }
+        }
+        Ok(drift)
+    }
+
+    /// Pulls the moved entries reported by [`Self::outdated`] and rewrites the lockfile with
+    /// their new digest (and `media_type`/`size_bytes` when the entry records them).
+    /// Version-constrained entries also get the selected tag as `reference` plus the full
+    /// pinned record. Only entries whose `name`, reference or `repository` equals `selector`
+    /// are considered when it is set; everything else, including entry order and unknown
+    /// fields, is left untouched.
+    pub async fn update_lock(
+        &self,
+        lock_path: &Path,
+        selector: Option<&str>,
+    ) -> Result<Vec<LockDrift>, DistError> {
+        let contents = fs::read_to_string(lock_path)?;
+        let entries = parse_lockfile(&contents)?;
+        let mut document: serde_json::Value = serde_json::from_str(&contents)?;
+        let items = match &mut document {
+            serde_json::Value::Array(items) => items,
+            document => document
+                .get_mut("components")
+                .and_then(serde_json::Value::as_array_mut)
+                .ok_or_else(|| DistError::InvalidInput("lockfile has no components".into()))?,
+        };
+        let selected = |entry: &LockResolvedEntry| {
+            selector.is_none_or(|selector| {
+                entry.name.as_deref() == Some(selector)
+                    || entry.reference.as_deref() == Some(selector)
+                    || entry
+                        .constraint
+                        .as_ref()
+                        .is_some_and(|constraint| constraint.repository == selector)
+            })
+        };
+        if let Some(selector) = selector
+            && !entries.iter().any(selected)
+        {
+            return Err(DistError::InvalidInput(format!(
+                "no lock entry named `{selector}`"
+            )));
+        }
+
+        let mut updated = Vec::new();
+        for (index, entry) in entries.into_iter().enumerate() {
+            if !selected(&entry) {
+                continue;
+            }
+            let Some(mut drift) = self.entry_drift(index, &entry).await? else {
+                continue;
             };
-            resolved.push(ResolvedArtifact {
-                digest,
-                ..resolved_item
+            if !drift.moved {
+                continue;
+            }
+            let reference = drift.selected.as_deref().unwrap_or(&drift.reference);
+            let component = self.lock_component(reference).await?;
+            let serde_json::Value::Object(item) = &mut items[index] else {
+                continue;
+            };
+            let constrained = drift.selected.is_some();
+            item.insert("digest".into(), component.digest.clone().into());
+            if constrained {
+                item.insert("reference".into(), component.reference.into());
+                item.remove("ref");
+            }
+            if constrained || item.contains_key("media_type") {
+                item.insert("media_type".into(), component.media_type.into());
+            }
+            if constrained || item.contains_key("size_bytes") {
+                item.insert("size_bytes".into(), component.size_bytes.into());
+            }
+            if constrained {
+                item.insert("source".into(), serde_json::to_value(component.source)?);
+            }
+            drift.current = component.digest;
+            updated.push(drift);
+        }
+        if !updated.is_empty() {
+            let json = serde_json::to_string_pretty(&document)?;
+            write_atomic(lock_path, format!("{json}\n").as_bytes())?;
+        }
+        Ok(updated)
+    }
+
+    /// Drift of one entry; `None` when the entry cannot move (no digest, or a reference
+    /// pinned by nature).
+    async fn entry_drift(
+        &self,
+        index: usize,
+        entry: &LockResolvedEntry,
+    ) -> Result<Option<LockDrift>, DistError> {
+        let locked = entry.digest.as_deref().map(normalize_digest);
+        let (reference, selected, current) = match &entry.constraint {
+            Some(constraint) => {
+                let selected = self.resolve_constraint(constraint).await?;
+                let current = self.oci.resolve_digest(&selected).await?;
+                (entry.label(), Some(selected), current)
+            }
+            None => {
+                let (Some(reference), Some(_)) = (&entry.reference, &locked) else {
+                    return Ok(None);
+                };
+                let Some(current) = self.current_digest(reference).await? else {
+                    return Ok(None);
+                };
+                (reference.clone(), None, current)
+            }
+        };
+        Ok(Some(LockDrift {
+            index,
+            name: entry.name.clone(),
+            reference,
+            moved: locked.as_deref() != Some(current.as_str()),
+            locked,
+            current,
+            selected,
+        }))
+    }
+
+    /// Reference a lock entry is fetched by: its own, else `repository@digest` when
+    /// pinned, else the highest tag matching its version constraint.
+    async fn entry_reference(&self, entry: &LockResolvedEntry) -> Result<String, DistError> {
+        if let Some(reference) = &entry.reference {
+            return Ok(reference.clone());
+        }
+        let Some(constraint) = &entry.constraint else {
+            return Err(DistError::InvalidInput("lock entry missing ref".into()));
+        };
+        if let Some(digest) = &entry.digest {
+            return Ok(format!(
+                "{}@{}",
+                constraint.oci_repository(),
+                normalize_digest(digest)
+            ));
+        }
+        if self.opts.frozen {
+            return Err(DistError::NotPinned {
+                reference: constraint.label(),
             });
         }
-        Ok(resolved)
+        self.resolve_constraint(constraint).await
+    }
+
+    /// Highest tag of the constraint's repository matching its `version` range (any
+    /// release when unset).
+    async fn resolve_constraint(
+        &self,
+        constraint: &VersionConstraint,
+    ) -> Result<String, DistError> {
+        let version = constraint.version.as_deref().unwrap_or("*");
+        let requirement = VersionReq::parse(version).map_err(|err| {
+            DistError::InvalidInput(format!(
+                "invalid version `{version}` for `{}`: {err}",
+                constraint.repository
+            ))
+        })?;
+        if self.opts.offline {
+            return Err(DistError::Offline {
+                reference: constraint.label(),
+            });
+        }
+        match self
+            .oci
+            .resolve_version(constraint.oci_repository(), &requirement)
+            .await
+        {
+            Err(OciComponentError::NoMatchingVersion { .. }) => Err(DistError::NotFound {
+                reference: constraint.label(),
+            }),
+            resolved => Ok(resolved?),
+        }
+    }
+
+    /// Digest a movable reference points at now; `None` for references pinned by nature
+    /// (digests, digest-pinned OCI refs, files, HTTP URLs).
+    async fn current_digest(&self, reference: &str) -> Result<Option<String>, DistError> {
+        match classify_reference(reference)? {
+            RefKind::Oci(oci) if !oci.contains('@') => {
+                if self.opts.offline {
+                    return Err(DistError::Offline {
+                        reference: reference.to_string(),
+                    });
+                }
+                Ok(Some(self.oci.resolve_digest(&oci).await?))
+            }
+            RefKind::Repo(reference) => Ok(Some(
+                self.query_service(&reference, ServiceKind::Repo)
+                    .await?
+                    .digest,
+            )),
+            RefKind::Store(reference) => Ok(Some(
+                self.query_service(&reference, ServiceKind::Store)
+                    .await?
+                    .digest,
+            )),
+            _ => Ok(None),
+        }
+    }
+
+    /// Resolves and caches `reference` (ignoring overrides) into a lock entry.
+    async fn lock_component(&self, reference: &str) -> Result<LockedComponent, DistError> {
+        let resolved = self.resolve_remote(reference, &no_progress).await?;
+        let path = resolved
+            .cache_path
+            .as_ref()
+            .filter(|path| path.exists())
+            .ok_or_else(|| DistError::CacheMiss {
+                reference: reference.to_string(),
+            })?;
+        let media_type = self
+            .cache
+            .store
+            .metadata(&resolved.digest)
+            .and_then(|metadata| metadata.media_type)
+            .unwrap_or_else(|| WASM_MEDIA_TYPE.to_string());
+        let source = match resolved.source {
+            ArtifactSource::Digest => LockSource::Digest,
+            ArtifactSource::Http(_) => LockSource::Http,
+            ArtifactSource::File(_) => LockSource::File,
+            ArtifactSource::Oci(_) => LockSource::Oci,
+            ArtifactSource::Repo(_) => LockSource::Repo,
+            ArtifactSource::Store(_) => LockSource::Store,
+            // `resolve_remote` skips overrides; refuse rather than lock a local file.
+            ArtifactSource::Overridden { path, .. } => {
+                return Err(DistError::InvalidInput(format!(
+                    "`{reference}` resolved to local override {}, which cannot be locked",
+                    path.display()
+                )));
+            }
+        };
+        Ok(LockedComponent {
+            name: None,
+            repository: None,
+            version: None,
+            reference: reference.to_string(),
+            size_bytes: fs::metadata(path)?.len(),
+            digest: resolved.digest,
+            media_type,
+            source,
+        })
     }
 
+    /// Digests of every store entry: components, packs and digest blobs.
     pub fn list_cache(&self) -> Vec<String> {
-        self.cache.list_digests()
+        self.cache.store.list()
     }
 
     pub fn remove_cached(&self, digests: &[String]) -> Result<(), DistError> {
         for digest in digests {
-            let dir = self.cache.component_dir(digest);
-            if dir.exists() {
-                fs::remove_dir_all(&dir)?;
-            }
+            self.cache.store.remove(digest)?;
+        }
+        Ok(())
+    }
+
+    /// Evicts entries per `policy`: expired ones first, then least recently used until the
+    /// cache fits. Pinned entries are never removed.
+    pub fn prune(&self, policy: &CachePolicy) -> Result<PruneReport, DistError> {
+        Ok(self.cache.store.prune(policy)?)
+    }
+
+    /// Marks cached digests as pinned (exempt from eviction) or clears the mark.
+    pub fn set_pinned(&self, digests: &[String], pinned: bool) -> Result<(), DistError> {
+        for digest in digests {
+            let dir = self
+                .cache
+                .store
+                .entry_dir(digest)
+                .ok()
+                .filter(|dir| dir.is_dir())
+                .ok_or_else(|| DistError::CacheMiss {
+                    reference: digest.clone(),
+                })?;
+            cache_policy::set_pinned(&dir, pinned)?;
         }
         Ok(())
     }
 
+    /// Removes entries left behind by interrupted writes.
     pub fn gc(&self) -> Result<Vec<String>, DistError> {
-        let mut removed = Vec::new();
-        for digest in self.cache.list_digests() {
-            let path = self.cache.component_path(&digest);
-            if !path.exists() {
-                let dir = self.cache.component_dir(&digest);
-                fs::remove_dir_all(&dir).ok();
-                removed.push(digest);
+        Ok(self.cache.store.gc()?)
+    }
+
+    /// Token saved by `greentic-dist auth login` for a `store://`/`repo://` reference.
+    pub fn stored_token(&self, reference: &str) -> Result<Option<String>, DistError> {
+        let Some(path) = &self.opts.credentials_file else {
+            return Ok(None);
+        };
+        Ok(CredentialStore::open(path.clone())?.token_for_reference(reference))
+    }
+
+    /// Serves `reference` from the overrides file, if one is configured and matches.
+    fn resolve_override(&self, reference: &str) -> Result<Option<ResolvedArtifact>, DistError> {
+        let Some(file) = &self.opts.overrides_file else {
+            return Ok(None);
+        };
+        let overrides = ComponentOverrides::load(file)?;
+        let Some(path) = overrides.lookup(reference) else {
+            return Ok(None);
+        };
+        if self.opts.strict {
+            return Err(DistError::OverrideRefused {
+                reference: reference.to_string(),
+                path: path.to_path_buf(),
+            });
+        }
+        let bytes = fs::read(path)?;
+        Ok(Some(ResolvedArtifact {
+            digest: compute_digest(&bytes),
+            cache_path: Some(path.to_path_buf()),
+            fetched: false,
+            source: ArtifactSource::Overridden {
+                reference: reference.to_string(),
+                path: path.to_path_buf(),
+            },
+        }))
+    }
+
+    /// Translates a `store://`/`repo://` reference into a digest-pinned location through the
+    /// service API, then fetches and caches it like a direct OCI or HTTPS reference.
+    async fn resolve_service(
+        &self,
+        reference: &str,
+        kind: ServiceKind,
+        progress: ByteProgress<'_>,
+    ) -> Result<ResolvedArtifact, DistError> {
+        let resolution = self.query_service(reference, kind).await?;
+        let source = match kind {
+            ServiceKind::Store => ArtifactSource::Store(reference.to_string()),
+            ServiceKind::Repo => ArtifactSource::Repo(reference.to_string()),
+        };
+        if let Some(path) = self.cache.existing_component(&resolution.digest) {
+            return Ok(ResolvedArtifact {
+                digest: resolution.digest,
+                cache_path: Some(path),
+                fetched: false,
+                source,
+            });
+        }
+
+        let fetched = match classify_reference(&resolution.location)? {
+            RefKind::Http(url) => {
+                self.fetch_http(&url, Some(&resolution.digest), progress)
+                    .await?
+            }
+            RefKind::Oci(oci) if oci.contains("@sha256:") => self.pull_oci(&oci).await?,
+            _ => {
+                return Err(DistError::InvalidInput(format!(
+                    "`{reference}` resolved to `{}`, expected a digest-pinned OCI or HTTPS location",
+                    resolution.location
+                )));
             }
+        };
+        if fetched.digest != resolution.digest {
+            return Err(DistError::DigestMismatch {
+                reference: reference.to_string(),
+                expected: resolution.digest,
+                actual: fetched.digest,
+            });
         }
-        Ok(removed)
+        Ok(ResolvedArtifact { source, ..fetched })
     }
 
-    async fn fetch_http(&self, url: &str) -> Result<ResolvedArtifact, DistError> {
+    /// Asks the store/repo API which digest and location `reference` currently maps to.
+    async fn query_service(
+        &self,
+        reference: &str,
+        kind: ServiceKind,
+    ) -> Result<ServiceResolution, DistError> {
+        let (base, env) = match kind {
+            ServiceKind::Store => (&self.opts.store_url, STORE_URL_ENV),
+            ServiceKind::Repo => (&self.opts.repo_url, REPO_URL_ENV),
+        };
+        let base = base.as_deref().ok_or_else(|| {
+            DistError::InvalidInput(format!(
+                "no API endpoint configured for `{reference}`; set {env}"
+            ))
+        })?;
+        let (publisher, component, version) = parse_service_reference(reference)?;
         if self.opts.offline {
             return Err(DistError::Offline {
-                reference: url.to_string(),
+                reference: reference.to_string(),
             });
         }
-        if !(url.starts_with("https://")
-            || (self.opts.allow_insecure_local_http && is_loopback_http(url)))
+        let mut url = Url::parse(base)
+            .ok()
+            .filter(|url| !url.cannot_be_a_base())
+            .ok_or_else(|| DistError::InvalidInput(format!("invalid {env} `{base}`")))?;
+        url.path_segments_mut()
+            .expect("checked above")
+            .pop_if_empty()
+            .extend([
+                "v1",
+                "components",
+                publisher,
+                component,
+                "versions",
+                version,
+            ]);
+        self.check_url(url.as_str())?;
+
+        let mut request = self.http.get(url);
+        if let Some(token) = self.stored_token(reference)? {
+            request = request.bearer_auth(token);
+        }
+        let response = request.send().await?;
+        match response.status() {
+            StatusCode::UNAUTHORIZED | StatusCode::FORBIDDEN => {
+                return Err(DistError::AuthRequired {
+                    target: reference.to_string(),
+                });
+            }
+            StatusCode::NOT_FOUND => {
+                return Err(DistError::NotFound {
+                    reference: reference.to_string(),
+                });
+            }
+            _ => {}
+        }
+        let resolution: ServiceResolution = response.error_for_status()?.json().await?;
+        if !is_sha256_digest(&resolution.digest) {
+            return Err(DistError::InvalidInput(format!(
+                "`{reference}` resolved to invalid digest `{}`",
+                resolution.digest
+            )));
+        }
+        Ok(resolution)
+    }
+
+    fn check_url(&self, url: &str) -> Result<(), DistError> {
+        if url.starts_with("https://")
+            || (self.opts.allow_insecure_local_http && is_loopback_http(url))
         {
-            return Err(DistError::InsecureUrl {
+            Ok(())
+        } else {
+            Err(DistError::InsecureUrl {
+                url: url.to_string(),
+            })
+        }
+    }
+
+    /// Downloads `url` and caches it. The body must hash to `expected` and to the URL's
+    /// `#sha256=<hex>` fragment when either is given; nothing is cached on a mismatch.
+    async fn fetch_http(
+        &self,
+        url: &str,
+        expected: Option<&str>,
+        progress: ByteProgress<'_>,
+    ) -> Result<ResolvedArtifact, DistError> {
+        if self.opts.offline {
+            return Err(DistError::Offline {
+                reference: url.to_string(),
+            });
+        }
+        self.check_url(url)?;
+        let expected: Vec<String> = url_integrity(url)?
+            .into_iter()
+            .chain(expected.map(normalize_digest))
+            .collect();
+        if expected.is_empty() && self.opts.require_integrity {
+            return Err(DistError::IntegrityRequired {
                 url: url.to_string(),
             });
         }
-        let bytes = self
+        let request_url = url.split_once('#').map_or(url, |(url, _)| url);
+        let mut response = self
             .http
-            .get(url)
+            .get(request_url)
             .send()
             .await?
-            .error_for_status()?
-            .bytes()
-            .await?;
-        let digest = digest_for_bytes(&bytes);
-        let path = self.cache.write_component(&digest, &bytes)?;
+            .error_for_status()?;
+        let total = response.content_length();
+        let mut bytes = Vec::new();
+        while let Some(chunk) = response.chunk().await? {
+            bytes.extend_from_slice(&chunk);
+            progress(bytes.len() as u64, total);
+        }
+        let digest = compute_digest(&bytes);
+        if let Some(expected) = expected.into_iter().find(|expected| *expected != digest) {
+            return Err(DistError::DigestMismatch {
+                reference: url.to_string(),
+                expected,
+                actual: digest,
+            });
+        }
+        let path = self.cache.write_component(&digest, url, &bytes)?;
         Ok(ResolvedArtifact {
             cache_path: Some(path),
             digest,
@@ -214,8 +1138,10 @@ impl DistClient {
 
     async fn ingest_file(&self, path: &Path) -> Result<ResolvedArtifact, DistError> {
         let bytes = fs::read(path)?;
-        let digest = digest_for_bytes(&bytes);
-        let cached = self.cache.write_component(&digest, &bytes)?;
+        let digest = compute_digest(&bytes);
+        let cached = self
+            .cache
+            .write_component(&digest, &path.display().to_string(), &bytes)?;
         Ok(ResolvedArtifact {
             cache_path: Some(cached),
             digest,
@@ -301,8 +1227,20 @@ pub enum DistError {
     Offline { reference: String },
     #[error("reference `{reference}` is not cached")]
     CacheMiss { reference: String },
-    #[error("auth not implemented for `{target}`")]
+    #[error("authentication required for `{target}`; run `greentic-dist auth login`")]
     AuthRequired { target: String },
+    #[error("`{reference}` was not found")]
+    NotFound { reference: String },
+    #[error("frozen lockfile entry `{reference}` is not pinned by digest")]
+    NotPinned { reference: String },
+    #[error("digest mismatch for `{reference}`: expected {expected}, got {actual}")]
+    DigestMismatch {
+        reference: String,
+        expected: String,
+        actual: String,
+    },
+    #[error("`{url}` has no integrity digest; add `#sha256=<hex>` or pin it in a lockfile")]
+    IntegrityRequired { url: String },
     #[error("io error: {0}")]
     Io(#[from] std::io::Error),
     #[error("http error: {0}")]
@@ -311,6 +1249,12 @@ pub enum DistError {
     Oci(#[from] crate::oci_components::OciComponentError),
     #[error("invalid lockfile: {0}")]
     Serde(#[from] serde_json::Error),
+    #[error(transparent)]
+    Overrides(#[from] OverridesError),
+    #[error(transparent)]
+    Credentials(#[from] CredentialsError),
+    #[error("strict mode refuses local override {} for `{reference}`", path.display())]
+    OverrideRefused { reference: String, path: PathBuf },
 }
 
 impl DistError {
@@ -319,8 +1263,12 @@ impl DistError {
             DistError::InvalidReference { .. }
             | DistError::InvalidInput(_)
             | DistError::InsecureUrl { .. }
-            | DistError::Serde(_) => 2,
-            DistError::CacheMiss { .. } => 3,
+            | DistError::Serde(_)
+            | DistError::Overrides(_)
+            | DistError::OverrideRefused { .. }
+            | DistError::NotPinned { .. }
+            | DistError::IntegrityRequired { .. } => 2,
+            DistError::CacheMiss { .. } | DistError::NotFound { .. } => 3,
             DistError::Offline { .. } => 4,
             DistError::AuthRequired { .. } => 5,
             _ => 10,
@@ -328,71 +1276,123 @@ impl DistError {
     }
 }
 
+/// `DistClient`'s view of the shared store: components as `<entry>/component.wasm`.
 #[derive(Clone, Debug)]
 struct ComponentCache {
-    base: PathBuf,
+    store: BlobStore,
+    policy: CachePolicy,
 }
 
 impl ComponentCache {
-    fn new(base: PathBuf) -> Self {
-        Self { base }
+    fn new(base: PathBuf, policy: CachePolicy) -> Self {
+        Self {
+            store: BlobStore::new(base),
+            policy,
+        }
     }
 
-    fn component_dir(&self, digest: &str) -> PathBuf {
-        self.base
-            .join(trim_digest_prefix(&normalize_digest(digest)))
+    fn component_path(&self, digest: &str) -> std::io::Result<PathBuf> {
+        Ok(self.store.entry_dir(digest)?.join("component.wasm"))
     }
 
-    fn component_path(&self, digest: &str) -> PathBuf {
-        self.component_dir(digest).join("component.wasm")
+    /// Whether the entry holds a component (as opposed to a pack or digest blob).
+    fn is_component(&self, digest: &str) -> bool {
+        match self
+            .store
+            .metadata(digest)
+            .and_then(|metadata| metadata.kind)
+        {
+            Some(kind) => kind == ArtifactKind::Component,
+            None => self.component_path(digest).is_ok_and(|path| path.exists()),
+        }
     }
 
     fn existing_component(&self, digest: &str) -> Option<PathBuf> {
-        let path = self.component_path(digest);
-        if path.exists() { Some(path) } else { None }
+        let path = self.component_path(digest).ok()?;
+        if !path.exists() {
+            return None;
+        }
+        self.store.touch(digest);
+        Some(path)
     }
 
-    fn write_component(&self, digest: &str, data: &[u8]) -> Result<PathBuf, std::io::Error> {
-        let dir = self.component_dir(digest);
-        fs::create_dir_all(&dir)?;
-        let path = dir.join("component.wasm");
-        fs::write(&path, data)?;
+    /// Writes and commits under the entry lock, so concurrent pulls of the same digest
+    /// never observe a truncated `component.wasm`.
+    fn write_component(
+        &self,
+        digest: &str,
+        reference: &str,
+        data: &[u8],
+    ) -> Result<PathBuf, std::io::Error> {
+        let lock = self.store.lock(digest)?;
+        let path = self.store.write_file(digest, "component.wasm", data)?;
+        self.store.commit(
+            digest,
+            StoreMetadata {
+                media_type: Some(WASM_MEDIA_TYPE.to_string()),
+                ..StoreMetadata::new(
+                    ArtifactKind::Component,
+                    reference,
+                    digest,
+                    data.len() as u64,
+                )
+            },
+        )?;
+        drop(lock);
+        self.store.enforce(&self.policy, digest);
         Ok(path)
     }
+}
 
-    fn list_digests(&self) -> Vec<String> {
-        let mut digests = Vec::new();
-        if let Ok(entries) = fs::read_dir(&self.base) {
-            for entry in entries.flatten() {
-                if let Ok(meta) = entry.metadata()
-                    && meta.is_dir()
-                    && let Some(name) = entry.file_name().to_str()
-                {
-                    digests.push(format!("sha256:{name}"));
-                }
-            }
-        }
-        digests
+/// A lock entry fetched once on behalf of every entry with the same digest or label
+/// (its reference, or its repository and version range).
+struct LockJob {
+    index: usize,
+    label: String,
+    entry: LockResolvedEntry,
+    digest: Option<String>,
+}
+
+impl LockJob {
+    fn key(&self) -> &str {
+        self.digest.as_deref().unwrap_or(&self.label)
     }
 }
 
-fn digest_for_bytes(bytes: &[u8]) -> String {
-    let mut hasher = Sha256::new();
-    hasher.update(bytes);
-    format!("sha256:{:x}", hasher.finalize())
+#[derive(Clone, Copy)]
+enum ServiceKind {
+    Store,
+    Repo,
 }
 
-fn trim_digest_prefix(digest: &str) -> &str {
-    digest
-        .strip_prefix("sha256:")
-        .unwrap_or_else(|| digest.trim_start_matches('@'))
+/// Store/repo API answer for one component version.
+#[derive(Debug, serde::Deserialize)]
+struct ServiceResolution {
+    digest: String,
+    /// Digest-pinned `oci://`/registry reference or HTTPS URL.
+    location: String,
 }
 
-fn normalize_digest(digest: &str) -> String {
-    if digest.starts_with("sha256:") {
-        digest.to_string()
+/// Splits `store://publisher/component[@version]`; a missing version means `latest`.
+fn parse_service_reference(reference: &str) -> Result<(&str, &str, &str), DistError> {
+    let invalid = || DistError::InvalidReference {
+        reference: reference.to_string(),
+    };
+    let rest = reference.split_once("://").ok_or_else(invalid)?.1;
+    let (path, version) = rest.split_once('@').unwrap_or((rest, "latest"));
+    let (publisher, component) = path.split_once('/').ok_or_else(invalid)?;
+    // Dot-only segments would be normalized into a different API path.
+    let valid = |segment: &str| {
+        !segment.is_empty()
+            && !segment.chars().all(|c| c == '.')
+            && segment
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.' | '+'))
+    };
+    if valid(publisher) && valid(component) && valid(version) {
+        Ok((publisher, component, version))
     } else {
-        format!("sha256:{digest}")
+        Err(invalid())
     }
 }
 
@@ -406,7 +1406,7 @@ enum RefKind {
 }
 
 fn classify_reference(input: &str) -> Result<RefKind, DistError> {
-    if is_digest(input) {
+    if is_sha256_digest(input) {
         return Ok(RefKind::Digest(normalize_digest(input)));
     }
     if let Ok(url) = Url::parse(input) {
@@ -439,8 +1439,22 @@ fn classify_reference(input: &str) -> Result<RefKind, DistError> {
     }
 }
 
-fn is_digest(s: &str) -> bool {
-    s.starts_with("sha256:") && s.len() == "sha256:".len() + 64
+/// Digest named by a `#sha256=<hex>` URL fragment; other fragments carry no integrity.
+fn url_integrity(url: &str) -> Result<Option<String>, DistError> {
+    let Some(hex) = Url::parse(url)
+        .ok()
+        .and_then(|url| url.fragment()?.strip_prefix("sha256=").map(str::to_string))
+    else {
+        return Ok(None);
+    };
+    let digest = format!("sha256:{}", hex.to_ascii_lowercase());
+    if is_sha256_digest(&digest) {
+        Ok(Some(digest))
+    } else {
+        Err(DistError::InvalidReference {
+            reference: url.to_string(),
+        })
+    }
 }
 
 fn is_loopback_http(url: &str) -> bool {
@@ -471,13 +1485,14 @@ enum LockEntry {
 }
 
 #[derive(Debug, serde::Deserialize)]
-#[allow(dead_code)]
 struct LockComponent {
     reference: Option<String>,
     #[serde(rename = "ref")]
     ref_field: Option<String>,
     digest: Option<String>,
     name: Option<String>,
+    repository: Option<String>,
+    version: Option<String>,
 }
 
 impl LockEntry {
@@ -486,14 +1501,21 @@ impl LockEntry {
             LockEntry::String(s) => LockResolvedEntry {
                 reference: Some(s.clone()),
                 digest: None,
+                name: None,
+                constraint: None,
             },
             LockEntry::Object(obj) => LockResolvedEntry {
                 reference: obj
                     .reference
                     .clone()
                     .or_else(|| obj.ref_field.clone())
-                    .or_else(|| obj.digest.clone()),
+                    .or_else(|| obj.repository.is_none().then(|| obj.digest.clone())?),
                 digest: obj.digest.clone(),
+                name: obj.name.clone(),
+                constraint: obj.repository.clone().map(|repository| VersionConstraint {
+                    repository,
+                    version: obj.version.clone(),
+                }),
             },
         }
     }
@@ -503,17 +1525,69 @@ impl LockEntry {
 struct LockResolvedEntry {
     reference: Option<String>,
     digest: Option<String>,
+    name: Option<String>,
+    constraint: Option<VersionConstraint>,
 }
 
-fn parse_lockfile(data: &str) -> Result<Vec<LockResolvedEntry>, serde_json::Error> {
-    if let Ok(entries) = serde_json::from_str::<Vec<LockEntry>>(data) {
-        return Ok(entries.into_iter().map(|e| e.to_resolved()).collect());
+/// `repository` plus optional semver `version` range of a lock entry.
+#[derive(Clone, Debug)]
+struct VersionConstraint {
+    repository: String,
+    version: Option<String>,
+}
+
+impl VersionConstraint {
+    fn oci_repository(&self) -> &str {
+        self.repository.trim_start_matches("oci://")
+    }
+
+    fn label(&self) -> String {
+        match &self.version {
+            Some(version) => format!("{}@{version}", self.repository),
+            None => self.repository.clone(),
+        }
+    }
+}
+
+impl LockResolvedEntry {
+    /// Reference shown for the entry when it has none of its own.
+    fn label(&self) -> String {
+        self.reference
+            .clone()
+            .or_else(|| self.constraint.as_ref().map(VersionConstraint::label))
+            .unwrap_or_default()
+    }
+}
+
+fn parse_lockfile(data: &str) -> Result<Vec<LockResolvedEntry>, DistError> {
+    let entries: Vec<LockResolvedEntry> = if let Ok(entries) =
+        serde_json::from_str::<Vec<LockEntry>>(data)
+    {
+        entries.into_iter().map(|e| e.to_resolved()).collect()
+    } else {
+        let parsed: LockFile = serde_json::from_str(data)?;
+        if let Some(version) = parsed.schema_version
+            && version > LOCKFILE_SCHEMA_VERSION
+        {
+            return Err(DistError::InvalidInput(format!(
+                "lockfile schema_version {version} is newer than supported version {LOCKFILE_SCHEMA_VERSION}"
+            )));
+        }
+        parsed
+            .components
+            .into_iter()
+            .map(|c| c.to_resolved())
+            .collect()
+    };
+    // Locked digests name store entries, so anything else could point outside the store.
+    if let Some(digest) = entries
+        .iter()
+        .filter_map(|entry| entry.digest.as_deref())
+        .find(|digest| !is_sha256_digest(digest))
+    {
+        return Err(DistError::InvalidInput(format!(
+            "lockfile digest `{digest}` is not sha256: followed by 64 lowercase hex characters"
+        )));
     }
-    let parsed: LockFile = serde_json::from_str(data)?;
-    let _ = parsed.schema_version;
-    Ok(parsed
-        .components
-        .into_iter()
-        .map(|c| c.to_resolved())
-        .collect())
+    Ok(entries)
 }
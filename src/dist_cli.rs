@@ -1,13 +1,20 @@
-use crate::dist::{DistClient, DistOptions};
-use crate::oci_components::default_cache_root;
+use crate::cache_policy::{CachePolicy, parse_age, parse_size};
+use crate::credentials::{CredentialStore, StoredCredential, default_credentials_path};
+use crate::dist::{
+    ArtifactSource, DEFAULT_PULL_CONCURRENCY, DistClient, DistOptions, LockDrift, PullEvent,
+    ResolvedArtifact,
+};
 #[cfg(feature = "pack-fetch")]
 use crate::oci_packs::{
     DefaultRegistryClient, OciPackFetcher, PackFetchOptions, RegistryClient, ResolvedPack,
 };
+use crate::store::default_store_root;
 use clap::{Parser, Subcommand};
 use serde::{Deserialize, Serialize};
 use std::fs;
+use std::io::Read;
 use std::path::PathBuf;
+use std::time::Duration;
 
 #[derive(Parser, Debug)]
 #[command(name = "greentic-dist")]
@@ -19,6 +26,15 @@ pub struct Cli {
     /// Offline mode (disable network fetches)
     #[arg(long, global = true)]
     pub offline: bool,
+    /// Overrides file mapping references or component ids to local builds
+    #[arg(long, global = true)]
+    pub overrides: Option<PathBuf>,
+    /// Strict/production mode: fail instead of applying overrides
+    #[arg(long, global = true)]
+    pub strict: bool,
+    /// Refuse HTTP(S) references without a `#sha256=` fragment or locked digest
+    #[arg(long, global = true)]
+    pub require_integrity: bool,
     #[command(subcommand)]
     pub command: Commands,
 }
@@ -36,15 +52,56 @@ pub enum Commands {
         reference: Option<String>,
         #[arg(long)]
         lock: Option<PathBuf>,
+        /// Refuse lock entries not pinned by digest and fetch only by digest
+        #[arg(long, requires = "lock")]
+        frozen: bool,
+        /// Maximum lock entries fetched at once
+        #[arg(long, requires = "lock", default_value_t = DEFAULT_PULL_CONCURRENCY)]
+        concurrency: usize,
+        /// Emit JSON (progress events as JSON Lines with --lock; `bytes` events come only
+        /// from HTTP(S) downloads, not OCI pulls)
+        #[arg(long)]
+        json: bool,
+    },
+    /// Report lock entries whose tag, store/repo version or semver range moved to another digest
+    Outdated {
+        #[arg(long, default_value = "pack.lock.json")]
+        lock: PathBuf,
+        #[arg(long)]
+        json: bool,
+    },
+    /// Re-pin moved lock entries (all, or the one with this name, reference or repository)
+    Update {
+        name: Option<String>,
+        #[arg(long, default_value = "pack.lock.json")]
+        lock: PathBuf,
+        #[arg(long)]
+        json: bool,
+    },
+    /// Check that the cache holds every lockfile entry intact
+    Verify {
+        #[arg(long)]
+        lock: PathBuf,
         #[arg(long)]
         json: bool,
     },
+    /// Resolve references to digests and write a lockfile
+    Lock {
+        /// References to lock (in addition to those from --manifest)
+        refs: Vec<String>,
+        /// Pack manifest (JSON) whose `extensions."greentic.components".refs` are locked
+        #[arg(long)]
+        manifest: Option<PathBuf>,
+        /// Where to write the lockfile (`-` for stdout)
+        #[arg(long, short, default_value = "pack.lock.json")]
+        out: PathBuf,
+    },
     /// Cache management commands
     Cache {
         #[command(subcommand)]
         command: CacheCommand,
     },
-    /// Authentication commands (stub)
+    /// Manage saved registry and store credentials
     Auth {
         #[command(subcommand)]
         command: AuthCommand,
@@ -56,6 +113,12 @@ pub enum Commands {
         #[arg(long)]
         show_media_type: bool,
     },
+    /// Manage a local dev distributor layout; requires the separate `greentic-dist-dev`
+    /// helper (`cargo install greentic-distributor-dev`) next to this binary or on PATH
+    Dev {
+        #[arg(trailing_var_arg = true, allow_hyphen_values = true)]
+        args: Vec<String>,
+    },
     /// Fetch an OCI pack into the local cache
     #[cfg(feature = "pack-fetch")]
     Pack {
@@ -86,18 +149,61 @@ pub enum CacheCommand {
         #[arg(long)]
         json: bool,
     },
+    /// Evict expired and least-recently-used entries (pinned entries are kept)
+    Prune {
+        /// Maximum cache size, e.g. `10G` or `512M` (default: GREENTIC_DIST_CACHE_MAX_SIZE)
+        #[arg(long, value_parser = parse_size)]
+        max_size: Option<u64>,
+        /// Maximum time since last access, e.g. `30d` or `12h` (default: GREENTIC_DIST_CACHE_MAX_AGE)
+        #[arg(long, value_parser = parse_age)]
+        max_age: Option<Duration>,
+        #[arg(long)]
+        json: bool,
+    },
+    /// Exempt cached digests from eviction
+    Pin { digests: Vec<String> },
+    /// Make pinned digests evictable again
+    Unpin { digests: Vec<String> },
 }
 
 #[derive(Subcommand, Debug)]
 pub enum AuthCommand {
-    /// Stub login hook for future repo/store auth
-    Login { target: String },
+    /// Save credentials for a registry host or a `store://`/`repo://` target
+    Login {
+        target: String,
+        /// Username for basic auth; without it the secret is saved as a token
+        #[arg(long)]
+        username: Option<String>,
+        /// Token or password (prefer --password-stdin)
+        #[arg(long, conflicts_with = "password_stdin")]
+        token: Option<String>,
+        /// Read the token or password from stdin
+        #[arg(long)]
+        password_stdin: bool,
+    },
+    /// Remove saved credentials for a target
+    Logout { target: String },
+    /// List targets with saved credentials
+    Status {
+        #[arg(long)]
+        json: bool,
+    },
 }
 
 #[derive(Serialize)]
 struct ResolveOutput<'a> {
     reference: &'a str,
     digest: &'a str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    overridden: Option<&'a std::path::Path>,
+}
+
+#[derive(Serialize)]
+struct AuthStatusOutput<'a> {
+    target: &'a str,
+    kind: &'a str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    username: Option<&'a str>,
 }
 
 #[derive(Serialize)]
@@ -106,6 +212,8 @@ struct PullOutput<'a> {
     digest: &'a str,
     cache_path: Option<&'a std::path::Path>,
     fetched: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    overridden: Option<&'a std::path::Path>,
 }
 
 #[cfg(feature = "pack-fetch")]
@@ -118,6 +226,24 @@ struct PackOutput<'a> {
     fetched: bool,
 }
 
+#[derive(Debug, Deserialize)]
+struct PackManifest {
+    #[serde(default)]
+    extensions: PackManifestExtensions,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct PackManifestExtensions {
+    #[serde(rename = "greentic.components")]
+    components: Option<PackManifestComponents>,
+}
+
+#[derive(Debug, Deserialize)]
+struct PackManifestComponents {
+    #[serde(default)]
+    refs: Vec<String>,
+}
+
 #[derive(Debug, Deserialize)]
 struct ComponentManifest {
     #[serde(default)]
@@ -130,6 +256,9 @@ struct ComponentManifestArtifacts {
     component_wasm: Option<String>,
 }
 
+/// Exit code of `greentic-dist verify` when the cache disagrees with the lockfile.
+const VERIFY_FAILED_EXIT_CODE: i32 = 6;
+
 pub async fn run_from_env() -> Result<(), CliError> {
     let cli = Cli::parse();
     run(cli).await
@@ -156,10 +285,28 @@ pub async fn run_with_pack_client<C: RegistryClient>(
     if let Some(dir) = cli.cache_dir {
         opts.cache_dir = dir;
     } else {
-        opts.cache_dir = default_cache_root();
+        opts.cache_dir = default_store_root();
     }
     opts.offline = offline || opts.offline;
+    if let Some(file) = cli.overrides {
+        opts.overrides_file = Some(file);
+    }
+    opts.strict = cli.strict || opts.strict;
+    opts.require_integrity = cli.require_integrity || opts.require_integrity;
+    if let Commands::Pull {
+        frozen,
+        concurrency,
+        ..
+    } = &cli.command
+    {
+        opts.frozen = *frozen;
+        opts.pull_concurrency = *concurrency;
+    }
 
+    let credentials_path = opts
+        .credentials_file
+        .clone()
+        .unwrap_or_else(default_credentials_path);
     let client = DistClient::new(opts);
 
     match cli.command {
@@ -168,10 +315,12 @@ pub async fn run_with_pack_client<C: RegistryClient>(
                 .resolve_ref(&reference)
                 .await
                 .map_err(CliError::from_dist)?;
+            warn_if_overridden(&resolved);
             if json {
                 let out = ResolveOutput {
                     reference: &reference,
                     digest: &resolved.digest,
+                    overridden: overridden_path(&resolved),
                 };
                 println!("{}", serde_json::to_string_pretty(&out).unwrap());
             } else {
@@ -182,24 +331,15 @@ pub async fn run_with_pack_client<C: RegistryClient>(
             reference,
             lock,
             json,
+            ..
         } => {
             if let Some(lock_path) = lock {
                 let resolved = client
-                    .pull_lock(&lock_path)
+                    .pull_lock_with_progress(&lock_path, &|event| render_pull_event(&event, json))
                     .await
                     .map_err(CliError::from_dist)?;
-                if json {
-                    let payload: Vec<_> = resolved
-                        .iter()
-                        .map(|r| PullOutput {
-                            reference: "",
-                            digest: &r.digest,
-                            cache_path: r.cache_path.as_deref(),
-                            fetched: r.fetched,
-                        })
-                        .collect();
-                    println!("{}", serde_json::to_string_pretty(&payload).unwrap());
-                } else {
+                resolved.iter().for_each(warn_if_overridden);
+                if !json {
                     for r in resolved {
                         let path = r
                             .cache_path
@@ -214,12 +354,14 @@ pub async fn run_with_pack_client<C: RegistryClient>(
                     .ensure_cached(&reference)
                     .await
                     .map_err(CliError::from_dist)?;
+                warn_if_overridden(&resolved);
                 if json {
                     let out = PullOutput {
                         reference: &reference,
                         digest: &resolved.digest,
                         cache_path: resolved.cache_path.as_deref(),
                         fetched: resolved.fetched,
+                        overridden: overridden_path(&resolved),
                     };
                     println!("{}", serde_json::to_string_pretty(&out).unwrap());
                 } else if let Some(path) = &resolved.cache_path {
@@ -234,6 +376,103 @@ pub async fn run_with_pack_client<C: RegistryClient>(
                 });
             }
         }
+        Commands::Outdated { lock, json } => {
+            let drift = client.outdated(&lock).await.map_err(CliError::from_dist)?;
+            if json {
+                println!("{}", serde_json::to_string_pretty(&drift).unwrap());
+            } else {
+                let moved: Vec<_> = drift.iter().filter(|entry| entry.moved).collect();
+                for entry in &moved {
+                    println!("{}", describe_drift(entry));
+                }
+                if moved.is_empty() {
+                    eprintln!("{} tracked entries are up to date", drift.len());
+                }
+            }
+        }
+        Commands::Update { name, lock, json } => {
+            let updated = client
+                .update_lock(&lock, name.as_deref())
+                .await
+                .map_err(CliError::from_dist)?;
+            if json {
+                println!("{}", serde_json::to_string_pretty(&updated).unwrap());
+            } else {
+                for entry in &updated {
+                    println!("{}", describe_drift(entry));
+                }
+                eprintln!("updated {} entries in {}", updated.len(), lock.display());
+            }
+        }
+        Commands::Verify { lock, json } => {
+            let report = client.verify_lock(&lock).map_err(CliError::from_dist)?;
+            if json {
+                println!("{}", serde_json::to_string_pretty(&report).unwrap());
+            } else {
+                for digest in &report.verified {
+                    println!("ok {digest}");
+                }
+                for reference in &report.unpinned {
+                    println!("unpinned {reference}");
+                }
+                for issue in &report.missing {
+                    println!("missing {} {}", issue.digest, issue.reference);
+                }
+                for issue in &report.mismatched {
+                    println!(
+                        "mismatch {} {} (cached {})",
+                        issue.digest,
+                        issue.reference,
+                        issue.actual.as_deref().unwrap_or_default()
+                    );
+                }
+                for digest in &report.extra {
+                    println!("extra {digest}");
+                }
+            }
+            if !report.is_ok() {
+                return Err(CliError {
+                    code: VERIFY_FAILED_EXIT_CODE,
+                    message: format!(
+                        "{} does not match the cache: {} unpinned, {} missing, {} mismatched",
+                        lock.display(),
+                        report.unpinned.len(),
+                        report.missing.len(),
+                        report.mismatched.len()
+                    ),
+                });
+            }
+        }
+        Commands::Lock {
+            mut refs,
+            manifest,
+            out,
+        } => {
+            if let Some(manifest) = manifest {
+                refs.extend(manifest_component_refs(&manifest)?);
+            }
+            if refs.is_empty() {
+                return Err(CliError {
+                    code: 2,
+                    message: "lock requires references or a --manifest with component refs".into(),
+                });
+            }
+            let lockfile = client.lock(&refs).await.map_err(CliError::from_dist)?;
+            let json = serde_json::to_string_pretty(&lockfile).unwrap();
+            if out.as_os_str() == "-" {
+                println!("{json}");
+            } else {
+                fs::write(&out, format!("{json}\n")).map_err(|err| CliError {
+                    code: 10,
+                    message: format!("failed to write {}: {err}", out.display()),
+                })?;
+                eprintln!(
+                    "locked {} component(s) in {}",
+                    lockfile.components.len(),
+                    out.display()
+                );
+            }
+        }
         Commands::Cache { command } => match command {
             CacheCommand::Ls { json } => {
                 let entries = client.list_cache();
@@ -261,17 +500,37 @@ pub async fn run_with_pack_client<C: RegistryClient>(
                     eprintln!("removed {}", removed.join(", "));
                 }
             }
-        },
-        Commands::Auth { command } => match command {
-            AuthCommand::Login { target } => {
-                return Err(CliError {
-                    code: 5,
-                    message: format!(
-                        "auth login for `{target}` is not implemented yet; stubbed for future store/repo"
-                    ),
-                });
+            CacheCommand::Prune {
+                max_size,
+                max_age,
+                json,
+            } => {
+                let defaults = CachePolicy::from_env();
+                let policy = CachePolicy {
+                    max_size_bytes: max_size.or(defaults.max_size_bytes),
+                    max_age: max_age.or(defaults.max_age),
+                };
+                let report = client.prune(&policy).map_err(CliError::from_dist)?;
+                if json {
+                    println!("{}", serde_json::to_string_pretty(&report).unwrap());
+                } else {
+                    for digest in &report.removed {
+                        eprintln!("removed {digest}");
+                    }
+                    println!(
+                        "freed {} bytes, {} bytes remaining",
+                        report.freed_bytes, report.remaining_bytes
+                    );
+                }
             }
+            CacheCommand::Pin { digests } => client
+                .set_pinned(&digests, true)
+                .map_err(CliError::from_dist)?,
+            CacheCommand::Unpin { digests } => client
+                .set_pinned(&digests, false)
+                .map_err(CliError::from_dist)?,
         },
+        Commands::Auth { command } => run_auth(command, credentials_path)?,
         Commands::Inspect {
             reference,
             show_media_type,
@@ -321,6 +580,7 @@ pub async fn run_with_pack_client<C: RegistryClient>(
                 println!("selected media type: {}", inspection.selected_media_type);
             }
         }
+        Commands::Dev { args } => run_dev_helper(&args)?,
         #[cfg(feature = "pack-fetch")]
         Commands::Pack {
             reference,
@@ -353,6 +613,204 @@ pub async fn run_with_pack_client<C: RegistryClient>(
     Ok(())
 }
 
+fn run_auth(command: AuthCommand, path: PathBuf) -> Result<(), CliError> {
+    let mut store = CredentialStore::open(path).map_err(CliError::from_credentials)?;
+    match command {
+        AuthCommand::Login {
+            target,
+            username,
+            token,
+            password_stdin,
+        } => {
+            let secret = match token {
+                Some(token) => token,
+                None if password_stdin => {
+                    let mut input = String::new();
+                    std::io::stdin()
+                        .read_to_string(&mut input)
+                        .map_err(|err| CliError {
+                            code: 2,
+                            message: format!("failed to read secret from stdin: {err}"),
+                        })?;
+                    input.trim_end_matches(['\r', '\n']).to_string()
+                }
+                None => {
+                    return Err(CliError {
+                        code: 2,
+                        message: "auth login requires --token or --password-stdin".into(),
+                    });
+                }
+            };
+            if secret.is_empty() {
+                return Err(CliError {
+                    code: 2,
+                    message: "refusing to save an empty secret".into(),
+                });
+            }
+            let credential = match username {
+                Some(username) => StoredCredential::Basic {
+                    username,
+                    password: secret,
+                },
+                None => StoredCredential::Token(secret),
+            };
+            store.set(&target, credential);
+            store.save().map_err(CliError::from_credentials)?;
+            eprintln!(
+                "saved credentials for {target} in {}",
+                store.path().display()
+            );
+        }
+        AuthCommand::Logout { target } => {
+            if store.remove(&target) {
+                store.save().map_err(CliError::from_credentials)?;
+                eprintln!("removed credentials for {target}");
+            } else {
+                eprintln!("not logged in to {target}");
+            }
+        }
+        AuthCommand::Status { json } => {
+            let entries = store.entries();
+            let rows: Vec<_> = entries
+                .iter()
+                .map(|(target, credential)| match credential {
+                    StoredCredential::Basic { username, .. } => AuthStatusOutput {
+                        target,
+                        kind: "basic",
+                        username: Some(username),
+                    },
+                    StoredCredential::Token(_) => AuthStatusOutput {
+                        target,
+                        kind: "token",
+                        username: None,
+                    },
+                })
+                .collect();
+            if json {
+                println!("{}", serde_json::to_string_pretty(&rows).unwrap());
+            } else {
+                for row in rows {
+                    match row.username {
+                        Some(username) => println!("{} {} ({username})", row.target, row.kind),
+                        None => println!("{} {}", row.target, row.kind),
+                    }
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Component refs listed under `extensions."greentic.components".refs` of a pack manifest.
+fn manifest_component_refs(path: &std::path::Path) -> Result<Vec<String>, CliError> {
+    let invalid = |err: &dyn std::fmt::Display| CliError {
+        code: 2,
+        message: format!("failed to read pack manifest {}: {err}", path.display()),
+    };
+    let bytes = fs::read(path).map_err(|err| invalid(&err))?;
+    let manifest: PackManifest = serde_json::from_slice(&bytes).map_err(|err| invalid(&err))?;
+    Ok(manifest
+        .extensions
+        .components
+        .map(|components| components.refs)
+        .unwrap_or_default())
+}
+
+fn describe_drift(entry: &LockDrift) -> String {
+    let mut line = format!(
+        "{} {} -> {}",
+        entry.name.as_deref().unwrap_or(&entry.reference),
+        entry.locked.as_deref().unwrap_or("unpinned"),
+        entry.current
+    );
+    if let Some(selected) = &entry.selected {
+        line.push_str(&format!(" ({selected})"));
+    }
+    line
+}
+
+/// Prints lock pull progress: one JSON object per line on stdout with `--json`, otherwise
+/// a line per started/finished/failed entry on stderr.
+fn render_pull_event(event: &PullEvent, json: bool) {
+    if json {
+        println!("{}", serde_json::to_string(event).unwrap());
+        return;
+    }
+    match event {
+        PullEvent::Started { reference, .. } => eprintln!("pulling {reference}"),
+        PullEvent::Bytes { .. } => {}
+        PullEvent::Finished {
+            reference,
+            digest,
+            fetched,
+            ..
+        } => eprintln!(
+            "{} {reference} {digest}",
+            if *fetched { "fetched" } else { "cached" }
+        ),
+        PullEvent::Failed {
+            reference, error, ..
+        } => eprintln!("failed {reference}: {error}"),
+    }
+}
+
+fn overridden_path(resolved: &ResolvedArtifact) -> Option<&std::path::Path> {
+    match &resolved.source {
+        ArtifactSource::Overridden { path, .. } => Some(path),
+        _ => None,
+    }
+}
+
+fn warn_if_overridden(resolved: &ResolvedArtifact) {
+    if let ArtifactSource::Overridden { reference, path } = &resolved.source {
+        eprintln!(
+            "warning: `{reference}` overridden by local file {}",
+            path.display()
+        );
+    }
+}
+
+/// Forwards `greentic-dist dev ...` to the `greentic-dist-dev` binary from
+/// `greentic-distributor-dev`, preferring one installed next to this executable.
+///
+/// The dev crate depends on this one, so its library cannot be linked in here; the
+/// helper has to be installed separately.
+fn run_dev_helper(args: &[String]) -> Result<(), CliError> {
+    const HELPER: &str = "greentic-dist-dev";
+    let program = std::env::current_exe()
+        .ok()
+        .and_then(|exe| {
+            exe.parent()
+                .map(|dir| dir.join(format!("{HELPER}{}", std::env::consts::EXE_SUFFIX)))
+        })
+        .filter(|path| path.is_file())
+        .unwrap_or_else(|| PathBuf::from(HELPER));
+    let status = std::process::Command::new(&program)
+        .args(args)
+        .status()
+        .map_err(|err| CliError {
+            code: 10,
+            message: if err.kind() == std::io::ErrorKind::NotFound {
+                format!(
+                    "failed to run {HELPER}: `greentic-dist dev` needs the {HELPER} helper next to greentic-dist or on PATH; install it with `cargo install greentic-distributor-dev`"
+                )
+            } else {
+                format!(
+                    "failed to run {HELPER}: {err}; install it with `cargo install greentic-distributor-dev`"
+                )
+            },
+        })?;
+    if status.success() {
+        Ok(())
+    } else {
+        // The helper already reported its own error.
+        Err(CliError {
+            code: status.code().unwrap_or(10),
+            message: String::new(),
+        })
+    }
+}
+
 #[cfg(feature = "pack-fetch")]
 pub async fn fetch_pack_for_cli<C: RegistryClient>(
     reference: &str,
@@ -367,6 +825,7 @@ pub async fn fetch_pack_for_cli<C: RegistryClient>(
     }
     opts.offline = offline;
     opts.allow_tags = allow_tags;
+    opts.cache_policy = CachePolicy::from_env();
 
     let fetcher = OciPackFetcher::with_client(client, opts);
     fetcher
@@ -385,4 +844,8 @@ impl CliError {
             message: err.to_string(),
         }
     }
+
+    fn from_credentials(err: crate::credentials::CredentialsError) -> Self {
+        Self::from_dist(err.into())
+    }
 }
@@ -4,6 +4,8 @@ use std::io;
 use reqwest::StatusCode;
 use thiserror::Error;
 
+use crate::source::{SourceAttempt, SourceOutcome};
+
 #[derive(Debug, Error)]
 pub enum DistributorError {
     #[error("io error: {0}")]
@@ -17,13 +19,46 @@ pub enum DistributorError {
     InvalidResponse(String),
     #[error("resource not found")]
     NotFound,
+    #[error("no source could serve the request: {}", join_attempts(.attempts))]
+    SourcesExhausted { attempts: Vec<SourceAttempt> },
+    #[error("digest mismatch: expected {expected}, got {actual}")]
+    DigestMismatch { expected: String, actual: String },
     #[error("permission denied")]
     PermissionDenied,
     #[cfg(feature = "http-runtime")]
     #[error("unexpected status {status}: {body}")]
     Status { status: StatusCode, body: String },
+    #[error("invalid source configuration: {0}")]
+    InvalidConfig(String),
     #[error("serialization error: {0}")]
     Serde(#[from] serde_json::Error),
     #[error("other distributor error: {0}")]
     Other(String),
 }
+
+impl DistributorError {
+    /// Returns true when the error only means "this artifact is not here".
+    ///
+    /// An exhausted chain counts as not found when every source reported a miss,
+    /// so nested chains keep falling through to their siblings.
+    pub fn is_not_found(&self) -> bool {
+        match self {
+            DistributorError::NotFound => true,
+            DistributorError::SourcesExhausted { attempts } => attempts
+                .iter()
+                .all(|attempt| attempt.outcome == SourceOutcome::Missing),
+            _ => false,
+        }
+    }
+}
+
+fn join_attempts(attempts: &[SourceAttempt]) -> String {
+    if attempts.is_empty() {
+        return "no sources configured".to_string();
+    }
+    attempts
+        .iter()
+        .map(ToString::to_string)
+        .collect::<Vec<_>>()
+        .join("; ")
+}
@@ -1,6 +1,16 @@
+mod cache_fs;
+#[cfg(any(
+    feature = "oci-components",
+    feature = "pack-fetch",
+    feature = "runner-api"
+))]
+pub mod cache_policy;
 pub mod config;
+#[cfg(feature = "dist-client")]
+pub mod credentials;
 pub mod error;
 pub mod source;
+pub mod source_config;
 pub mod types;
 
 #[cfg(feature = "dist-client")]
@@ -13,10 +23,33 @@ mod http;
 pub mod oci_components;
 #[cfg(feature = "pack-fetch")]
 pub mod oci_packs;
+#[cfg(feature = "oci-components")]
+pub mod overrides;
+#[cfg(any(
+    feature = "oci-components",
+    feature = "pack-fetch",
+    feature = "runner-api"
+))]
+pub mod registry_auth;
+#[cfg(feature = "dist-client")]
+pub mod remote_source;
 #[cfg(feature = "runner-api")]
 pub mod runner_api;
+#[cfg(any(
+    feature = "oci-components",
+    feature = "pack-fetch",
+    feature = "runner-api"
+))]
+mod store;
 mod wit_client;
 
+pub use cache_fs::write_atomic;
+#[cfg(any(
+    feature = "oci-components",
+    feature = "pack-fetch",
+    feature = "runner-api"
+))]
+pub use cache_policy::{CachePolicy, PruneReport};
 pub use config::DistributorClientConfig;
 #[cfg(feature = "dist-client")]
 pub use dist::{DistClient, DistOptions, ResolvedArtifact};
@@ -32,7 +65,22 @@ pub use oci_components::{
 pub use oci_packs::{OciPackError, OciPackFetcher, PackFetchOptions, ResolvedPack};
 #[cfg(feature = "pack-fetch")]
 pub use oci_packs::{fetch_pack, fetch_pack_to_cache};
-pub use source::{ChainedDistributorSource, DistributorSource};
+#[cfg(feature = "oci-components")]
+pub use overrides::{ComponentOverrides, OverridesError};
+#[cfg(any(
+    feature = "oci-components",
+    feature = "pack-fetch",
+    feature = "runner-api"
+))]
+pub use registry_auth::{RegistryAuthOptions, RegistryCredentials};
+#[cfg(feature = "http-runtime")]
+pub use source::AsyncArtifactStream;
+pub use source::{
+    ArtifactStream, CachingSource, ChainErrorPolicy, ChainStrategy, ChainedDistributorSource,
+    DistributorSource, FetchedArtifact, HashingReader, LookupCancellation, NamedSource,
+    SourceAttempt, SourceOutcome,
+};
+pub use source_config::{SourceChainBuilder, SourceChainConfig, SourceSpec};
 pub use types::*;
 pub use wit_client::{
     DistributorApiBindings, GeneratedDistributorApiBindings, WitDistributorClient,
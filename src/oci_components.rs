@@ -1,6 +1,5 @@
 use std::fs;
 use std::path::{Path, PathBuf};
-use std::time::{SystemTime, UNIX_EPOCH};
 
 use async_trait::async_trait;
 use oci_distribution::Reference;
@@ -10,11 +9,19 @@ use oci_distribution::manifest::{
     IMAGE_MANIFEST_LIST_MEDIA_TYPE, IMAGE_MANIFEST_MEDIA_TYPE, OCI_IMAGE_INDEX_MEDIA_TYPE,
     OCI_IMAGE_MEDIA_TYPE,
 };
-use oci_distribution::secrets::RegistryAuth;
-use serde::{Deserialize, Serialize};
-use sha2::{Digest, Sha256};
+use semver::{Version, VersionReq};
+use serde::Deserialize;
 use thiserror::Error;
 
+use crate::cache_fs::CacheLock;
+use crate::cache_policy::CachePolicy;
+use crate::registry_auth::{RegistryAuthOptions, RegistryCredentials};
+use crate::store::{
+    ArtifactKind, BlobStore, StoreMetadata, compute_digest, default_store_root, normalize_digest,
+};
+
+use crate::overrides::ComponentOverrides;
+
 const OCI_ARTIFACT_MANIFEST_MEDIA_TYPE: &str = "application/vnd.oci.artifact.manifest.v1+json";
 const DOCKER_MANIFEST_MEDIA_TYPE: &str = "application/vnd.docker.distribution.manifest.v2+json";
 const DOCKER_MANIFEST_LIST_MEDIA_TYPE: &str =
@@ -68,6 +75,14 @@ pub struct ComponentResolveOptions {
     pub cache_dir: PathBuf,
     pub accepted_manifest_types: Vec<String>,
     pub preferred_layer_media_types: Vec<String>,
+    /// Local files served in place of matching references (see [`ComponentOverrides`]).
+    pub overrides: ComponentOverrides,
+    /// Refuse to resolve references that have an override instead of applying it.
+    pub strict: bool,
+    /// Registry credential lookup for pulls.
+    pub auth: RegistryAuthOptions,
+    /// Size/age bounds enforced on `cache_dir` after each fetch.
+    pub cache_policy: CachePolicy,
 }
 
 impl Default for ComponentResolveOptions {
@@ -75,7 +90,7 @@ impl Default for ComponentResolveOptions {
         Self {
             allow_tags: false,
             offline: false,
-            cache_dir: default_cache_root(),
+            cache_dir: default_store_root(),
             accepted_manifest_types: DEFAULT_ACCEPTED_MANIFEST_TYPES
                 .iter()
                 .map(|s| s.to_string())
@@ -84,6 +99,10 @@ impl Default for ComponentResolveOptions {
                 .iter()
                 .map(|s| s.to_string())
                 .collect(),
+            overrides: ComponentOverrides::default(),
+            strict: false,
+            auth: RegistryAuthOptions::default(),
+            cache_policy: CachePolicy::default(),
         }
     }
 }
@@ -97,6 +116,8 @@ pub struct ResolvedComponent {
     pub path: PathBuf,
     pub fetched_from_network: bool,
     pub manifest_digest: Option<String>,
+    /// Set when `path` is a local override rather than a cached registry artifact.
+    pub overridden: bool,
 }
 
 #[derive(Debug, Deserialize)]
@@ -111,19 +132,6 @@ struct ComponentManifestArtifacts {
     component_wasm: Option<String>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
-struct CacheMetadata {
-    original_reference: String,
-    resolved_digest: String,
-    media_type: String,
-    fetched_at_unix_seconds: u64,
-    size_bytes: u64,
-    #[serde(default)]
-    manifest_digest: Option<String>,
-    #[serde(default)]
-    manifest_wasm_name: Option<String>,
-}
-
 /// Resolve OCI component references with caching and offline support.
 pub struct OciComponentResolver<C: RegistryClient = DefaultRegistryClient> {
     client: C,
@@ -167,10 +175,96 @@ impl<C: RegistryClient> OciComponentResolver<C> {
         Ok(results)
     }
 
+    /// Digest `reference` points at in the registry right now, without pulling layers or
+    /// touching the cache; digest-pinned references resolve to their own digest.
+    pub async fn resolve_digest(&self, reference: &str) -> Result<String, OciComponentError> {
+        let parsed =
+            Reference::try_from(reference).map_err(|e| OciComponentError::InvalidReference {
+                reference: reference.to_string(),
+                reason: e.to_string(),
+            })?;
+        if let Some(digest) = parsed.digest() {
+            return Ok(normalize_digest(digest));
+        }
+        if !self.opts.allow_tags {
+            return Err(OciComponentError::DigestRequired {
+                reference: reference.to_string(),
+            });
+        }
+        if self.opts.offline {
+            return Err(OciComponentError::OfflineTaggedReference {
+                reference: reference.to_string(),
+            });
+        }
+        let credentials = self.opts.auth.resolve(parsed.resolve_registry());
+        self.client
+            .fetch_manifest_digest(&parsed, &credentials)
+            .await
+            .map(|digest| normalize_digest(&digest))
+            .map_err(|source| OciComponentError::PullFailed {
+                reference: reference.to_string(),
+                source,
+            })
+    }
+
+    /// Highest semver tag of `repository` matching `requirement` (tags may carry a `v`
+    /// prefix), as a `registry/repository:tag` reference.
+    pub async fn resolve_version(
+        &self,
+        repository: &str,
+        requirement: &VersionReq,
+    ) -> Result<String, OciComponentError> {
+        let parsed =
+            Reference::try_from(repository).map_err(|e| OciComponentError::InvalidReference {
+                reference: repository.to_string(),
+                reason: e.to_string(),
+            })?;
+        if !self.opts.allow_tags {
+            return Err(OciComponentError::DigestRequired {
+                reference: repository.to_string(),
+            });
+        }
+        if self.opts.offline {
+            return Err(OciComponentError::OfflineTaggedReference {
+                reference: format!("{repository}@{requirement}"),
+            });
+        }
+        let credentials = self.opts.auth.resolve(parsed.resolve_registry());
+        let tags = self
+            .client
+            .list_tags(&parsed, &credentials)
+            .await
+            .map_err(|source| OciComponentError::PullFailed {
+                reference: repository.to_string(),
+                source,
+            })?;
+        let (_, tag) = tags
+            .iter()
+            .filter_map(|tag| {
+                let version = Version::parse(tag.strip_prefix('v').unwrap_or(tag)).ok()?;
+                requirement.matches(&version).then_some((version, tag))
+            })
+            .max_by(|a, b| a.0.cmp(&b.0))
+            .ok_or_else(|| OciComponentError::NoMatchingVersion {
+                repository: repository.to_string(),
+                requirement: requirement.to_string(),
+            })?;
+        Ok(Reference::with_tag(
+            parsed.registry().to_string(),
+            parsed.repository().to_string(),
+            tag.clone(),
+        )
+        .whole())
+    }
+
     async fn resolve_single(
         &self,
         reference: &str,
     ) -> Result<ResolvedComponent, OciComponentError> {
+        if let Some(path) = self.opts.overrides.lookup(reference) {
+            return self.resolve_override(reference, path);
+        }
+
         let parsed =
             Reference::try_from(reference).map_err(|e| OciComponentError::InvalidReference {
                 reference: reference.to_string(),
@@ -206,9 +300,10 @@ impl<C: RegistryClient> OciComponentResolver<C> {
             .iter()
             .map(|s| s.as_str())
             .collect::<Vec<_>>();
+        let credentials = self.opts.auth.resolve(parsed.resolve_registry());
         let image = self
             .client
-            .pull(&parsed, &accepted_layer_types)
+            .pull_with_auth(&parsed, &accepted_layer_types, &credentials)
             .await
             .map_err(|source| OciComponentError::PullFailed {
                 reference: reference.to_string(),
@@ -246,6 +341,18 @@ impl<C: RegistryClient> OciComponentResolver<C> {
             });
         }
 
+        // Fetch unlocked and lock only the commit, so no blocking lock is held across an
+        // await; a concurrent fetch that committed first wins.
+        let lock = self.cache.lock(&resolved_digest, reference)?;
+        if let Some(hit) = self.cache.try_hit(&resolved_digest, reference) {
+            return Ok(hit);
+        }
+        if let Some(layer) = manifest_layer
+            && layer.media_type != chosen_layer.media_type
+        {
+            self.cache
+                .write_manifest_layer(&resolved_digest, &layer.data, reference)?;
+        }
         let path = self.cache.write(
             &resolved_digest,
             &chosen_layer.media_type,
@@ -254,12 +361,10 @@ impl<C: RegistryClient> OciComponentResolver<C> {
             manifest_digest.clone(),
             manifest_wasm_name.as_deref(),
         )?;
-        if let Some(layer) = manifest_layer
-            && layer.media_type != chosen_layer.media_type
-        {
-            self.cache
-                .write_manifest_layer(&resolved_digest, &layer.data, reference)?;
-        }
+
+        drop(lock);
+        self.cache
+            .enforce(&self.opts.cache_policy, &resolved_digest);
 
         Ok(ResolvedComponent {
             original_reference: reference.to_string(),
@@ -268,6 +373,33 @@ impl<C: RegistryClient> OciComponentResolver<C> {
             path,
             fetched_from_network: true,
             manifest_digest,
+            overridden: false,
+        })
+    }
+
+    fn resolve_override(
+        &self,
+        reference: &str,
+        path: &Path,
+    ) -> Result<ResolvedComponent, OciComponentError> {
+        if self.opts.strict {
+            return Err(OciComponentError::OverrideRefused {
+                reference: reference.to_string(),
+                path: path.to_path_buf(),
+            });
+        }
+        let data = fs::read(path).map_err(|source| OciComponentError::Io {
+            reference: reference.to_string(),
+            source,
+        })?;
+        Ok(ResolvedComponent {
+            original_reference: reference.to_string(),
+            resolved_digest: compute_digest(&data),
+            media_type: "application/wasm".to_string(),
+            path: path.to_path_buf(),
+            fetched_from_network: false,
+            manifest_digest: None,
+            overridden: true,
         })
     }
 }
@@ -290,33 +422,6 @@ fn select_layer<'a>(
     Ok(&layers[0])
 }
 
-fn compute_digest(bytes: &[u8]) -> String {
-    let mut hasher = Sha256::new();
-    hasher.update(bytes);
-    format!("sha256:{:x}", hasher.finalize())
-}
-
-fn normalize_digest(digest: &str) -> String {
-    if digest.starts_with("sha256:") {
-        digest.to_string()
-    } else {
-        format!("sha256:{digest}")
-    }
-}
-
-pub(crate) fn default_cache_root() -> PathBuf {
-    if let Ok(root) = std::env::var("GREENTIC_DIST_CACHE_DIR") {
-        return PathBuf::from(root);
-    }
-    if let Some(cache) = dirs_next::cache_dir() {
-        return cache.join("greentic").join("components");
-    }
-    if let Ok(root) = std::env::var("GREENTIC_HOME") {
-        return PathBuf::from(root).join("cache").join("components");
-    }
-    PathBuf::from(".greentic").join("cache").join("components")
-}
-
 fn manifest_component_wasm_name(
     data: &[u8],
     reference: &str,
@@ -345,12 +450,14 @@ fn manifest_component_wasm_name(
 
 #[derive(Clone, Debug)]
 struct OciCache {
-    root: PathBuf,
+    store: BlobStore,
 }
 
 impl OciCache {
     fn new(root: PathBuf) -> Self {
-        Self { root }
+        Self {
+            store: BlobStore::new(root),
+        }
     }
 
     fn write_layer_data(
@@ -360,18 +467,13 @@ impl OciCache {
         data: &[u8],
         reference: &str,
     ) -> Result<PathBuf, OciComponentError> {
-        let dir = self.artifact_dir(digest);
-        fs::create_dir_all(&dir).map_err(|source| OciComponentError::Io {
-            reference: reference.to_string(),
-            source,
-        })?;
-
-        let artifact_path = self.artifact_path_for_media_type(digest, media_type, None);
-        fs::write(&artifact_path, data).map_err(|source| OciComponentError::Io {
-            reference: reference.to_string(),
-            source,
-        })?;
-        Ok(artifact_path)
+        let filename = artifact_filename(media_type, None);
+        self.store
+            .write_file(digest, filename, data)
+            .map_err(|source| OciComponentError::Io {
+                reference: reference.to_string(),
+                source,
+            })
     }
 
     fn write(
@@ -387,37 +489,34 @@ impl OciCache {
             self.write_layer_data(digest, media_type, data, reference)?
         } else if let Some(name) = manifest_wasm_name {
             let path = self.write_named_file(digest, name, data, reference)?;
-            if name != DEFAULT_WASM_FILENAME {
-                self.write_legacy_symlink(self.artifact_dir(digest).as_path(), name);
+            if name != DEFAULT_WASM_FILENAME
+                && let Ok(dir) = self.store.entry_dir(digest)
+            {
+                self.write_legacy_symlink(&dir, name);
             }
             path
         } else {
             self.write_layer_data(digest, media_type, data, reference)?
         };
-        let dir = self.artifact_dir(digest);
 
-        let metadata = CacheMetadata {
-            original_reference: reference.to_string(),
-            resolved_digest: digest.to_string(),
-            media_type: media_type.to_string(),
-            fetched_at_unix_seconds: SystemTime::now()
-                .duration_since(UNIX_EPOCH)
-                .unwrap_or_default()
-                .as_secs(),
-            size_bytes: data.len() as u64,
+        let metadata = StoreMetadata {
+            media_type: Some(media_type.to_string()),
             manifest_digest,
             manifest_wasm_name: manifest_wasm_name.map(|name| name.to_string()),
+            content_digest: Some(compute_digest(data)),
+            ..StoreMetadata::new(
+                ArtifactKind::Component,
+                reference,
+                digest,
+                data.len() as u64,
+            )
         };
-        let metadata_path = dir.join("metadata.json");
-        let buf =
-            serde_json::to_vec_pretty(&metadata).map_err(|source| OciComponentError::Serde {
+        self.store
+            .commit(digest, metadata)
+            .map_err(|source| OciComponentError::Io {
                 reference: reference.to_string(),
                 source,
             })?;
-        fs::write(&metadata_path, buf).map_err(|source| OciComponentError::Io {
-            reference: reference.to_string(),
-            source,
-        })?;
 
         Ok(artifact_path)
     }
@@ -429,17 +528,12 @@ impl OciCache {
         data: &[u8],
         reference: &str,
     ) -> Result<PathBuf, OciComponentError> {
-        let dir = self.artifact_dir(digest);
-        fs::create_dir_all(&dir).map_err(|source| OciComponentError::Io {
-            reference: reference.to_string(),
-            source,
-        })?;
-        let path = dir.join(filename);
-        fs::write(&path, data).map_err(|source| OciComponentError::Io {
-            reference: reference.to_string(),
-            source,
-        })?;
-        Ok(path)
+        self.store
+            .write_file(digest, filename, data)
+            .map_err(|source| OciComponentError::Io {
+                reference: reference.to_string(),
+                source,
+            })
     }
 
     fn write_manifest_layer(
@@ -451,60 +545,56 @@ impl OciCache {
         self.write_layer_data(digest, COMPONENT_MANIFEST_MEDIA_TYPE, data, reference)
     }
 
+    /// Cached entry for `digest`; the store's metadata is written last, so an entry
+    /// without it is an interrupted write and counts as a miss.
     fn try_hit(&self, digest: &str, reference: &str) -> Option<ResolvedComponent> {
-        let metadata = self.read_metadata(digest).ok();
+        let metadata = self.store.metadata(digest)?;
         let media_type = metadata
-            .as_ref()
-            .map(|m| m.media_type.clone())
+            .media_type
+            .clone()
             .unwrap_or_else(|| "application/octet-stream".to_string());
         let manifest_wasm_name = metadata
-            .as_ref()
-            .and_then(|m| m.manifest_wasm_name.clone())
+            .manifest_wasm_name
+            .clone()
             .or_else(|| self.manifest_wasm_name_from_cache(digest, reference));
-        let path =
-            self.artifact_path_for_media_type(digest, &media_type, manifest_wasm_name.as_deref());
+        let path = self.store.entry_dir(digest).ok()?.join(artifact_filename(
+            &media_type,
+            manifest_wasm_name.as_deref(),
+        ));
         if !path.exists() {
             return None;
         }
+        self.store.touch(digest);
         Some(ResolvedComponent {
             original_reference: reference.to_string(),
             resolved_digest: digest.to_string(),
             media_type,
             path,
             fetched_from_network: false,
-            manifest_digest: metadata.and_then(|m| m.manifest_digest),
+            manifest_digest: metadata.manifest_digest,
+            overridden: false,
         })
     }
 
-    fn read_metadata(&self, digest: &str) -> anyhow::Result<CacheMetadata> {
-        let metadata_path = self.metadata_path(digest);
-        let bytes = fs::read(metadata_path)?;
-        Ok(serde_json::from_slice(&bytes)?)
-    }
-
-    fn artifact_dir(&self, digest: &str) -> PathBuf {
-        self.root.join(trim_digest_prefix(digest))
+    fn enforce(&self, policy: &CachePolicy, written: &str) {
+        self.store.enforce(policy, written);
     }
 
-    fn artifact_path_for_media_type(
-        &self,
-        digest: &str,
-        media_type: &str,
-        manifest_wasm_name: Option<&str>,
-    ) -> PathBuf {
-        let dir = self.artifact_dir(digest);
-        let filename = if media_type == COMPONENT_MANIFEST_MEDIA_TYPE {
-            "component.manifest.json"
-        } else if let Some(name) = manifest_wasm_name {
-            name
-        } else {
-            DEFAULT_WASM_FILENAME
-        };
-        dir.join(filename)
+    fn lock(&self, digest: &str, reference: &str) -> Result<CacheLock, OciComponentError> {
+        self.store
+            .lock(digest)
+            .map_err(|source| OciComponentError::Io {
+                reference: reference.to_string(),
+                source,
+            })
     }
 
     fn manifest_wasm_name_from_cache(&self, digest: &str, reference: &str) -> Option<String> {
-        let path = self.artifact_dir(digest).join("component.manifest.json");
+        let path = self
+            .store
+            .entry_dir(digest)
+            .ok()?
+            .join("component.manifest.json");
         if !path.exists() {
             return None;
         }
@@ -529,16 +619,16 @@ impl OciCache {
             let _ = std::os::windows::fs::symlink_file(&target_path, &legacy_path);
         }
     }
-
-    fn metadata_path(&self, digest: &str) -> PathBuf {
-        self.artifact_dir(digest).join("metadata.json")
-    }
 }
 
-fn trim_digest_prefix(digest: &str) -> &str {
-    digest
-        .strip_prefix("sha256:")
-        .unwrap_or_else(|| digest.trim_start_matches('@'))
+fn artifact_filename<'a>(media_type: &str, manifest_wasm_name: Option<&'a str>) -> &'a str {
+    if media_type == COMPONENT_MANIFEST_MEDIA_TYPE {
+        "component.manifest.json"
+    } else if let Some(name) = manifest_wasm_name {
+        name
+    } else {
+        DEFAULT_WASM_FILENAME
+    }
 }
 
 #[derive(Clone, Debug)]
@@ -565,9 +655,51 @@ pub trait RegistryClient: Send + Sync {
         reference: &Reference,
         accepted_manifest_types: &[&str],
     ) -> Result<PulledImage, OciDistributionError>;
+
+    /// Pulls presenting `credentials`; clients without auth support ignore them.
+    async fn pull_with_auth(
+        &self,
+        reference: &Reference,
+        accepted_manifest_types: &[&str],
+        credentials: &RegistryCredentials,
+    ) -> Result<PulledImage, OciDistributionError> {
+        let _ = credentials;
+        self.pull(reference, accepted_manifest_types).await
+    }
+
+    /// Digest of the manifest `reference` points at. The default pulls the image; registry
+    /// clients should only fetch the manifest.
+    async fn fetch_manifest_digest(
+        &self,
+        reference: &Reference,
+        credentials: &RegistryCredentials,
+    ) -> Result<String, OciDistributionError> {
+        let image = self
+            .pull_with_auth(reference, DEFAULT_LAYER_MEDIA_TYPES, credentials)
+            .await?;
+        image.digest.ok_or_else(|| {
+            OciDistributionError::GenericError(Some(format!(
+                "registry returned no manifest digest for {}",
+                reference.whole()
+            )))
+        })
+    }
+
+    /// Every tag of `reference`'s repository. The default reports listing as unsupported.
+    async fn list_tags(
+        &self,
+        reference: &Reference,
+        credentials: &RegistryCredentials,
+    ) -> Result<Vec<String>, OciDistributionError> {
+        let _ = credentials;
+        Err(OciDistributionError::GenericError(Some(format!(
+            "tag listing is not supported for {}",
+            reference.whole()
+        ))))
+    }
 }
 
-/// Registry client backed by `oci-distribution` with HTTPS enforced and anonymous pulls.
+/// Registry client backed by `oci-distribution` with HTTPS enforced.
 #[derive(Clone)]
 pub struct DefaultRegistryClient {
     inner: Client,
@@ -579,33 +711,92 @@ impl Default for DefaultRegistryClient {
     }
 }
 
-#[async_trait]
-impl RegistryClient for DefaultRegistryClient {
-    fn default_client() -> Self {
+impl DefaultRegistryClient {
+    /// Client using `protocol`, e.g. `ClientProtocol::HttpsExcept` for a local registry.
+    pub fn with_protocol(protocol: ClientProtocol) -> Self {
         let config = ClientConfig {
-            protocol: ClientProtocol::Https,
+            protocol,
             ..Default::default()
         };
         Self {
             inner: Client::new(config),
         }
     }
+}
+
+#[async_trait]
+impl RegistryClient for DefaultRegistryClient {
+    fn default_client() -> Self {
+        Self::with_protocol(ClientProtocol::Https)
+    }
 
     async fn pull(
         &self,
         reference: &Reference,
         accepted_manifest_types: &[&str],
+    ) -> Result<PulledImage, OciDistributionError> {
+        self.pull_with_auth(
+            reference,
+            accepted_manifest_types,
+            &RegistryCredentials::Anonymous,
+        )
+        .await
+    }
+
+    async fn pull_with_auth(
+        &self,
+        reference: &Reference,
+        accepted_manifest_types: &[&str],
+        credentials: &RegistryCredentials,
     ) -> Result<PulledImage, OciDistributionError> {
         let image = self
             .inner
             .pull(
                 reference,
-                &RegistryAuth::Anonymous,
+                &credentials.to_oci(),
                 accepted_manifest_types.to_vec(),
             )
             .await?;
         Ok(convert_image(image))
     }
+
+    async fn fetch_manifest_digest(
+        &self,
+        reference: &Reference,
+        credentials: &RegistryCredentials,
+    ) -> Result<String, OciDistributionError> {
+        self.inner
+            .fetch_manifest_digest(reference, &credentials.to_oci())
+            .await
+    }
+
+    async fn list_tags(
+        &self,
+        reference: &Reference,
+        credentials: &RegistryCredentials,
+    ) -> Result<Vec<String>, OciDistributionError> {
+        const PAGE_SIZE: usize = 1000;
+        let auth = credentials.to_oci();
+        let mut tags: Vec<String> = Vec::new();
+        // Registries may cap pages below `n`, so a short page does not mean the list is done:
+        // keep asking for tags after the last one until a page comes back empty. A registry
+        // that ignores `last` repeats its page, which also ends the walk.
+        loop {
+            let page = self
+                .inner
+                .list_tags(
+                    reference,
+                    &auth,
+                    Some(PAGE_SIZE),
+                    tags.last().map(String::as_str),
+                )
+                .await?;
+            if page.tags.is_empty() || page.tags.last() == tags.last() {
+                return Ok(tags);
+            }
+            tags.extend(page.tags);
+        }
+    }
 }
 
 #[cfg(test)]
@@ -615,6 +806,31 @@ mod tests {
     const TEST_DIGEST: &str =
         "sha256:aaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaa";
 
+    #[test]
+    fn convert_image_reports_layer_digest_with_a_single_prefix() {
+        use oci_distribution::client::{Config, ImageLayer};
+
+        let image = ImageData {
+            layers: vec![ImageLayer::new(
+                b"layer-bytes".to_vec(),
+                "application/wasm".to_string(),
+                None,
+            )],
+            digest: Some(TEST_DIGEST.to_string()),
+            config: Config::oci_v1(Vec::new(), None),
+            manifest: None,
+        };
+
+        let pulled = convert_image(image);
+
+        // Regression: layer digests used to come out as `sha256:sha256:<hex>`.
+        assert_eq!(
+            pulled.layers[0].digest.as_deref(),
+            Some(compute_digest(b"layer-bytes").as_str())
+        );
+        assert_eq!(pulled.digest.as_deref(), Some(TEST_DIGEST));
+    }
+
     #[test]
     fn select_layer_prefers_wasm_over_manifest() {
         let layers = vec![
@@ -676,6 +892,32 @@ mod tests {
         assert!(wasm_path.exists());
     }
 
+    #[test]
+    fn cache_entry_without_metadata_is_a_miss() {
+        let temp = tempfile::tempdir().unwrap();
+        let cache = OciCache::new(temp.path().to_path_buf());
+        let reference = "ghcr.io/greentic/components@sha256:aaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaa";
+
+        // An interrupted write leaves the artifact without its metadata commit marker.
+        cache
+            .write_layer_data(TEST_DIGEST, "application/wasm", b"partial", reference)
+            .unwrap();
+        assert!(cache.try_hit(TEST_DIGEST, reference).is_none());
+
+        cache
+            .write(
+                TEST_DIGEST,
+                "application/wasm",
+                b"wasm-bytes",
+                reference,
+                None,
+                None,
+            )
+            .unwrap();
+        let hit = cache.try_hit(TEST_DIGEST, reference).unwrap();
+        assert_eq!(fs::read(hit.path).unwrap(), b"wasm-bytes");
+    }
+
     #[test]
     fn cache_writes_manifest_named_wasm_file() {
         let temp = tempfile::tempdir().unwrap();
@@ -710,8 +952,19 @@ mod tests {
             )
             .unwrap();
         assert!(wasm_path.exists());
-        assert!(cache.artifact_dir(digest).join(&manifest_name).exists());
-        let legacy_path = cache.artifact_dir(digest).join(DEFAULT_WASM_FILENAME);
+        assert!(
+            cache
+                .store
+                .entry_dir(digest)
+                .unwrap()
+                .join(&manifest_name)
+                .exists()
+        );
+        let legacy_path = cache
+            .store
+            .entry_dir(digest)
+            .unwrap()
+            .join(DEFAULT_WASM_FILENAME);
         if legacy_path.exists() {
             let metadata = fs::symlink_metadata(&legacy_path).unwrap();
             assert!(metadata.file_type().is_symlink());
@@ -826,7 +1079,7 @@ fn convert_image(image: ImageData) -> PulledImage {
         .layers
         .into_iter()
         .map(|layer| {
-            let digest = format!("sha256:{}", layer.sha256_digest());
+            let digest = layer.sha256_digest();
             PulledLayer {
                 media_type: layer.media_type,
                 data: layer.data,
@@ -889,4 +1142,11 @@ pub enum OciComponentError {
     },
     #[error("invalid component_wasm filename `{name}` in manifest for `{reference}`")]
     InvalidManifestWasmName { reference: String, name: String },
+    #[error("strict mode refuses local override {} for `{reference}`", path.display())]
+    OverrideRefused { reference: String, path: PathBuf },
+    #[error("no tag of `{repository}` matches version `{requirement}`")]
+    NoMatchingVersion {
+        repository: String,
+        requirement: String,
+    },
 }
@@ -1,6 +1,5 @@
 use std::fs;
 use std::path::PathBuf;
-use std::time::{SystemTime, UNIX_EPOCH};
 
 use async_trait::async_trait;
 use oci_distribution::Reference;
@@ -10,11 +9,16 @@ use oci_distribution::manifest::{
     IMAGE_MANIFEST_LIST_MEDIA_TYPE, IMAGE_MANIFEST_MEDIA_TYPE, OCI_IMAGE_INDEX_MEDIA_TYPE,
     OCI_IMAGE_MEDIA_TYPE,
 };
-use oci_distribution::secrets::RegistryAuth;
-use serde::{Deserialize, Serialize};
-use sha2::{Digest, Sha256};
 use thiserror::Error;
 
+use crate::cache_fs::CacheLock;
+use crate::cache_policy::CachePolicy;
+use crate::registry_auth::{RegistryAuthOptions, RegistryCredentials};
+use crate::store::{
+    ArtifactKind, BlobStore, StoreMetadata, compute_digest, default_store_root,
+    legacy_pack_cache_root, normalize_digest,
+};
+
 const OCI_ARTIFACT_MANIFEST_MEDIA_TYPE: &str = "application/vnd.oci.artifact.manifest.v1+json";
 const DOCKER_MANIFEST_MEDIA_TYPE: &str = "application/vnd.docker.distribution.manifest.v2+json";
 const DOCKER_MANIFEST_LIST_MEDIA_TYPE: &str =
@@ -53,6 +57,13 @@ pub struct PackFetchOptions {
     /// Allowed layer media types when pulling from registry.
     pub accepted_layer_media_types: Vec<String>,
     pub preferred_layer_media_types: Vec<String>,
+    /// Registry credential lookup for pulls.
+    pub auth: RegistryAuthOptions,
+    /// Size/age bounds enforced on `cache_dir` after each fetch.
+    pub cache_policy: CachePolicy,
+    /// Pack cache written by releases before the shared store; digest hits missing from
+    /// `cache_dir` are imported from it. `None` disables the fallback.
+    pub legacy_cache_dir: Option<PathBuf>,
 }
 
 impl Default for PackFetchOptions {
@@ -60,7 +71,7 @@ impl Default for PackFetchOptions {
         Self {
             allow_tags: false,
             offline: false,
-            cache_dir: default_cache_root(),
+            cache_dir: default_store_root(),
             accepted_manifest_types: DEFAULT_ACCEPTED_MANIFEST_TYPES
                 .iter()
                 .map(|s| s.to_string())
@@ -84,6 +95,9 @@ impl Default for PackFetchOptions {
                 PACK_LAYER_MEDIA_TYPE_PACK_ZIP.to_string(),
                 PACK_LAYER_MEDIA_TYPE_MARKDOWN.to_string(),
             ],
+            auth: RegistryAuthOptions::default(),
+            cache_policy: CachePolicy::default(),
+            legacy_cache_dir: Some(legacy_pack_cache_root()),
         }
     }
 }
@@ -99,17 +113,6 @@ pub struct ResolvedPack {
     pub manifest_digest: Option<String>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
-struct CacheMetadata {
-    original_reference: String,
-    resolved_digest: String,
-    media_type: String,
-    fetched_at_unix_seconds: u64,
-    size_bytes: u64,
-    #[serde(default)]
-    manifest_digest: Option<String>,
-}
-
 /// Fetch OCI packs with caching and offline support.
 pub struct OciPackFetcher<C: RegistryClient = DefaultRegistryClient> {
     client: C,
@@ -125,7 +128,7 @@ impl Default for OciPackFetcher<DefaultRegistryClient> {
 
 impl<C: RegistryClient> OciPackFetcher<C> {
     pub fn new(opts: PackFetchOptions) -> Self {
-        let cache = PackCache::new(opts.cache_dir.clone());
+        let cache = PackCache::new(opts.cache_dir.clone(), opts.legacy_cache_dir.clone());
         Self {
             client: C::default_client(),
             opts,
@@ -134,7 +137,7 @@ impl<C: RegistryClient> OciPackFetcher<C> {
     }
 
     pub fn with_client(client: C, opts: PackFetchOptions) -> Self {
-        let cache = PackCache::new(opts.cache_dir.clone());
+        let cache = PackCache::new(opts.cache_dir.clone(), opts.legacy_cache_dir.clone());
         Self {
             client,
             opts,
@@ -165,7 +168,11 @@ impl<C: RegistryClient> OciPackFetcher<C> {
 
         let expected_digest = parsed.digest().map(normalize_digest);
         if let Some(expected_digest) = expected_digest.as_ref() {
-            if let Some(hit) = self.cache.try_hit(expected_digest, reference) {
+            if let Some(hit) = self
+                .cache
+                .try_hit(expected_digest, reference)
+                .or_else(|| self.cache.import_legacy(expected_digest, reference))
+            {
                 return Ok(hit);
             }
             if self.opts.offline {
@@ -186,9 +193,10 @@ impl<C: RegistryClient> OciPackFetcher<C> {
             .iter()
             .map(|s| s.as_str())
             .collect::<Vec<_>>();
+        let credentials = self.opts.auth.resolve(parsed.resolve_registry());
         let image = self
             .client
-            .pull(&parsed, &accepted_layer_types)
+            .pull_with_auth(&parsed, &accepted_layer_types, &credentials)
             .await
             .map_err(|source| OciPackError::PullFailed {
                 reference: reference.to_string(),
@@ -217,6 +225,12 @@ impl<C: RegistryClient> OciPackFetcher<C> {
             });
         }
 
+        // Fetch unlocked and lock only the commit, so no blocking lock is held across an
+        // await; a concurrent fetch that committed first wins.
+        let lock = self.cache.lock(&resolved_digest, reference)?;
+        if let Some(hit) = self.cache.try_hit(&resolved_digest, reference) {
+            return Ok(hit);
+        }
         let path = self.cache.write(
             &resolved_digest,
             &chosen_layer.media_type,
@@ -225,6 +239,10 @@ impl<C: RegistryClient> OciPackFetcher<C> {
             manifest_digest.clone(),
         )?;
 
+        drop(lock);
+        self.cache
+            .enforce(&self.opts.cache_policy, &resolved_digest);
+
         Ok(ResolvedPack {
             original_reference: reference.to_string(),
             resolved_digest,
@@ -262,41 +280,20 @@ fn select_layer<'a>(
     Ok(&layers[0])
 }
 
-fn compute_digest(bytes: &[u8]) -> String {
-    let mut hasher = Sha256::new();
-    hasher.update(bytes);
-    format!("sha256:{:x}", hasher.finalize())
-}
-
-fn normalize_digest(digest: &str) -> String {
-    if digest.starts_with("sha256:") {
-        digest.to_string()
-    } else {
-        format!("sha256:{digest}")
-    }
-}
-
-pub(crate) fn default_cache_root() -> PathBuf {
-    if let Ok(root) = std::env::var("GREENTIC_PACK_CACHE_DIR") {
-        return PathBuf::from(root);
-    }
-    if let Some(cache) = dirs_next::cache_dir() {
-        return cache.join("greentic").join("packs");
-    }
-    if let Ok(root) = std::env::var("GREENTIC_HOME") {
-        return PathBuf::from(root).join("cache").join("packs");
-    }
-    PathBuf::from(".greentic").join("cache").join("packs")
-}
-
 #[derive(Clone, Debug)]
 struct PackCache {
-    root: PathBuf,
+    store: BlobStore,
+    legacy: Option<BlobStore>,
 }
 
 impl PackCache {
-    fn new(root: PathBuf) -> Self {
-        Self { root }
+    fn new(root: PathBuf, legacy_root: Option<PathBuf>) -> Self {
+        Self {
+            legacy: legacy_root
+                .filter(|legacy| legacy != &root)
+                .map(BlobStore::new),
+            store: BlobStore::new(root),
+        }
     }
 
     fn write(
@@ -307,76 +304,83 @@ impl PackCache {
         reference: &str,
         manifest_digest: Option<String>,
     ) -> Result<PathBuf, OciPackError> {
-        let dir = self.artifact_dir(digest);
-        fs::create_dir_all(&dir).map_err(|source| OciPackError::Io {
-            reference: reference.to_string(),
-            source,
-        })?;
-        let pack_path = dir.join(PACK_FILENAME);
-        fs::write(&pack_path, data).map_err(|source| OciPackError::Io {
+        let io_err = |source| OciPackError::Io {
             reference: reference.to_string(),
             source,
-        })?;
-
-        let metadata = CacheMetadata {
-            original_reference: reference.to_string(),
-            resolved_digest: digest.to_string(),
-            media_type: media_type.to_string(),
-            fetched_at_unix_seconds: SystemTime::now()
-                .duration_since(UNIX_EPOCH)
-                .unwrap_or_default()
-                .as_secs(),
-            size_bytes: data.len() as u64,
+        };
+        let pack_path = self
+            .store
+            .write_file(digest, PACK_FILENAME, data)
+            .map_err(io_err)?;
+        let metadata = StoreMetadata {
+            media_type: Some(media_type.to_string()),
             manifest_digest,
+            content_digest: Some(compute_digest(data)),
+            ..StoreMetadata::new(ArtifactKind::Pack, reference, digest, data.len() as u64)
         };
-        let metadata_path = dir.join("metadata.json");
-        let buf = serde_json::to_vec_pretty(&metadata).map_err(|source| OciPackError::Serde {
-            reference: reference.to_string(),
-            source,
-        })?;
-        fs::write(&metadata_path, buf).map_err(|source| OciPackError::Io {
-            reference: reference.to_string(),
-            source,
-        })?;
-
+        self.store.commit(digest, metadata).map_err(io_err)?;
         Ok(pack_path)
     }
 
+    /// Cached entry for `digest`; the store's metadata is the commit marker written last.
     fn try_hit(&self, digest: &str, reference: &str) -> Option<ResolvedPack> {
-        let metadata = self.read_metadata(digest).ok();
-        let media_type = metadata
-            .as_ref()
-            .map(|m| m.media_type.clone())
-            .unwrap_or_else(|| "application/octet-stream".to_string());
-        let path = self.artifact_dir(digest).join(PACK_FILENAME);
+        let metadata = self.store.metadata(digest)?;
+        let path = self.store.entry_dir(digest).ok()?.join(PACK_FILENAME);
         if !path.exists() {
             return None;
         }
+        self.store.touch(digest);
         Some(ResolvedPack {
             original_reference: reference.to_string(),
             resolved_digest: digest.to_string(),
-            media_type,
+            media_type: metadata
+                .media_type
+                .unwrap_or_else(|| "application/octet-stream".to_string()),
             path,
             fetched_from_network: false,
-            manifest_digest: metadata.and_then(|m| m.manifest_digest),
+            manifest_digest: metadata.manifest_digest,
         })
     }
 
-    fn read_metadata(&self, digest: &str) -> anyhow::Result<CacheMetadata> {
-        let metadata_path = self.artifact_dir(digest).join("metadata.json");
-        let bytes = fs::read(metadata_path)?;
-        Ok(serde_json::from_slice(&bytes)?)
+    /// Copies an entry of the pre-store pack cache (same `<sha256>/pack.gtpack` layout)
+    /// into the store, so packs cached by older releases stay available offline.
+    /// Best effort: any failure falls back to a normal fetch.
+    fn import_legacy(&self, digest: &str, reference: &str) -> Option<ResolvedPack> {
+        let legacy = self.legacy.as_ref()?;
+        let metadata = legacy.metadata(digest)?;
+        let data = fs::read(legacy.entry_dir(digest).ok()?.join(PACK_FILENAME)).ok()?;
+        let _lock = self.store.lock(digest).ok()?;
+        if let Some(hit) = self.try_hit(digest, reference) {
+            return Some(hit);
+        }
+        let original_reference = match metadata.original_reference.as_str() {
+            "" => reference,
+            original => original,
+        };
+        self.write(
+            digest,
+            metadata
+                .media_type
+                .as_deref()
+                .unwrap_or("application/octet-stream"),
+            &data,
+            original_reference,
+            metadata.manifest_digest,
+        )
+        .ok()?;
+        self.try_hit(digest, reference)
     }
 
-    fn artifact_dir(&self, digest: &str) -> PathBuf {
-        self.root.join(trim_digest_prefix(digest))
+    fn enforce(&self, policy: &CachePolicy, written: &str) {
+        self.store.enforce(policy, written);
     }
-}
 
-fn trim_digest_prefix(digest: &str) -> &str {
-    digest
-        .strip_prefix("sha256:")
-        .unwrap_or_else(|| digest.trim_start_matches('@'))
+    fn lock(&self, digest: &str, reference: &str) -> Result<CacheLock, OciPackError> {
+        self.store.lock(digest).map_err(|source| OciPackError::Io {
+            reference: reference.to_string(),
+            source,
+        })
+    }
 }
 
 #[derive(Clone, Debug)]
@@ -403,9 +407,20 @@ pub trait RegistryClient: Send + Sync {
         reference: &Reference,
         accepted_manifest_types: &[&str],
     ) -> Result<PulledImage, OciDistributionError>;
+
+    /// Pulls presenting `credentials`; clients without auth support ignore them.
+    async fn pull_with_auth(
+        &self,
+        reference: &Reference,
+        accepted_manifest_types: &[&str],
+        credentials: &RegistryCredentials,
+    ) -> Result<PulledImage, OciDistributionError> {
+        let _ = credentials;
+        self.pull(reference, accepted_manifest_types).await
+    }
 }
 
-/// Registry client backed by `oci-distribution` with HTTPS enforced and anonymous pulls.
+/// Registry client backed by `oci-distribution` with HTTPS enforced.
 #[derive(Clone)]
 pub struct DefaultRegistryClient {
     inner: Client,
@@ -417,28 +432,49 @@ impl Default for DefaultRegistryClient {
     }
 }
 
-#[async_trait]
-impl RegistryClient for DefaultRegistryClient {
-    fn default_client() -> Self {
+impl DefaultRegistryClient {
+    /// Client using `protocol`, e.g. `ClientProtocol::HttpsExcept` for a local registry.
+    pub fn with_protocol(protocol: ClientProtocol) -> Self {
         let config = ClientConfig {
-            protocol: ClientProtocol::Https,
+            protocol,
             ..Default::default()
         };
         Self {
             inner: Client::new(config),
         }
     }
+}
+
+#[async_trait]
+impl RegistryClient for DefaultRegistryClient {
+    fn default_client() -> Self {
+        Self::with_protocol(ClientProtocol::Https)
+    }
 
     async fn pull(
         &self,
         reference: &Reference,
         accepted_manifest_types: &[&str],
+    ) -> Result<PulledImage, OciDistributionError> {
+        self.pull_with_auth(
+            reference,
+            accepted_manifest_types,
+            &RegistryCredentials::Anonymous,
+        )
+        .await
+    }
+
+    async fn pull_with_auth(
+        &self,
+        reference: &Reference,
+        accepted_manifest_types: &[&str],
+        credentials: &RegistryCredentials,
     ) -> Result<PulledImage, OciDistributionError> {
         let image = self
             .inner
             .pull(
                 reference,
-                &RegistryAuth::Anonymous,
+                &credentials.to_oci(),
                 accepted_manifest_types.to_vec(),
             )
             .await?;
@@ -451,7 +487,7 @@ fn convert_image(image: ImageData) -> PulledImage {
         .layers
         .into_iter()
         .map(|layer| {
-            let digest = format!("sha256:{}", layer.sha256_digest());
+            let digest = layer.sha256_digest();
             PulledLayer {
                 media_type: layer.media_type,
                 data: layer.data,
@@ -502,3 +538,36 @@ pub enum OciPackError {
         source: serde_json::Error,
     },
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TEST_DIGEST: &str =
+        "sha256:aaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaa";
+
+    #[test]
+    fn convert_image_reports_layer_digest_with_a_single_prefix() {
+        use oci_distribution::client::{Config, ImageLayer};
+
+        let image = ImageData {
+            layers: vec![ImageLayer::new(
+                b"layer-bytes".to_vec(),
+                "application/wasm".to_string(),
+                None,
+            )],
+            digest: Some(TEST_DIGEST.to_string()),
+            config: Config::oci_v1(Vec::new(), None),
+            manifest: None,
+        };
+
+        let pulled = convert_image(image);
+
+        // Regression: layer digests used to come out as `sha256:sha256:<hex>`.
+        assert_eq!(
+            pulled.layers[0].digest.as_deref(),
+            Some(compute_digest(b"layer-bytes").as_str())
+        );
+        assert_eq!(pulled.digest.as_deref(), Some(TEST_DIGEST));
+    }
+}
@@ -0,0 +1,136 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use oci_distribution::Reference;
+use serde::Deserialize;
+use thiserror::Error;
+
+/// Environment variable naming the overrides file picked up by `DistOptions::default()`.
+pub const OVERRIDES_ENV: &str = "GREENTIC_DIST_OVERRIDES";
+
+/// Environment variable (`1`) enabling strict mode, in which overrides are refused.
+pub const STRICT_ENV: &str = "GREENTIC_DIST_STRICT";
+
+/// Local development overrides: component references served from a local file instead.
+///
+/// Keys are matched against a reference in order:
+/// 1. the exact reference string (`ghcr.io/greentic/echo@sha256:...`),
+/// 2. for OCI references, the repository without tag or digest (`ghcr.io/greentic/echo`),
+/// 3. for OCI references, the last repository segment, i.e. the component id (`echo`).
+///
+/// The on-disk format is JSON: `{"overrides": {"<ref or id>": "<path>"}}`. Relative paths
+/// are resolved against the directory containing the file.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ComponentOverrides {
+    entries: BTreeMap<String, PathBuf>,
+}
+
+#[derive(Deserialize)]
+struct OverridesFile {
+    #[serde(default)]
+    overrides: BTreeMap<String, PathBuf>,
+}
+
+impl ComponentOverrides {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn load(path: &Path) -> Result<Self, OverridesError> {
+        let bytes = fs::read(path).map_err(|source| OverridesError::Read {
+            path: path.to_path_buf(),
+            source,
+        })?;
+        let file: OverridesFile =
+            serde_json::from_slice(&bytes).map_err(|source| OverridesError::Parse {
+                path: path.to_path_buf(),
+                source,
+            })?;
+        let base = path.parent().unwrap_or_else(|| Path::new(""));
+        Ok(Self {
+            entries: file
+                .overrides
+                .into_iter()
+                .map(|(key, target)| (key, base.join(target)))
+                .collect(),
+        })
+    }
+
+    pub fn insert(&mut self, key: impl Into<String>, path: impl Into<PathBuf>) {
+        self.entries.insert(key.into(), path.into());
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Local path overriding `reference`, if any.
+    pub fn lookup(&self, reference: &str) -> Option<&Path> {
+        if let Some(path) = self.entries.get(reference) {
+            return Some(path);
+        }
+        let trimmed = reference.strip_prefix("oci://").unwrap_or(reference);
+        Reference::try_from(trimmed).ok()?;
+        let repository = strip_tag_and_digest(trimmed);
+        let component_id = repository.rsplit('/').next().unwrap_or(repository);
+        self.entries
+            .get(repository)
+            .or_else(|| self.entries.get(component_id))
+            .map(PathBuf::as_path)
+    }
+}
+
+fn strip_tag_and_digest(reference: &str) -> &str {
+    let without_digest = reference
+        .split_once('@')
+        .map_or(reference, |(repo, _)| repo);
+    match without_digest.rfind(':') {
+        Some(idx) if !without_digest[idx..].contains('/') => &without_digest[..idx],
+        _ => without_digest,
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum OverridesError {
+    #[error("failed to read overrides file {}: {source}", path.display())]
+    Read {
+        path: PathBuf,
+        #[source]
+        source: std::io::Error,
+    },
+    #[error("invalid overrides file {}: {source}", path.display())]
+    Parse {
+        path: PathBuf,
+        #[source]
+        source: serde_json::Error,
+    },
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn matches_exact_repository_and_component_id() {
+        let mut overrides = ComponentOverrides::new();
+        overrides.insert("ghcr.io/greentic/echo:1.0.0", "/exact.wasm");
+        overrides.insert("ghcr.io/greentic/router", "/router.wasm");
+        overrides.insert("state", "/state.wasm");
+
+        assert_eq!(
+            overrides.lookup("ghcr.io/greentic/echo:1.0.0"),
+            Some(Path::new("/exact.wasm"))
+        );
+        assert_eq!(overrides.lookup("ghcr.io/greentic/echo:2.0.0"), None);
+        assert_eq!(
+            overrides.lookup("oci://ghcr.io/greentic/router@sha256:0123456789abcdef0123456789abcdef0123456789abcdef0123456789abcdef"),
+            Some(Path::new("/router.wasm"))
+        );
+        assert_eq!(
+            overrides.lookup("localhost:5000/greentic/state:latest"),
+            Some(Path::new("/state.wasm"))
+        );
+        assert_eq!(overrides.lookup("/tmp/state"), None);
+    }
+}
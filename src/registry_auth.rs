@@ -0,0 +1,514 @@
+use std::collections::BTreeMap;
+use std::ffi::{OsStr, OsString};
+use std::fs;
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD;
+use oci_distribution::secrets::RegistryAuth;
+use serde::Deserialize;
+
+/// Username Docker uses for token credentials.
+const TOKEN_USERNAME: &str = "<token>";
+
+/// How long a credential helper may run before it is killed.
+pub const DEFAULT_HELPER_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Credentials presented to an OCI registry.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub enum RegistryCredentials {
+    #[default]
+    Anonymous,
+    Basic {
+        username: String,
+        password: String,
+    },
+    /// Access or identity token (GHCR PAT, Harbor robot token, Docker `identitytoken`).
+    ///
+    /// This is not bearer auth: the registry protocol client only speaks basic auth, so the
+    /// token is sent as the password of the token exchange with Docker's `<token>`
+    /// username. Registries that only accept a pre-issued `Authorization: Bearer` token
+    /// reject it with 401.
+    IdentityToken(String),
+}
+
+impl RegistryCredentials {
+    pub fn is_anonymous(&self) -> bool {
+        matches!(self, RegistryCredentials::Anonymous)
+    }
+
+    pub(crate) fn to_oci(&self) -> RegistryAuth {
+        match self {
+            RegistryCredentials::Anonymous => RegistryAuth::Anonymous,
+            RegistryCredentials::Basic { username, password } => {
+                RegistryAuth::Basic(username.clone(), password.clone())
+            }
+            RegistryCredentials::IdentityToken(token) => {
+                RegistryAuth::Basic(TOKEN_USERNAME.to_string(), token.clone())
+            }
+        }
+    }
+}
+
+/// Where credentials for a registry are looked up.
+///
+/// Sources are consulted in order and the first match wins:
+/// 1. `credentials`, keyed by registry host (`ghcr.io`, `localhost:5000`),
+/// 2. `GREENTIC_REGISTRY_<HOST>_TOKEN`, or `_USERNAME` plus `_PASSWORD`, where `<HOST>` is
+///    the registry upper-cased with every other character replaced by `_` (`GHCR_IO`),
+/// 3. the `auths` section of `credentials_store` (written by `greentic-dist auth login`),
+/// 4. the Docker config (`docker_config`, else `$DOCKER_CONFIG/config.json`, else
+///    `~/.docker/config.json`): a `credHelpers` entry for the registry, then its `auths`
+///    entry, then the `credsStore` helper. Helpers are run as `docker-credential-<name> get`
+///    from `helper_path` (else `PATH`) with the registry on stdin, as Docker does, and are
+///    killed if they have not answered within `helper_timeout`.
+///
+/// Docker `registrytoken` entries are pre-issued bearer tokens, which the registry client
+/// cannot present; they are skipped with a warning rather than sent as identity tokens.
+///
+/// Anything else is pulled anonymously.
+#[derive(Clone, Debug)]
+pub struct RegistryAuthOptions {
+    pub credentials: BTreeMap<String, RegistryCredentials>,
+    pub use_env: bool,
+    pub credentials_store: Option<PathBuf>,
+    pub use_docker_config: bool,
+    pub docker_config: Option<PathBuf>,
+    /// Directories searched for `docker-credential-*` helpers, in `PATH` syntax; `None`
+    /// searches `PATH`.
+    pub helper_path: Option<OsString>,
+    pub helper_timeout: Duration,
+}
+
+impl Default for RegistryAuthOptions {
+    fn default() -> Self {
+        Self {
+            credentials: BTreeMap::new(),
+            use_env: true,
+            credentials_store: None,
+            use_docker_config: true,
+            docker_config: None,
+            helper_path: None,
+            helper_timeout: DEFAULT_HELPER_TIMEOUT,
+        }
+    }
+}
+
+impl RegistryAuthOptions {
+    /// Options that never look up credentials.
+    pub fn anonymous() -> Self {
+        Self {
+            credentials: BTreeMap::new(),
+            use_env: false,
+            credentials_store: None,
+            use_docker_config: false,
+            docker_config: None,
+            helper_path: None,
+            helper_timeout: DEFAULT_HELPER_TIMEOUT,
+        }
+    }
+
+    pub fn with_credentials(
+        mut self,
+        registry: impl Into<String>,
+        credentials: RegistryCredentials,
+    ) -> Self {
+        self.credentials
+            .insert(normalize_registry(&registry.into()), credentials);
+        self
+    }
+
+    /// Credentials for `registry` (as returned by `Reference::resolve_registry`).
+    pub fn resolve(&self, registry: &str) -> RegistryCredentials {
+        self.resolve_with(registry, |key| std::env::var(key).ok())
+    }
+
+    fn resolve_with(
+        &self,
+        registry: &str,
+        env: impl Fn(&str) -> Option<String>,
+    ) -> RegistryCredentials {
+        let registry = normalize_registry(registry);
+        if let Some(credentials) = self.credentials.get(&registry) {
+            return credentials.clone();
+        }
+        if self.use_env
+            && let Some(credentials) = env_credentials(&registry, env)
+        {
+            return credentials;
+        }
+        let helpers = Helpers {
+            path: self.helper_path.as_deref(),
+            timeout: self.helper_timeout,
+        };
+        if let Some(path) = &self.credentials_store
+            && let Some(credentials) = docker_config_credentials(path, &registry, &helpers)
+        {
+            return credentials;
+        }
+        if self.use_docker_config
+            && let Some(path) = self.docker_config_path()
+            && let Some(credentials) = docker_config_credentials(&path, &registry, &helpers)
+        {
+            return credentials;
+        }
+        RegistryCredentials::Anonymous
+    }
+
+    fn docker_config_path(&self) -> Option<PathBuf> {
+        if let Some(path) = &self.docker_config {
+            return Some(path.clone());
+        }
+        if let Some(dir) = std::env::var_os("DOCKER_CONFIG") {
+            return Some(PathBuf::from(dir).join("config.json"));
+        }
+        dirs_next::home_dir().map(|home| home.join(".docker").join("config.json"))
+    }
+}
+
+/// Env var prefix for `registry`, e.g. `GREENTIC_REGISTRY_GHCR_IO`.
+pub fn registry_env_prefix(registry: &str) -> String {
+    let host: String = normalize_registry(registry)
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() {
+                c.to_ascii_uppercase()
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    format!("GREENTIC_REGISTRY_{host}")
+}
+
+fn env_credentials(
+    registry: &str,
+    env: impl Fn(&str) -> Option<String>,
+) -> Option<RegistryCredentials> {
+    let prefix = registry_env_prefix(registry);
+    let var = |suffix: &str| env(&format!("{prefix}_{suffix}")).filter(|v| !v.is_empty());
+    if let Some(token) = var("TOKEN") {
+        return Some(RegistryCredentials::IdentityToken(token));
+    }
+    Some(RegistryCredentials::Basic {
+        username: var("USERNAME")?,
+        password: var("PASSWORD")?,
+    })
+}
+
+#[derive(Deserialize)]
+struct DockerConfig {
+    #[serde(default)]
+    auths: BTreeMap<String, DockerAuthEntry>,
+    #[serde(default, rename = "credHelpers")]
+    cred_helpers: BTreeMap<String, String>,
+    #[serde(default, rename = "credsStore")]
+    creds_store: Option<String>,
+}
+
+/// `docker-credential-<name> get` output.
+#[derive(Deserialize)]
+struct HelperCredentials {
+    #[serde(rename = "Username")]
+    username: String,
+    #[serde(rename = "Secret")]
+    secret: String,
+}
+
+#[derive(Deserialize)]
+struct DockerAuthEntry {
+    #[serde(default)]
+    auth: Option<String>,
+    #[serde(default)]
+    username: Option<String>,
+    #[serde(default)]
+    password: Option<String>,
+    #[serde(default)]
+    identitytoken: Option<String>,
+    #[serde(default)]
+    registrytoken: Option<String>,
+}
+
+/// Where and how long credential helpers are run.
+struct Helpers<'a> {
+    path: Option<&'a OsStr>,
+    timeout: Duration,
+}
+
+fn docker_config_credentials(
+    path: &Path,
+    registry: &str,
+    helpers: &Helpers<'_>,
+) -> Option<RegistryCredentials> {
+    let bytes = fs::read(path).ok()?;
+    let config: DockerConfig = serde_json::from_slice(&bytes).ok()?;
+    if let Some((_, helper)) = config
+        .cred_helpers
+        .iter()
+        .find(|(key, _)| normalize_registry(key) == registry)
+    {
+        return helper_credentials(helper, registry, helpers);
+    }
+    let entry = config
+        .auths
+        .into_iter()
+        .find(|(key, _)| is_registry_key(key) && normalize_registry(key) == registry)
+        .and_then(|(_, entry)| {
+            if entry.registrytoken.is_some() {
+                eprintln!(
+                    "warning: ignoring `registrytoken` for {registry} in {}: bearer registry tokens are not supported; use `identitytoken` or a username and password",
+                    path.display()
+                );
+            }
+            auth_entry_credentials(entry)
+        });
+    if entry.is_some() {
+        return entry;
+    }
+    helper_credentials(config.creds_store.as_deref()?, registry, helpers)
+}
+
+fn auth_entry_credentials(entry: DockerAuthEntry) -> Option<RegistryCredentials> {
+    if let Some(token) = entry.identitytoken {
+        return Some(RegistryCredentials::IdentityToken(token));
+    }
+    if let (Some(username), Some(password)) = (entry.username, entry.password) {
+        return Some(RegistryCredentials::Basic { username, password });
+    }
+    let decoded = STANDARD.decode(entry.auth?.trim()).ok()?;
+    let (username, password) = String::from_utf8(decoded)
+        .ok()?
+        .split_once(':')
+        .map(|(username, password)| (username.to_string(), password.to_string()))?;
+    Some(RegistryCredentials::Basic { username, password })
+}
+
+/// Runs the Docker credential helper protocol; helper failures mean "no credentials".
+fn helper_credentials(
+    helper: &str,
+    registry: &str,
+    helpers: &Helpers<'_>,
+) -> Option<RegistryCredentials> {
+    // Docker Hub logins are stored under the legacy index URL.
+    let server = if registry == "index.docker.io" {
+        "https://index.docker.io/v1/"
+    } else {
+        registry
+    };
+    let program = format!("docker-credential-{helper}");
+    let program = match helpers.path {
+        Some(dirs) => std::env::split_paths(dirs)
+            .map(|dir| dir.join(format!("{program}{}", std::env::consts::EXE_SUFFIX)))
+            .find(|candidate| candidate.is_file())?,
+        None => PathBuf::from(program),
+    };
+    let stdout = run_helper(&program, server, helpers.timeout)?;
+    let found: HelperCredentials = serde_json::from_slice(&stdout).ok()?;
+    if found.secret.is_empty() {
+        return None;
+    }
+    if found.username == TOKEN_USERNAME {
+        return Some(RegistryCredentials::IdentityToken(found.secret));
+    }
+    Some(RegistryCredentials::Basic {
+        username: found.username,
+        password: found.secret,
+    })
+}
+
+/// Runs `<program> get` and returns its stdout if it succeeds within `timeout`; a helper
+/// still running at the deadline is killed.
+fn run_helper(program: &Path, server: &str, timeout: Duration) -> Option<Vec<u8>> {
+    let mut child = Command::new(program)
+        .arg("get")
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()
+        .ok()?;
+    // Dropping stdin right away lets the helper see EOF after the server name.
+    let sent = child
+        .stdin
+        .take()
+        .is_some_and(|mut stdin| stdin.write_all(server.as_bytes()).is_ok());
+    // Read stdout on the side so a chatty helper cannot block on a full pipe.
+    let mut stdout = child.stdout.take()?;
+    let reader = thread::spawn(move || {
+        let mut buf = Vec::new();
+        stdout.read_to_end(&mut buf).map(|_| buf)
+    });
+    let deadline = Instant::now() + timeout;
+    let status = loop {
+        match child.try_wait() {
+            Ok(Some(status)) => break status,
+            Ok(None) if Instant::now() < deadline => thread::sleep(Duration::from_millis(10)),
+            _ => {
+                let _ = child.kill();
+                let _ = child.wait();
+                // Grandchildren may still hold stdout open; leave the reader behind.
+                return None;
+            }
+        }
+    };
+    let stdout = reader.join().ok()?.ok()?;
+    (sent && status.success()).then_some(stdout)
+}
+
+/// Skips non-registry entries such as `store://` logins sharing the file.
+pub(crate) fn is_registry_key(key: &str) -> bool {
+    key.split_once("://")
+        .is_none_or(|(scheme, _)| matches!(scheme, "http" | "https"))
+}
+
+/// Reduces Docker config keys (`https://index.docker.io/v1/`) and registry names to a host.
+pub(crate) fn normalize_registry(registry: &str) -> String {
+    let host = registry
+        .split_once("://")
+        .map_or(registry, |(_, rest)| rest)
+        .split('/')
+        .next()
+        .unwrap_or_default()
+        .to_ascii_lowercase();
+    match host.as_str() {
+        "docker.io" | "registry-1.docker.io" => "index.docker.io".to_string(),
+        _ => host,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn no_env(_: &str) -> Option<String> {
+        None
+    }
+
+    #[test]
+    fn explicit_then_env_then_docker_config() {
+        let temp = tempfile::tempdir().unwrap();
+        let config = temp.path().join("config.json");
+        fs::write(
+            &config,
+            format!(
+                r#"{{"auths": {{
+                    "https://ghcr.io": {{"auth": "{}"}},
+                    "https://index.docker.io/v1/": {{"identitytoken": "hub-token"}}
+                }}}}"#,
+                STANDARD.encode("docker-user:docker-pass")
+            ),
+        )
+        .unwrap();
+        let opts = RegistryAuthOptions {
+            docker_config: Some(config),
+            ..Default::default()
+        };
+
+        assert_eq!(
+            opts.resolve_with("ghcr.io", no_env),
+            RegistryCredentials::Basic {
+                username: "docker-user".into(),
+                password: "docker-pass".into()
+            }
+        );
+        assert_eq!(
+            opts.resolve_with("docker.io", no_env),
+            RegistryCredentials::IdentityToken("hub-token".into())
+        );
+        assert_eq!(
+            opts.resolve_with("quay.io", no_env),
+            RegistryCredentials::Anonymous
+        );
+
+        let env = |key: &str| (key == "GREENTIC_REGISTRY_GHCR_IO_TOKEN").then(|| "env".into());
+        assert_eq!(
+            opts.resolve_with("ghcr.io", env),
+            RegistryCredentials::IdentityToken("env".into())
+        );
+
+        let opts = opts.with_credentials(
+            "GHCR.io",
+            RegistryCredentials::IdentityToken("explicit".into()),
+        );
+        assert_eq!(
+            opts.resolve_with("ghcr.io", env),
+            RegistryCredentials::IdentityToken("explicit".into())
+        );
+    }
+
+    #[test]
+    fn credentials_store_precedes_docker_config() {
+        let temp = tempfile::tempdir().unwrap();
+        let store = temp.path().join("credentials.json");
+        fs::write(
+            &store,
+            r#"{"auths": {
+                "ghcr.io": {"identitytoken": "stored"},
+                "store://acme": {"identitytoken": "not-a-registry"}
+            }}"#,
+        )
+        .unwrap();
+        let opts = RegistryAuthOptions {
+            credentials_store: Some(store),
+            use_docker_config: false,
+            ..Default::default()
+        };
+
+        assert_eq!(
+            opts.resolve_with("ghcr.io", no_env),
+            RegistryCredentials::IdentityToken("stored".into())
+        );
+        assert_eq!(
+            opts.resolve_with("acme", no_env),
+            RegistryCredentials::Anonymous
+        );
+    }
+
+    #[test]
+    fn registry_tokens_are_not_sent_as_identity_tokens() {
+        let temp = tempfile::tempdir().unwrap();
+        let config = temp.path().join("config.json");
+        fs::write(
+            &config,
+            format!(
+                r#"{{"auths": {{
+                    "ghcr.io": {{"registrytoken": "bearer-only"}},
+                    "quay.io": {{"registrytoken": "bearer-only", "auth": "{}"}}
+                }}}}"#,
+                STANDARD.encode("quay-user:quay-pass")
+            ),
+        )
+        .unwrap();
+        let opts = RegistryAuthOptions {
+            docker_config: Some(config),
+            ..Default::default()
+        };
+
+        assert_eq!(
+            opts.resolve_with("ghcr.io", no_env),
+            RegistryCredentials::Anonymous
+        );
+        assert_eq!(
+            opts.resolve_with("quay.io", no_env),
+            RegistryCredentials::Basic {
+                username: "quay-user".into(),
+                password: "quay-pass".into()
+            }
+        );
+    }
+
+    #[test]
+    fn env_prefix_uses_normalized_host() {
+        assert_eq!(
+            registry_env_prefix("localhost:5000"),
+            "GREENTIC_REGISTRY_LOCALHOST_5000"
+        );
+        assert_eq!(
+            registry_env_prefix("https://index.docker.io/v1/"),
+            "GREENTIC_REGISTRY_INDEX_DOCKER_IO"
+        );
+    }
+}
@@ -0,0 +1,234 @@
+use std::fs;
+use std::future::Future;
+use std::time::Duration;
+
+use oci_distribution::errors::OciDistributionError;
+
+use crate::oci_components::{
+    ComponentResolveOptions, ComponentsExtension, ComponentsMode, OciComponentError,
+    OciComponentResolver,
+};
+use crate::oci_packs::{OciPackError, OciPackFetcher, PackFetchOptions};
+use crate::source::{DistributorSource, LookupCancellation};
+use crate::source_config::expand_template;
+use crate::{ComponentId, DistributorError, PackId, Version};
+
+/// Source that maps ids/versions onto OCI references through templates.
+///
+/// Tags are allowed because templates usually end in `:{version}`; artifacts are cached
+/// in the regular OCI pack/component caches.
+pub struct OciTemplateSource {
+    pack_template: Option<String>,
+    component_template: Option<String>,
+}
+
+impl OciTemplateSource {
+    pub fn new(pack_template: Option<String>, component_template: Option<String>) -> Self {
+        Self {
+            pack_template,
+            component_template,
+        }
+    }
+
+    fn pack_reference(&self, pack_id: &PackId, version: &Version) -> Option<String> {
+        self.pack_template
+            .as_deref()
+            .map(|template| expand_template(template, pack_id.as_str(), version))
+    }
+
+    fn component_reference(&self, component_id: &ComponentId, version: &Version) -> Option<String> {
+        self.component_template
+            .as_deref()
+            .map(|template| expand_template(template, component_id.as_str(), version))
+    }
+}
+
+impl DistributorSource for OciTemplateSource {
+    fn fetch_pack(&self, pack_id: &PackId, version: &Version) -> Result<Vec<u8>, DistributorError> {
+        let reference = self
+            .pack_reference(pack_id, version)
+            .ok_or(DistributorError::NotFound)?;
+        let fetcher: OciPackFetcher = OciPackFetcher::new(PackFetchOptions {
+            allow_tags: true,
+            ..Default::default()
+        });
+        block_on(fetcher.fetch_pack(&reference))?.map_err(|err| match err {
+            OciPackError::PullFailed { source, .. } if is_missing(&source) => {
+                DistributorError::NotFound
+            }
+            other => DistributorError::Other(other.to_string()),
+        })
+    }
+
+    fn fetch_component(
+        &self,
+        component_id: &ComponentId,
+        version: &Version,
+    ) -> Result<Vec<u8>, DistributorError> {
+        let reference = self
+            .component_reference(component_id, version)
+            .ok_or(DistributorError::NotFound)?;
+        let resolver: OciComponentResolver = OciComponentResolver::new(ComponentResolveOptions {
+            allow_tags: true,
+            ..Default::default()
+        });
+        let extension = ComponentsExtension {
+            refs: vec![reference],
+            mode: ComponentsMode::Eager,
+        };
+        let resolved = block_on(resolver.resolve_refs(&extension))?.map_err(|err| match err {
+            OciComponentError::PullFailed { source, .. } if is_missing(&source) => {
+                DistributorError::NotFound
+            }
+            other => DistributorError::Other(other.to_string()),
+        })?;
+        let component = resolved
+            .into_iter()
+            .next()
+            .ok_or(DistributorError::NotFound)?;
+        Ok(fs::read(&component.path)?)
+    }
+
+    fn name(&self) -> &str {
+        "oci"
+    }
+
+    fn pack_location(&self, pack_id: &PackId, version: &Version) -> Option<String> {
+        self.pack_reference(pack_id, version)
+    }
+
+    fn component_location(&self, component_id: &ComponentId, version: &Version) -> Option<String> {
+        self.component_reference(component_id, version)
+    }
+}
+
+/// Source that reads artifacts from a static HTTP(S) tree.
+pub struct HttpTreeSource {
+    base_url: String,
+    pack_path: String,
+    component_path: String,
+    http: reqwest::Client,
+}
+
+impl HttpTreeSource {
+    pub fn new(
+        base_url: String,
+        pack_path: String,
+        component_path: String,
+    ) -> Result<Self, DistributorError> {
+        let http = reqwest::Client::builder()
+            .no_proxy()
+            .build()
+            .map_err(|err| {
+                DistributorError::InvalidConfig(format!("failed to build http client: {err}"))
+            })?;
+        Ok(Self {
+            base_url,
+            pack_path,
+            component_path,
+            http,
+        })
+    }
+
+    fn url(&self, template: &str, id: &str, version: &Version) -> String {
+        format!(
+            "{}/{}",
+            self.base_url.trim_end_matches('/'),
+            expand_template(template, id, version).trim_start_matches('/')
+        )
+    }
+
+    fn get(&self, url: String) -> Result<Vec<u8>, DistributorError> {
+        block_on(async {
+            let response = self
+                .http
+                .get(&url)
+                .send()
+                .await
+                .map_err(|err| DistributorError::Other(format!("{url}: {err}")))?;
+            let status = response.status();
+            if status == reqwest::StatusCode::NOT_FOUND {
+                return Err(DistributorError::NotFound);
+            }
+            if !status.is_success() {
+                return Err(DistributorError::Other(format!("{url}: {status}")));
+            }
+            let bytes = response
+                .bytes()
+                .await
+                .map_err(|err| DistributorError::Other(format!("{url}: {err}")))?;
+            Ok(bytes.to_vec())
+        })?
+    }
+}
+
+impl DistributorSource for HttpTreeSource {
+    fn fetch_pack(&self, pack_id: &PackId, version: &Version) -> Result<Vec<u8>, DistributorError> {
+        self.get(self.url(&self.pack_path, pack_id.as_str(), version))
+    }
+
+    fn fetch_component(
+        &self,
+        component_id: &ComponentId,
+        version: &Version,
+    ) -> Result<Vec<u8>, DistributorError> {
+        self.get(self.url(&self.component_path, component_id.as_str(), version))
+    }
+
+    fn name(&self) -> &str {
+        "http"
+    }
+
+    fn pack_location(&self, pack_id: &PackId, version: &Version) -> Option<String> {
+        Some(self.url(&self.pack_path, pack_id.as_str(), version))
+    }
+
+    fn component_location(&self, component_id: &ComponentId, version: &Version) -> Option<String> {
+        Some(self.url(&self.component_path, component_id.as_str(), version))
+    }
+}
+
+fn is_missing(err: &OciDistributionError) -> bool {
+    matches!(err, OciDistributionError::ImageManifestNotFoundError(_))
+}
+
+/// Drives an async fetch from the synchronous `DistributorSource` API.
+///
+/// The future runs on a private runtime in a scoped thread so callers may already be
+/// inside a Tokio runtime. It is dropped as soon as a parallel chain cancels the lookup.
+fn block_on<F>(future: F) -> Result<F::Output, DistributorError>
+where
+    F: Future + Send,
+    F::Output: Send,
+{
+    let cancellation = LookupCancellation::current();
+    let cancelled = async move {
+        let Some(flag) = cancellation else {
+            return std::future::pending().await;
+        };
+        while !flag.is_cancelled() {
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+    };
+    std::thread::scope(|scope| {
+        scope
+            .spawn(|| {
+                tokio::runtime::Builder::new_current_thread()
+                    .enable_all()
+                    .build()
+                    .map_err(|err| {
+                        DistributorError::Other(format!("failed to build source runtime: {err}"))
+                    })?
+                    .block_on(async {
+                        tokio::select! {
+                            output = future => Ok(output),
+                            () = cancelled => {
+                                Err(DistributorError::Other("lookup cancelled".into()))
+                            }
+                        }
+                    })
+            })
+            .join()
+            .map_err(|_| DistributorError::Other("source fetch panicked".into()))?
+    })
+}
@@ -1,7 +1,6 @@
 use std::fs;
-use std::io::{Read, Write};
+use std::io::Read;
 use std::path::PathBuf;
-use std::time::{SystemTime, UNIX_EPOCH};
 
 use async_trait::async_trait;
 use oci_distribution::Reference;
@@ -11,11 +10,17 @@ use oci_distribution::manifest::{
     IMAGE_MANIFEST_LIST_MEDIA_TYPE, IMAGE_MANIFEST_MEDIA_TYPE, OCI_IMAGE_INDEX_MEDIA_TYPE,
     OCI_IMAGE_MEDIA_TYPE,
 };
-use oci_distribution::secrets::RegistryAuth;
-use serde::{Deserialize, Serialize};
+use serde::Serialize;
 use sha2::{Digest, Sha256};
 use thiserror::Error;
 
+use crate::cache_fs::CacheLock;
+use crate::cache_policy::CachePolicy;
+use crate::registry_auth::{RegistryAuthOptions, RegistryCredentials};
+use crate::store::{
+    ArtifactKind, BlobStore, StoreMetadata, compute_digest, default_store_root, normalize_digest,
+};
+
 const OCI_ARTIFACT_MANIFEST_MEDIA_TYPE: &str = "application/vnd.oci.artifact.manifest.v1+json";
 const DOCKER_MANIFEST_MEDIA_TYPE: &str = "application/vnd.docker.distribution.manifest.v2+json";
 const DOCKER_MANIFEST_LIST_MEDIA_TYPE: &str =
@@ -38,16 +43,22 @@ static DEFAULT_ACCEPTED_MANIFEST_TYPES: &[&str] = &[
 pub struct DigestFetchOptions {
     pub cache_dir: PathBuf,
     pub accepted_manifest_types: Vec<String>,
+    /// Registry credential lookup for pulls.
+    pub auth: RegistryAuthOptions,
+    /// Size/age bounds enforced on `cache_dir` after each fetch.
+    pub cache_policy: CachePolicy,
 }
 
 impl Default for DigestFetchOptions {
     fn default() -> Self {
         Self {
-            cache_dir: default_cache_root(),
+            cache_dir: default_store_root(),
             accepted_manifest_types: DEFAULT_ACCEPTED_MANIFEST_TYPES
                 .iter()
                 .map(|s| s.to_string())
                 .collect(),
+            auth: RegistryAuthOptions::default(),
+            cache_policy: CachePolicy::default(),
         }
     }
 }
@@ -193,9 +204,17 @@ impl<C: RegistryClient> DigestFetcher<C> {
             .iter()
             .map(|s| s.as_str())
             .collect::<Vec<_>>();
+        let credentials = self
+            .opts
+            .auth
+            .resolve(digest_ref.reference().resolve_registry());
         let image = self
             .client
-            .pull(digest_ref.reference(), &accepted_manifest_types)
+            .pull_with_auth(
+                digest_ref.reference(),
+                &accepted_manifest_types,
+                &credentials,
+            )
             .await
             .map_err(|source| RunnerApiError::PullFailed {
                 reference: digest_ref.reference.to_string(),
@@ -205,18 +224,24 @@ impl<C: RegistryClient> DigestFetcher<C> {
         let layer = select_layer_by_digest(&image.layers, digest_ref.digest())?;
         verify_bytes_digest(&layer.data, digest_ref.digest())?;
 
-        let path = self.cache.write(
-            digest_ref.digest(),
-            layer.media_type.as_deref(),
-            &layer.data,
-        )?;
+        // Fetch unlocked and lock only the commit, so no blocking lock is held across an
+        // await; a concurrent fetch that committed first wins.
+        let lock = self.cache.lock(digest_ref.digest())?;
+        if let Some(hit) = self.cache.try_hit(&digest_ref) {
+            return Ok(hit);
+        }
+        let path = self
+            .cache
+            .write(&digest_ref, layer.media_type.as_deref(), &layer.data)?;
         let metadata = FetchMetadata {
             digest: digest_ref.digest().to_string(),
             cache: CacheInfo { hit: false },
             size_bytes: layer.data.len() as u64,
             media_type: layer.media_type.clone(),
         };
-        self.cache.write_metadata(&metadata)?;
+        drop(lock);
+        self.cache
+            .enforce(&self.opts.cache_policy, digest_ref.digest());
         Ok(CachedDigest { path, metadata })
     }
 }
@@ -246,14 +271,6 @@ fn is_digest(value: &str) -> bool {
     value.starts_with("sha256:") && value.len() == "sha256:".len() + 64
 }
 
-fn normalize_digest(digest: &str) -> String {
-    if digest.starts_with("sha256:") {
-        digest.to_string()
-    } else {
-        format!("sha256:{digest}")
-    }
-}
-
 fn verify_bytes_digest(bytes: &[u8], expected_digest: &str) -> Result<(), RunnerApiError> {
     let computed = compute_digest(bytes);
     if computed != expected_digest {
@@ -292,12 +309,6 @@ fn verify_file_digest(path: &PathBuf, expected_digest: &str) -> Result<(), Runne
     Ok(())
 }
 
-fn compute_digest(bytes: &[u8]) -> String {
-    let mut hasher = Sha256::new();
-    hasher.update(bytes);
-    format!("sha256:{:x}", hasher.finalize())
-}
-
 fn select_layer_by_digest<'a>(
     layers: &'a [PulledLayer],
     digest: &str,
@@ -310,119 +321,82 @@ fn select_layer_by_digest<'a>(
         })
 }
 
-fn default_cache_root() -> PathBuf {
-    if let Ok(root) = std::env::var("GREENTIC_DIST_CACHE_DIR") {
-        return PathBuf::from(root).join("digests");
-    }
-    if let Some(cache) = dirs_next::cache_dir() {
-        return cache.join("greentic").join("digests");
-    }
-    if let Ok(root) = std::env::var("GREENTIC_HOME") {
-        return PathBuf::from(root).join("cache").join("digests");
-    }
-    PathBuf::from(".greentic").join("cache").join("digests")
-}
-
 #[derive(Clone, Debug)]
 struct DigestCache {
-    root: PathBuf,
+    store: BlobStore,
 }
 
 impl DigestCache {
     fn new(root: PathBuf) -> Self {
-        Self { root }
+        Self {
+            store: BlobStore::new(root),
+        }
     }
 
+    /// Stores the blob and commits its metadata.
     fn write(
         &self,
-        digest: &str,
-        _media_type: Option<&str>,
+        digest_ref: &DigestRef,
+        media_type: Option<&str>,
         data: &[u8],
     ) -> Result<PathBuf, RunnerApiError> {
-        let dir = self.artifact_dir(digest);
-        fs::create_dir_all(&dir).map_err(|source| RunnerApiError::Io {
-            reference: digest.to_string(),
-            source,
-        })?;
-        let path = dir.join(DEFAULT_BLOB_FILENAME);
-        let mut file = fs::File::create(&path).map_err(|source| RunnerApiError::Io {
-            reference: digest.to_string(),
-            source,
-        })?;
-        file.write_all(data).map_err(|source| RunnerApiError::Io {
+        let digest = digest_ref.digest();
+        let io_err = |source| RunnerApiError::Io {
             reference: digest.to_string(),
             source,
-        })?;
+        };
+        let path = self
+            .store
+            .write_file(digest, DEFAULT_BLOB_FILENAME, data)
+            .map_err(io_err)?;
+        let metadata = StoreMetadata {
+            media_type: media_type.map(str::to_string),
+            ..StoreMetadata::new(
+                ArtifactKind::Blob,
+                &digest_ref.reference().whole(),
+                digest,
+                data.len() as u64,
+            )
+        };
+        self.store.commit(digest, metadata).map_err(io_err)?;
         Ok(path)
     }
 
-    fn write_metadata(&self, metadata: &FetchMetadata) -> Result<(), RunnerApiError> {
-        let dir = self.artifact_dir(&metadata.digest);
-        fs::create_dir_all(&dir).map_err(|source| RunnerApiError::Io {
-            reference: metadata.digest.clone(),
-            source,
-        })?;
-        let stored = CacheMetadata {
-            digest: metadata.digest.clone(),
-            size_bytes: metadata.size_bytes,
-            media_type: metadata.media_type.clone(),
-            fetched_at_unix_seconds: SystemTime::now()
-                .duration_since(UNIX_EPOCH)
-                .unwrap_or_default()
-                .as_secs(),
-        };
-        let bytes = serde_json::to_vec_pretty(&stored).map_err(|source| RunnerApiError::Serde {
-            reference: metadata.digest.clone(),
-            source,
-        })?;
-        let path = dir.join("metadata.json");
-        fs::write(path, bytes).map_err(|source| RunnerApiError::Io {
-            reference: metadata.digest.clone(),
-            source,
-        })?;
-        Ok(())
+    fn enforce(&self, policy: &CachePolicy, written: &str) {
+        self.store.enforce(policy, written);
     }
 
+    fn lock(&self, digest: &str) -> Result<CacheLock, RunnerApiError> {
+        self.store
+            .lock(digest)
+            .map_err(|source| RunnerApiError::Io {
+                reference: digest.to_string(),
+                source,
+            })
+    }
+
+    /// Cached blob for `digest_ref`; the store's metadata is the commit marker written last.
     fn try_hit(&self, digest_ref: &DigestRef) -> Option<CachedDigest> {
         let path = self
-            .artifact_dir(digest_ref.digest())
+            .store
+            .entry_dir(digest_ref.digest())
+            .ok()?
             .join(DEFAULT_BLOB_FILENAME);
         if !path.exists() {
             return None;
         }
-        let metadata = self.read_metadata(digest_ref.digest()).ok();
-        let size_bytes = metadata
-            .as_ref()
-            .map(|m| m.size_bytes)
-            .or_else(|| fs::metadata(&path).ok().map(|m| m.len()))
-            .unwrap_or_default();
-        let media_type = metadata.and_then(|m| m.media_type);
+        let metadata = self.store.metadata(digest_ref.digest())?;
+        self.store.touch(digest_ref.digest());
         Some(CachedDigest {
             path,
             metadata: FetchMetadata {
                 digest: digest_ref.digest().to_string(),
                 cache: CacheInfo { hit: true },
-                size_bytes,
-                media_type,
+                size_bytes: metadata.size_bytes,
+                media_type: metadata.media_type,
             },
         })
     }
-
-    fn read_metadata(&self, digest: &str) -> anyhow::Result<CacheMetadata> {
-        let path = self.artifact_dir(digest).join("metadata.json");
-        let bytes = fs::read(path)?;
-        Ok(serde_json::from_slice(&bytes)?)
-    }
-
-    fn artifact_dir(&self, digest: &str) -> PathBuf {
-        self.root.join(trim_digest_prefix(digest))
-    }
-}
-
-fn trim_digest_prefix(digest: &str) -> &str {
-    digest
-        .strip_prefix("sha256:")
-        .unwrap_or_else(|| digest.trim_start_matches('@'))
 }
 
 #[derive(Clone, Debug)]
@@ -448,9 +422,20 @@ pub trait RegistryClient: Send + Sync {
         reference: &Reference,
         accepted_manifest_types: &[&str],
     ) -> Result<PulledImage, OciDistributionError>;
+
+    /// Pulls presenting `credentials`; clients without auth support ignore them.
+    async fn pull_with_auth(
+        &self,
+        reference: &Reference,
+        accepted_manifest_types: &[&str],
+        credentials: &RegistryCredentials,
+    ) -> Result<PulledImage, OciDistributionError> {
+        let _ = credentials;
+        self.pull(reference, accepted_manifest_types).await
+    }
 }
 
-/// Registry client backed by `oci-distribution` with HTTPS enforced and anonymous pulls.
+/// Registry client backed by `oci-distribution` with HTTPS enforced.
 #[derive(Clone)]
 pub struct DefaultRegistryClient {
     inner: Client,
@@ -462,28 +447,49 @@ impl Default for DefaultRegistryClient {
     }
 }
 
-#[async_trait]
-impl RegistryClient for DefaultRegistryClient {
-    fn default_client() -> Self {
+impl DefaultRegistryClient {
+    /// Client using `protocol`, e.g. `ClientProtocol::HttpsExcept` for a local registry.
+    pub fn with_protocol(protocol: ClientProtocol) -> Self {
         let config = ClientConfig {
-            protocol: ClientProtocol::Https,
+            protocol,
             ..Default::default()
         };
         Self {
             inner: Client::new(config),
         }
     }
+}
+
+#[async_trait]
+impl RegistryClient for DefaultRegistryClient {
+    fn default_client() -> Self {
+        Self::with_protocol(ClientProtocol::Https)
+    }
 
     async fn pull(
         &self,
         reference: &Reference,
         accepted_manifest_types: &[&str],
+    ) -> Result<PulledImage, OciDistributionError> {
+        self.pull_with_auth(
+            reference,
+            accepted_manifest_types,
+            &RegistryCredentials::Anonymous,
+        )
+        .await
+    }
+
+    async fn pull_with_auth(
+        &self,
+        reference: &Reference,
+        accepted_manifest_types: &[&str],
+        credentials: &RegistryCredentials,
     ) -> Result<PulledImage, OciDistributionError> {
         let image = self
             .inner
             .pull(
                 reference,
-                &RegistryAuth::Anonymous,
+                &credentials.to_oci(),
                 accepted_manifest_types.to_vec(),
             )
             .await?;
@@ -496,7 +502,7 @@ fn convert_image(image: ImageData) -> PulledImage {
         .layers
         .into_iter()
         .map(|layer| {
-            let digest = format!("sha256:{}", layer.sha256_digest());
+            let digest = layer.sha256_digest();
             PulledLayer {
                 media_type: Some(layer.media_type),
                 data: layer.data,
@@ -507,15 +513,6 @@ fn convert_image(image: ImageData) -> PulledImage {
     PulledImage { layers }
 }
 
-#[derive(Debug, Serialize, Deserialize)]
-struct CacheMetadata {
-    digest: String,
-    size_bytes: u64,
-    #[serde(default)]
-    media_type: Option<String>,
-    fetched_at_unix_seconds: u64,
-}
-
 #[derive(Debug, Error)]
 pub enum RunnerApiError {
     #[error("invalid OCI reference `{reference}`: {reason}")]
@@ -557,6 +554,30 @@ mod tests {
     const TEST_DIGEST: &str =
         "sha256:aaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaa";
 
+    #[test]
+    fn convert_image_reports_layer_digest_with_a_single_prefix() {
+        use oci_distribution::client::{Config, ImageLayer};
+
+        let image = ImageData {
+            layers: vec![ImageLayer::new(
+                b"layer-bytes".to_vec(),
+                "application/wasm".to_string(),
+                None,
+            )],
+            digest: Some(TEST_DIGEST.to_string()),
+            config: Config::oci_v1(Vec::new(), None),
+            manifest: None,
+        };
+
+        let pulled = convert_image(image);
+
+        // Regression: layer digests used to come out as `sha256:sha256:<hex>`.
+        assert_eq!(
+            pulled.layers[0].digest.as_deref(),
+            Some(compute_digest(b"layer-bytes").as_str())
+        );
+    }
+
     #[test]
     fn parse_digest_ref_rejects_bare_digest() {
         let err = parse_digest_ref(TEST_DIGEST).unwrap_err();
@@ -1,3 +1,20 @@
+use std::cell::RefCell;
+use std::fmt;
+use std::fs;
+use std::io::{self, Cursor, ErrorKind, Read};
+use std::path::{Path, PathBuf};
+#[cfg(feature = "http-runtime")]
+use std::pin::Pin;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, mpsc};
+#[cfg(feature = "http-runtime")]
+use std::task::{Context, Poll, ready};
+use std::thread;
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
 use crate::{ComponentId, DistributorError, PackId, Version};
 
 /// Pluggable source for fetching packs and components by identifier/version.
@@ -9,29 +26,932 @@ pub trait DistributorSource: Send + Sync {
         component_id: &ComponentId,
         version: &Version,
     ) -> Result<Vec<u8>, DistributorError>;
+
+    /// Opens a pack for streaming instead of buffering it in memory.
+    ///
+    /// The default implementation buffers via [`DistributorSource::fetch_pack`]; sources
+    /// backed by files or sockets should override it.
+    fn open_pack(
+        &self,
+        pack_id: &PackId,
+        version: &Version,
+    ) -> Result<ArtifactStream, DistributorError> {
+        self.fetch_pack(pack_id, version)
+            .map(ArtifactStream::from_bytes)
+    }
+
+    /// Opens a component for streaming instead of buffering it in memory.
+    fn open_component(
+        &self,
+        component_id: &ComponentId,
+        version: &Version,
+    ) -> Result<ArtifactStream, DistributorError> {
+        self.fetch_component(component_id, version)
+            .map(ArtifactStream::from_bytes)
+    }
+
+    /// Opens a pack for async streaming.
+    ///
+    /// The default implementation buffers via [`DistributorSource::fetch_pack`]; sources
+    /// backed by files or sockets should override it.
+    #[cfg(feature = "http-runtime")]
+    fn open_pack_async(
+        &self,
+        pack_id: &PackId,
+        version: &Version,
+    ) -> Result<AsyncArtifactStream, DistributorError> {
+        self.fetch_pack(pack_id, version)
+            .map(AsyncArtifactStream::from_bytes)
+    }
+
+    /// Opens a component for async streaming.
+    #[cfg(feature = "http-runtime")]
+    fn open_component_async(
+        &self,
+        component_id: &ComponentId,
+        version: &Version,
+    ) -> Result<AsyncArtifactStream, DistributorError> {
+        self.fetch_component(component_id, version)
+            .map(AsyncArtifactStream::from_bytes)
+    }
+
+    /// Fetches a pack along with its digest and provenance.
+    ///
+    /// When `expected_sha256` is given (`sha256:<hex>` or bare hex) the bytes are verified
+    /// and [`DistributorError::DigestMismatch`] is returned on disagreement.
+    fn fetch_pack_artifact(
+        &self,
+        pack_id: &PackId,
+        version: &Version,
+        expected_sha256: Option<&str>,
+    ) -> Result<FetchedArtifact, DistributorError> {
+        let bytes = self.fetch_pack(pack_id, version)?;
+        FetchedArtifact::new(bytes, self.name(), self.pack_location(pack_id, version))
+            .verify(expected_sha256)
+    }
+
+    /// Fetches a component along with its digest and provenance.
+    fn fetch_component_artifact(
+        &self,
+        component_id: &ComponentId,
+        version: &Version,
+        expected_sha256: Option<&str>,
+    ) -> Result<FetchedArtifact, DistributorError> {
+        let bytes = self.fetch_component(component_id, version)?;
+        FetchedArtifact::new(
+            bytes,
+            self.name(),
+            self.component_location(component_id, version),
+        )
+        .verify(expected_sha256)
+    }
+
+    /// Short label used when reporting which sources a chain consulted.
+    fn name(&self) -> &str {
+        "source"
+    }
+
+    /// Human-readable location probed for a pack (path, reference, URL), if known.
+    fn pack_location(&self, _pack_id: &PackId, _version: &Version) -> Option<String> {
+        None
+    }
+
+    /// Human-readable location probed for a component (path, reference, URL), if known.
+    fn component_location(
+        &self,
+        _component_id: &ComponentId,
+        _version: &Version,
+    ) -> Option<String> {
+        None
+    }
+}
+
+/// Artifact bytes plus the digest and the source that served them.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FetchedArtifact {
+    pub bytes: Vec<u8>,
+    /// `sha256:<hex>` digest of `bytes`.
+    pub sha256: String,
+    pub source_name: String,
+    pub location: Option<String>,
+}
+
+impl FetchedArtifact {
+    pub fn new(bytes: Vec<u8>, source_name: impl Into<String>, location: Option<String>) -> Self {
+        let sha256 = sha256_digest(&bytes);
+        Self {
+            bytes,
+            sha256,
+            source_name: source_name.into(),
+            location,
+        }
+    }
+
+    /// Checks the digest against an optional expectation.
+    pub fn verify(self, expected_sha256: Option<&str>) -> Result<Self, DistributorError> {
+        if let Some(expected) = expected_sha256 {
+            let expected = normalize_sha256(expected);
+            if expected != self.sha256 {
+                return Err(DistributorError::DigestMismatch {
+                    expected,
+                    actual: self.sha256,
+                });
+            }
+        }
+        Ok(self)
+    }
+}
+
+fn sha256_digest(bytes: &[u8]) -> String {
+    format!("sha256:{:x}", Sha256::digest(bytes))
+}
+
+fn normalize_sha256(digest: &str) -> String {
+    let hex = digest.strip_prefix("sha256:").unwrap_or(digest);
+    format!("sha256:{}", hex.to_ascii_lowercase())
+}
+
+/// Readable artifact returned by the streaming fetch API.
+pub struct ArtifactStream {
+    reader: Box<dyn Read + Send>,
+    /// Size in bytes, when the source knows it up front.
+    pub size: Option<u64>,
+    /// Expected `sha256:<hex>` digest, when the source knows it up front.
+    pub digest: Option<String>,
+}
+
+impl ArtifactStream {
+    pub fn new(reader: impl Read + Send + 'static, size: Option<u64>) -> Self {
+        Self {
+            reader: Box::new(reader),
+            size,
+            digest: None,
+        }
+    }
+
+    pub fn from_bytes(bytes: Vec<u8>) -> Self {
+        let size = bytes.len() as u64;
+        Self::new(Cursor::new(bytes), Some(size))
+    }
+
+    pub fn with_digest(mut self, digest: impl Into<String>) -> Self {
+        self.digest = Some(digest.into());
+        self
+    }
+
+    /// Wraps the stream in a [`HashingReader`] that checks `digest` when finished.
+    pub fn hashing(self) -> HashingReader<Self> {
+        let expected = self.digest.clone();
+        HashingReader::new(self).expecting(expected)
+    }
+}
+
+impl Read for ArtifactStream {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.reader.read(buf)
+    }
+}
+
+impl fmt::Debug for ArtifactStream {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ArtifactStream")
+            .field("size", &self.size)
+            .field("digest", &self.digest)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Async counterpart of [`ArtifactStream`].
+#[cfg(feature = "http-runtime")]
+pub struct AsyncArtifactStream {
+    reader: Pin<Box<dyn tokio::io::AsyncRead + Send>>,
+    /// Size in bytes, when the source knows it up front.
+    pub size: Option<u64>,
+    /// Expected `sha256:<hex>` digest, when the source knows it up front.
+    pub digest: Option<String>,
+}
+
+#[cfg(feature = "http-runtime")]
+impl AsyncArtifactStream {
+    pub fn new(reader: impl tokio::io::AsyncRead + Send + 'static, size: Option<u64>) -> Self {
+        Self {
+            reader: Box::pin(reader),
+            size,
+            digest: None,
+        }
+    }
+
+    pub fn from_bytes(bytes: Vec<u8>) -> Self {
+        let size = bytes.len() as u64;
+        Self::new(Cursor::new(bytes), Some(size))
+    }
+
+    pub fn with_digest(mut self, digest: impl Into<String>) -> Self {
+        self.digest = Some(digest.into());
+        self
+    }
+
+    /// Wraps the stream in a [`HashingReader`] that checks `digest` when finished.
+    pub fn hashing(self) -> HashingReader<Self> {
+        let expected = self.digest.clone();
+        HashingReader::new(self).expecting(expected)
+    }
+}
+
+#[cfg(feature = "http-runtime")]
+impl tokio::io::AsyncRead for AsyncArtifactStream {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut tokio::io::ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        self.reader.as_mut().poll_read(cx, buf)
+    }
+}
+
+#[cfg(feature = "http-runtime")]
+impl fmt::Debug for AsyncArtifactStream {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("AsyncArtifactStream")
+            .field("size", &self.size)
+            .field("digest", &self.digest)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Reader (sync, or async with `http-runtime`) that hashes bytes as they pass through,
+/// so an artifact can be written to disk and verified in one pass.
+pub struct HashingReader<R> {
+    inner: R,
+    hasher: Sha256,
+    bytes_read: u64,
+    expected: Option<String>,
+}
+
+impl<R> HashingReader<R> {
+    pub fn new(inner: R) -> Self {
+        Self {
+            inner,
+            hasher: Sha256::new(),
+            bytes_read: 0,
+            expected: None,
+        }
+    }
+
+    /// Makes [`Self::finish`] fail unless the bytes hash to `expected` (`sha256:<hex>` or
+    /// bare hex); `None` accepts any content.
+    pub fn expecting(mut self, expected: Option<String>) -> Self {
+        self.expected = expected.as_deref().map(normalize_sha256);
+        self
+    }
+
+    pub fn bytes_read(&self) -> u64 {
+        self.bytes_read
+    }
+
+    /// `sha256:<hex>` digest of everything read so far (read to EOF first), checked against
+    /// the expectation.
+    pub fn finish(self) -> Result<String, DistributorError> {
+        let actual = format!("sha256:{:x}", self.hasher.finalize());
+        match self.expected {
+            Some(expected) if expected != actual => {
+                Err(DistributorError::DigestMismatch { expected, actual })
+            }
+            _ => Ok(actual),
+        }
+    }
+
+    fn update(&mut self, bytes: &[u8]) {
+        self.hasher.update(bytes);
+        self.bytes_read += bytes.len() as u64;
+    }
+}
+
+impl<R: Read> Read for HashingReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let read = self.inner.read(buf)?;
+        self.update(&buf[..read]);
+        Ok(read)
+    }
+}
+
+#[cfg(feature = "http-runtime")]
+impl<R: tokio::io::AsyncRead + Unpin> tokio::io::AsyncRead for HashingReader<R> {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut tokio::io::ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        let start = buf.filled().len();
+        ready!(Pin::new(&mut self.inner).poll_read(cx, buf))?;
+        self.update(&buf.filled()[start..]);
+        Poll::Ready(Ok(()))
+    }
+}
+
+impl<R> fmt::Debug for HashingReader<R> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("HashingReader")
+            .field("bytes_read", &self.bytes_read)
+            .field("expected", &self.expected)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Wraps a source with a fixed name so chain diagnostics can tell sources apart.
+pub struct NamedSource<S> {
+    name: String,
+    inner: S,
+}
+
+impl<S: DistributorSource> NamedSource<S> {
+    pub fn new(name: impl Into<String>, inner: S) -> Self {
+        Self {
+            name: name.into(),
+            inner,
+        }
+    }
+
+    pub fn inner(&self) -> &S {
+        &self.inner
+    }
+}
+
+impl<S: DistributorSource> DistributorSource for NamedSource<S> {
+    fn fetch_pack(&self, pack_id: &PackId, version: &Version) -> Result<Vec<u8>, DistributorError> {
+        self.inner.fetch_pack(pack_id, version)
+    }
+
+    fn fetch_component(
+        &self,
+        component_id: &ComponentId,
+        version: &Version,
+    ) -> Result<Vec<u8>, DistributorError> {
+        self.inner.fetch_component(component_id, version)
+    }
+
+    fn open_pack(
+        &self,
+        pack_id: &PackId,
+        version: &Version,
+    ) -> Result<ArtifactStream, DistributorError> {
+        self.inner.open_pack(pack_id, version)
+    }
+
+    fn open_component(
+        &self,
+        component_id: &ComponentId,
+        version: &Version,
+    ) -> Result<ArtifactStream, DistributorError> {
+        self.inner.open_component(component_id, version)
+    }
+
+    #[cfg(feature = "http-runtime")]
+    fn open_pack_async(
+        &self,
+        pack_id: &PackId,
+        version: &Version,
+    ) -> Result<AsyncArtifactStream, DistributorError> {
+        self.inner.open_pack_async(pack_id, version)
+    }
+
+    #[cfg(feature = "http-runtime")]
+    fn open_component_async(
+        &self,
+        component_id: &ComponentId,
+        version: &Version,
+    ) -> Result<AsyncArtifactStream, DistributorError> {
+        self.inner.open_component_async(component_id, version)
+    }
+
+    fn fetch_pack_artifact(
+        &self,
+        pack_id: &PackId,
+        version: &Version,
+        expected_sha256: Option<&str>,
+    ) -> Result<FetchedArtifact, DistributorError> {
+        let mut artifact = self
+            .inner
+            .fetch_pack_artifact(pack_id, version, expected_sha256)?;
+        artifact.source_name = self.name.clone();
+        Ok(artifact)
+    }
+
+    fn fetch_component_artifact(
+        &self,
+        component_id: &ComponentId,
+        version: &Version,
+        expected_sha256: Option<&str>,
+    ) -> Result<FetchedArtifact, DistributorError> {
+        let mut artifact =
+            self.inner
+                .fetch_component_artifact(component_id, version, expected_sha256)?;
+        artifact.source_name = self.name.clone();
+        Ok(artifact)
+    }
+
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn pack_location(&self, pack_id: &PackId, version: &Version) -> Option<String> {
+        self.inner.pack_location(pack_id, version)
+    }
+
+    fn component_location(&self, component_id: &ComponentId, version: &Version) -> Option<String> {
+        self.inner.component_location(component_id, version)
+    }
+}
+
+impl<S: DistributorSource + ?Sized> DistributorSource for Box<S> {
+    fn fetch_pack(&self, pack_id: &PackId, version: &Version) -> Result<Vec<u8>, DistributorError> {
+        (**self).fetch_pack(pack_id, version)
+    }
+
+    fn fetch_component(
+        &self,
+        component_id: &ComponentId,
+        version: &Version,
+    ) -> Result<Vec<u8>, DistributorError> {
+        (**self).fetch_component(component_id, version)
+    }
+
+    fn open_pack(
+        &self,
+        pack_id: &PackId,
+        version: &Version,
+    ) -> Result<ArtifactStream, DistributorError> {
+        (**self).open_pack(pack_id, version)
+    }
+
+    fn open_component(
+        &self,
+        component_id: &ComponentId,
+        version: &Version,
+    ) -> Result<ArtifactStream, DistributorError> {
+        (**self).open_component(component_id, version)
+    }
+
+    #[cfg(feature = "http-runtime")]
+    fn open_pack_async(
+        &self,
+        pack_id: &PackId,
+        version: &Version,
+    ) -> Result<AsyncArtifactStream, DistributorError> {
+        (**self).open_pack_async(pack_id, version)
+    }
+
+    #[cfg(feature = "http-runtime")]
+    fn open_component_async(
+        &self,
+        component_id: &ComponentId,
+        version: &Version,
+    ) -> Result<AsyncArtifactStream, DistributorError> {
+        (**self).open_component_async(component_id, version)
+    }
+
+    fn fetch_pack_artifact(
+        &self,
+        pack_id: &PackId,
+        version: &Version,
+        expected_sha256: Option<&str>,
+    ) -> Result<FetchedArtifact, DistributorError> {
+        (**self).fetch_pack_artifact(pack_id, version, expected_sha256)
+    }
+
+    fn fetch_component_artifact(
+        &self,
+        component_id: &ComponentId,
+        version: &Version,
+        expected_sha256: Option<&str>,
+    ) -> Result<FetchedArtifact, DistributorError> {
+        (**self).fetch_component_artifact(component_id, version, expected_sha256)
+    }
+
+    fn name(&self) -> &str {
+        (**self).name()
+    }
+
+    fn pack_location(&self, pack_id: &PackId, version: &Version) -> Option<String> {
+        (**self).pack_location(pack_id, version)
+    }
+
+    fn component_location(&self, component_id: &ComponentId, version: &Version) -> Option<String> {
+        (**self).component_location(component_id, version)
+    }
+}
+
+/// Write-through cache in front of another source.
+///
+/// Artifacts are stored under `{dir}/packs/{id}/{version}/pack.gtpack` and
+/// `{dir}/components/{id}/{version}/component.wasm`. Entries are keyed by id and version
+/// only, so by default hits never reach the inner source and an artifact republished under
+/// the same version (e.g. a moved OCI tag) is never picked up. Set
+/// [`with_max_age`](Self::with_max_age) to refetch entries older than that; a stale entry is
+/// still served when the inner source fails, but not once it reports the artifact missing.
+pub struct CachingSource<S> {
+    dir: PathBuf,
+    inner: S,
+    max_age: Option<Duration>,
+}
+
+impl<S: DistributorSource> CachingSource<S> {
+    pub fn new(dir: impl Into<PathBuf>, inner: S) -> Self {
+        Self {
+            dir: dir.into(),
+            inner,
+            max_age: None,
+        }
+    }
+
+    /// Revalidate entries against the inner source once they are older than `max_age`.
+    pub fn with_max_age(mut self, max_age: Duration) -> Self {
+        self.max_age = Some(max_age);
+        self
+    }
+
+    fn pack_path(&self, pack_id: &PackId, version: &Version) -> PathBuf {
+        self.dir
+            .join("packs")
+            .join(pack_id.as_str())
+            .join(version.to_string())
+            .join("pack.gtpack")
+    }
+
+    fn component_path(&self, component_id: &ComponentId, version: &Version) -> PathBuf {
+        self.dir
+            .join("components")
+            .join(component_id.as_str())
+            .join(version.to_string())
+            .join("component.wasm")
+    }
+
+    fn read_through<F>(&self, path: &Path, fetch: F) -> Result<Vec<u8>, DistributorError>
+    where
+        F: FnOnce() -> Result<Vec<u8>, DistributorError>,
+    {
+        let stale = match fs::read(path) {
+            Ok(bytes) if !self.is_stale(path) => return Ok(bytes),
+            Ok(bytes) => Some(bytes),
+            Err(err) if err.kind() == ErrorKind::NotFound => None,
+            Err(err) => return Err(DistributorError::Io(err)),
+        };
+        let bytes = match (fetch(), stale) {
+            (Ok(bytes), _) => bytes,
+            (Err(err), Some(stale)) if !err.is_not_found() => return Ok(stale),
+            (Err(err), _) => return Err(err),
+        };
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        crate::cache_fs::write_atomic(path, &bytes)?;
+        Ok(bytes)
+    }
+
+    fn is_stale(&self, path: &Path) -> bool {
+        let Some(max_age) = self.max_age else {
+            return false;
+        };
+        fs::metadata(path)
+            .and_then(|meta| meta.modified())
+            .ok()
+            .and_then(|modified| modified.elapsed().ok())
+            .is_none_or(|age| age >= max_age)
+    }
+}
+
+impl<S: DistributorSource> DistributorSource for CachingSource<S> {
+    fn fetch_pack(&self, pack_id: &PackId, version: &Version) -> Result<Vec<u8>, DistributorError> {
+        let path = self.pack_path(pack_id, version);
+        self.read_through(&path, || self.inner.fetch_pack(pack_id, version))
+    }
+
+    fn fetch_component(
+        &self,
+        component_id: &ComponentId,
+        version: &Version,
+    ) -> Result<Vec<u8>, DistributorError> {
+        let path = self.component_path(component_id, version);
+        self.read_through(&path, || self.inner.fetch_component(component_id, version))
+    }
+
+    fn name(&self) -> &str {
+        "cache"
+    }
+
+    fn pack_location(&self, pack_id: &PackId, version: &Version) -> Option<String> {
+        self.inner.pack_location(pack_id, version)
+    }
+
+    fn component_location(&self, component_id: &ComponentId, version: &Version) -> Option<String> {
+        self.inner.component_location(component_id, version)
+    }
+}
+
+/// How a chain reacts to a source failing with something other than "not found".
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ChainErrorPolicy {
+    /// Abort the lookup and return the failing source's error.
+    #[default]
+    StopOnError,
+    /// Record the failure and keep trying the remaining sources.
+    ContinueOnError,
+}
+
+/// Outcome of consulting a single source during a chained lookup.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum SourceOutcome {
+    Missing,
+    Failed(String),
+}
+
+impl fmt::Display for SourceOutcome {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SourceOutcome::Missing => f.write_str("missing"),
+            SourceOutcome::Failed(reason) => f.write_str(reason),
+        }
+    }
+}
+
+/// Diagnostic record for one source consulted by [`ChainedDistributorSource`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SourceAttempt {
+    pub source: String,
+    pub location: Option<String>,
+    pub outcome: SourceOutcome,
+}
+
+impl fmt::Display for SourceAttempt {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.location {
+            Some(location) => write!(f, "{}({location}): {}", self.source, self.outcome),
+            None => write!(f, "{}: {}", self.source, self.outcome),
+        }
+    }
+}
+
+thread_local! {
+    static CANCELLATION: RefCell<Option<LookupCancellation>> = const { RefCell::new(None) };
+}
+
+/// Cancellation flag of the parallel chain lookup running on the current thread.
+///
+/// Sources queried by a parallel [`ChainedDistributorSource`] can poll it during long
+/// fetches and return early once another source has won; their result is discarded.
+/// Lookups of nested chains are cancelled together with their parent.
+#[derive(Clone, Debug)]
+pub struct LookupCancellation(Arc<CancellationState>);
+
+#[derive(Debug)]
+struct CancellationState {
+    cancelled: AtomicBool,
+    parent: Option<LookupCancellation>,
+}
+
+impl LookupCancellation {
+    /// Returns the flag of the lookup this thread is serving, if any.
+    pub fn current() -> Option<Self> {
+        CANCELLATION.with(|current| current.borrow().clone())
+    }
+
+    /// Returns true once the lookup, or any lookup it is nested in, has been decided.
+    pub fn is_cancelled(&self) -> bool {
+        self.0.cancelled.load(Ordering::Acquire)
+            || self.0.parent.as_ref().is_some_and(Self::is_cancelled)
+    }
+
+    fn nested() -> Self {
+        Self(Arc::new(CancellationState {
+            cancelled: AtomicBool::new(false),
+            parent: Self::current(),
+        }))
+    }
+
+    fn enter(self) {
+        CANCELLATION.with(|current| *current.borrow_mut() = Some(self));
+    }
+
+    fn cancel(&self) {
+        self.0.cancelled.store(true, Ordering::Release);
+    }
+}
+
+/// Order in which a chain consults its sources.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ChainStrategy {
+    /// Try sources one after another in priority order.
+    #[default]
+    Sequential,
+    /// Query every source concurrently and return the highest-priority success.
+    ParallelPriority,
+    /// Query every source concurrently and return whichever success arrives first.
+    ParallelFirst,
 }
 
 /// Simple priority-ordered collection of sources that tries each until one succeeds.
+///
+/// When every source misses, the chain returns [`DistributorError::NotFound`] so callers that
+/// match on it keep working; opt into [`with_miss_diagnostics`](Self::with_miss_diagnostics)
+/// to get [`DistributorError::SourcesExhausted`] listing every source it consulted instead.
+/// If any source failed (under [`ChainErrorPolicy::ContinueOnError`]) the chain always reports
+/// `SourcesExhausted`.
+///
+/// With a parallel [`ChainStrategy`] each source is queried on its own detached thread and the
+/// chain returns as soon as the winner is known. The lookup is then cancelled: sources still in
+/// flight should poll [`LookupCancellation::current`] and give up, otherwise they keep running
+/// in the background until they finish and their result is dropped.
 pub struct ChainedDistributorSource {
-    sources: Vec<Box<dyn DistributorSource>>,
+    sources: Vec<Arc<dyn DistributorSource>>,
+    policy: ChainErrorPolicy,
+    strategy: ChainStrategy,
+    miss_diagnostics: bool,
 }
 
 impl ChainedDistributorSource {
     pub fn new(sources: Vec<Box<dyn DistributorSource>>) -> Self {
-        Self { sources }
+        Self {
+            sources: sources.into_iter().map(Arc::from).collect(),
+            policy: ChainErrorPolicy::default(),
+            strategy: ChainStrategy::default(),
+            miss_diagnostics: false,
+        }
     }
-}
 
-impl DistributorSource for ChainedDistributorSource {
-    fn fetch_pack(&self, pack_id: &PackId, version: &Version) -> Result<Vec<u8>, DistributorError> {
+    pub fn with_policy(mut self, policy: ChainErrorPolicy) -> Self {
+        self.policy = policy;
+        self
+    }
+
+    pub fn with_strategy(mut self, strategy: ChainStrategy) -> Self {
+        self.strategy = strategy;
+        self
+    }
+
+    /// Report [`DistributorError::SourcesExhausted`] with every probed source even when all of
+    /// them simply missed, instead of a bare [`DistributorError::NotFound`].
+    pub fn with_miss_diagnostics(mut self, enabled: bool) -> Self {
+        self.miss_diagnostics = enabled;
+        self
+    }
+
+    fn lookup<T, F, L>(&self, fetch: F, locate: L) -> Result<T, DistributorError>
+    where
+        T: Send + 'static,
+        F: Fn(&dyn DistributorSource) -> Result<T, DistributorError> + Send + Sync + 'static,
+        L: Fn(&dyn DistributorSource) -> Option<String>,
+    {
+        match self.strategy {
+            ChainStrategy::Sequential => self.lookup_sequential(fetch, locate),
+            ChainStrategy::ParallelPriority => self.lookup_parallel(fetch, locate, false),
+            ChainStrategy::ParallelFirst => self.lookup_parallel(fetch, locate, true),
+        }
+    }
+
+    fn lookup_sequential<T, F, L>(&self, fetch: F, locate: L) -> Result<T, DistributorError>
+    where
+        F: Fn(&dyn DistributorSource) -> Result<T, DistributorError>,
+        L: Fn(&dyn DistributorSource) -> Option<String>,
+    {
+        let mut attempts = Vec::with_capacity(self.sources.len());
         for source in &self.sources {
-            match source.fetch_pack(pack_id, version) {
-                Ok(bytes) => return Ok(bytes),
-                Err(DistributorError::NotFound) => continue,
-                Err(err) => return Err(err),
+            let source = source.as_ref();
+            match self.classify(fetch(source)) {
+                Verdict::Served(value) => return Ok(value),
+                Verdict::Abort(err) => return Err(err),
+                Verdict::Skip(outcome) => attempts.push(SourceAttempt {
+                    source: source.name().to_string(),
+                    location: locate(source),
+                    outcome,
+                }),
+            }
+        }
+        Err(self.exhausted(attempts))
+    }
+
+    fn lookup_parallel<T, F, L>(
+        &self,
+        fetch: F,
+        locate: L,
+        first_success: bool,
+    ) -> Result<T, DistributorError>
+    where
+        T: Send + 'static,
+        F: Fn(&dyn DistributorSource) -> Result<T, DistributorError> + Send + Sync + 'static,
+        L: Fn(&dyn DistributorSource) -> Option<String>,
+    {
+        let cancellation = LookupCancellation::nested();
+        let fetch = Arc::new(fetch);
+        let (tx, rx) = mpsc::channel();
+        for (index, source) in self.sources.iter().enumerate() {
+            let (source, fetch) = (Arc::clone(source), Arc::clone(&fetch));
+            let (tx, cancellation) = (tx.clone(), cancellation.clone());
+            thread::spawn(move || {
+                cancellation.enter();
+                // The receiver is gone once a winner has been picked; nothing to report.
+                let _ = tx.send((index, fetch(source.as_ref())));
+            });
+        }
+        drop(tx);
+        let result = self.race(rx, &locate, first_success);
+        // Tell the losers to give up; they are detached, so nothing waits for them.
+        cancellation.cancel();
+        result
+    }
+
+    fn race<T, L>(
+        &self,
+        rx: mpsc::Receiver<(usize, Result<T, DistributorError>)>,
+        locate: &L,
+        first_success: bool,
+    ) -> Result<T, DistributorError>
+    where
+        L: Fn(&dyn DistributorSource) -> Option<String>,
+    {
+        let mut pending: Vec<Option<Verdict<T>>> = self.sources.iter().map(|_| None).collect();
+        let mut settled = 0;
+        let mut attempts = Vec::with_capacity(self.sources.len());
+        for (index, result) in rx {
+            pending[index] = Some(match self.classify(result) {
+                Verdict::Served(value) if first_success => return Ok(value),
+                verdict => verdict,
+            });
+            // In priority mode a result is final once every higher-priority source is settled.
+            if !first_success
+                && let Some(result) = self.settle(&mut pending, &mut settled, &mut attempts, locate)
+            {
+                return result;
             }
         }
-        Err(DistributorError::NotFound)
+        if let Some(result) = self.settle(&mut pending, &mut settled, &mut attempts, locate) {
+            return result;
+        }
+        Err(self.exhausted(attempts))
+    }
+
+    /// Walks known verdicts in priority order, stopping at the first source still in flight.
+    fn settle<T, L>(
+        &self,
+        pending: &mut [Option<Verdict<T>>],
+        settled: &mut usize,
+        attempts: &mut Vec<SourceAttempt>,
+        locate: &L,
+    ) -> Option<Result<T, DistributorError>>
+    where
+        L: Fn(&dyn DistributorSource) -> Option<String>,
+    {
+        while let Some(verdict) = pending.get_mut(*settled).and_then(Option::take) {
+            let source = self.sources[*settled].as_ref();
+            *settled += 1;
+            match verdict {
+                Verdict::Served(value) => return Some(Ok(value)),
+                Verdict::Abort(err) => return Some(Err(err)),
+                Verdict::Skip(outcome) => attempts.push(SourceAttempt {
+                    source: source.name().to_string(),
+                    location: locate(source),
+                    outcome,
+                }),
+            }
+        }
+        None
+    }
+
+    fn exhausted(&self, attempts: Vec<SourceAttempt>) -> DistributorError {
+        let all_missing = attempts
+            .iter()
+            .all(|attempt| attempt.outcome == SourceOutcome::Missing);
+        if all_missing && !self.miss_diagnostics {
+            DistributorError::NotFound
+        } else {
+            DistributorError::SourcesExhausted { attempts }
+        }
+    }
+
+    fn classify<T>(&self, result: Result<T, DistributorError>) -> Verdict<T> {
+        match result {
+            Ok(value) => Verdict::Served(value),
+            Err(err) if err.is_not_found() => Verdict::Skip(SourceOutcome::Missing),
+            Err(err) if self.policy == ChainErrorPolicy::StopOnError => Verdict::Abort(err),
+            Err(err) => Verdict::Skip(SourceOutcome::Failed(err.to_string())),
+        }
+    }
+}
+
+enum Verdict<T> {
+    Served(T),
+    Abort(DistributorError),
+    Skip(SourceOutcome),
+}
+
+impl DistributorSource for ChainedDistributorSource {
+    fn fetch_pack(&self, pack_id: &PackId, version: &Version) -> Result<Vec<u8>, DistributorError> {
+        let (id, ver) = (pack_id.clone(), version.clone());
+        self.lookup(
+            move |source| source.fetch_pack(&id, &ver),
+            |source| source.pack_location(pack_id, version),
+        )
     }
 
     fn fetch_component(
@@ -39,14 +959,93 @@ impl DistributorSource for ChainedDistributorSource {
         component_id: &ComponentId,
         version: &Version,
     ) -> Result<Vec<u8>, DistributorError> {
-        for source in &self.sources {
-            match source.fetch_component(component_id, version) {
-                Ok(bytes) => return Ok(bytes),
-                Err(DistributorError::NotFound) => continue,
-                Err(err) => return Err(err),
-            }
-        }
-        Err(DistributorError::NotFound)
+        let (id, ver) = (component_id.clone(), version.clone());
+        self.lookup(
+            move |source| source.fetch_component(&id, &ver),
+            |source| source.component_location(component_id, version),
+        )
+    }
+
+    fn open_pack(
+        &self,
+        pack_id: &PackId,
+        version: &Version,
+    ) -> Result<ArtifactStream, DistributorError> {
+        let (id, ver) = (pack_id.clone(), version.clone());
+        self.lookup(
+            move |source| source.open_pack(&id, &ver),
+            |source| source.pack_location(pack_id, version),
+        )
+    }
+
+    fn open_component(
+        &self,
+        component_id: &ComponentId,
+        version: &Version,
+    ) -> Result<ArtifactStream, DistributorError> {
+        let (id, ver) = (component_id.clone(), version.clone());
+        self.lookup(
+            move |source| source.open_component(&id, &ver),
+            |source| source.component_location(component_id, version),
+        )
+    }
+
+    #[cfg(feature = "http-runtime")]
+    fn open_pack_async(
+        &self,
+        pack_id: &PackId,
+        version: &Version,
+    ) -> Result<AsyncArtifactStream, DistributorError> {
+        let (id, ver) = (pack_id.clone(), version.clone());
+        self.lookup(
+            move |source| source.open_pack_async(&id, &ver),
+            |source| source.pack_location(pack_id, version),
+        )
+    }
+
+    #[cfg(feature = "http-runtime")]
+    fn open_component_async(
+        &self,
+        component_id: &ComponentId,
+        version: &Version,
+    ) -> Result<AsyncArtifactStream, DistributorError> {
+        let (id, ver) = (component_id.clone(), version.clone());
+        self.lookup(
+            move |source| source.open_component_async(&id, &ver),
+            |source| source.component_location(component_id, version),
+        )
+    }
+
+    fn fetch_pack_artifact(
+        &self,
+        pack_id: &PackId,
+        version: &Version,
+        expected_sha256: Option<&str>,
+    ) -> Result<FetchedArtifact, DistributorError> {
+        let (id, ver) = (pack_id.clone(), version.clone());
+        let expected = expected_sha256.map(str::to_string);
+        self.lookup(
+            move |source| source.fetch_pack_artifact(&id, &ver, expected.as_deref()),
+            |source| source.pack_location(pack_id, version),
+        )
+    }
+
+    fn fetch_component_artifact(
+        &self,
+        component_id: &ComponentId,
+        version: &Version,
+        expected_sha256: Option<&str>,
+    ) -> Result<FetchedArtifact, DistributorError> {
+        let (id, ver) = (component_id.clone(), version.clone());
+        let expected = expected_sha256.map(str::to_string);
+        self.lookup(
+            move |source| source.fetch_component_artifact(&id, &ver, expected.as_deref()),
+            |source| source.component_location(component_id, version),
+        )
+    }
+
+    fn name(&self) -> &str {
+        "chain"
     }
 }
 
@@ -54,6 +1053,8 @@ impl DistributorSource for ChainedDistributorSource {
 mod tests {
     use super::*;
     use std::collections::HashMap;
+    use std::sync::Mutex;
+    use std::time::{Duration, Instant};
 
     struct MemorySource {
         packs: HashMap<(PackId, Version), Vec<u8>>,
@@ -149,4 +1150,461 @@ mod tests {
         let err = chained.fetch_pack(&pack_id, &version).unwrap_err();
         assert!(matches!(err, DistributorError::Other(msg) if msg == "boom"));
     }
+
+    #[test]
+    fn chained_returns_not_found_when_all_miss() {
+        let version = Version::parse("1.0.0").unwrap();
+        let pack_id = PackId::try_from("pack.absent").unwrap();
+        let chained = ChainedDistributorSource::new(vec![
+            Box::new(NamedSource::new("dev", MemorySource::new())),
+            Box::new(NamedSource::new("oci", MemorySource::new())),
+        ]);
+
+        let err = chained.fetch_pack(&pack_id, &version).unwrap_err();
+        assert!(matches!(err, DistributorError::NotFound));
+    }
+
+    #[test]
+    fn chained_reports_every_source_when_all_miss() {
+        let version = Version::parse("1.0.0").unwrap();
+        let pack_id = PackId::try_from("pack.absent").unwrap();
+        let chained = ChainedDistributorSource::new(vec![
+            Box::new(NamedSource::new("dev", MemorySource::new())),
+            Box::new(NamedSource::new("oci", MemorySource::new())),
+        ])
+        .with_miss_diagnostics(true);
+
+        let err = chained.fetch_pack(&pack_id, &version).unwrap_err();
+        assert!(err.is_not_found());
+        let DistributorError::SourcesExhausted { attempts } = &err else {
+            panic!("unexpected error: {err}");
+        };
+        let names: Vec<_> = attempts.iter().map(|a| a.source.as_str()).collect();
+        assert_eq!(names, vec!["dev", "oci"]);
+        assert_eq!(
+            err.to_string(),
+            "no source could serve the request: dev: missing; oci: missing"
+        );
+    }
+
+    #[test]
+    fn chained_continue_on_error_falls_through() {
+        let version = Version::parse("1.0.0").unwrap();
+        let pack_id = PackId::try_from("pack.flaky").unwrap();
+        let mut fallback = MemorySource::new();
+        fallback
+            .packs
+            .insert((pack_id.clone(), version.clone()), b"fallback".to_vec());
+        let chained = ChainedDistributorSource::new(vec![
+            Box::new(MemorySource::with_error("503")),
+            Box::new(fallback),
+        ])
+        .with_policy(ChainErrorPolicy::ContinueOnError);
+
+        let bytes = chained.fetch_pack(&pack_id, &version).unwrap();
+        assert_eq!(bytes, b"fallback");
+    }
+
+    #[test]
+    fn chained_continue_on_error_aggregates_failures() {
+        let version = Version::parse("1.0.0").unwrap();
+        let component_id = ComponentId::try_from("component.absent").unwrap();
+        let chained = ChainedDistributorSource::new(vec![
+            Box::new(NamedSource::new("dev", MemorySource::new())),
+            Box::new(NamedSource::new("oci", MemorySource::with_error("503"))),
+        ])
+        .with_policy(ChainErrorPolicy::ContinueOnError);
+
+        let err = chained
+            .fetch_component(&component_id, &version)
+            .unwrap_err();
+        assert!(!err.is_not_found());
+        let DistributorError::SourcesExhausted { attempts } = err else {
+            panic!("expected aggregated error");
+        };
+        assert_eq!(attempts[0].outcome, SourceOutcome::Missing);
+        assert_eq!(
+            attempts[1].outcome,
+            SourceOutcome::Failed("other distributor error: 503".into())
+        );
+    }
+
+    #[test]
+    fn nested_chain_miss_counts_as_not_found() {
+        let version = Version::parse("1.0.0").unwrap();
+        let pack_id = PackId::try_from("pack.nested").unwrap();
+        let mut fallback = MemorySource::new();
+        fallback
+            .packs
+            .insert((pack_id.clone(), version.clone()), b"outer".to_vec());
+        let inner = ChainedDistributorSource::new(vec![Box::new(MemorySource::new())]);
+        let chained = ChainedDistributorSource::new(vec![Box::new(inner), Box::new(fallback)]);
+
+        let bytes = chained.fetch_pack(&pack_id, &version).unwrap();
+        assert_eq!(bytes, b"outer");
+    }
+
+    struct DelayedSource {
+        delay: Duration,
+        bytes: Option<&'static [u8]>,
+        cancelled: Arc<AtomicBool>,
+    }
+
+    impl DelayedSource {
+        fn serving(delay_ms: u64, bytes: &'static [u8]) -> Self {
+            Self {
+                delay: Duration::from_millis(delay_ms),
+                bytes: Some(bytes),
+                cancelled: Arc::default(),
+            }
+        }
+
+        fn missing(delay_ms: u64) -> Self {
+            Self {
+                delay: Duration::from_millis(delay_ms),
+                bytes: None,
+                cancelled: Arc::default(),
+            }
+        }
+
+        /// Sleeps for the delay, giving up early when the chain cancels the lookup.
+        fn wait(&self) -> Result<(), DistributorError> {
+            let deadline = Instant::now() + self.delay;
+            while Instant::now() < deadline {
+                if LookupCancellation::current().is_some_and(|flag| flag.is_cancelled()) {
+                    self.cancelled.store(true, Ordering::SeqCst);
+                    return Err(DistributorError::Other("cancelled".into()));
+                }
+                std::thread::sleep(Duration::from_millis(5));
+            }
+            Ok(())
+        }
+    }
+
+    impl DistributorSource for DelayedSource {
+        fn fetch_pack(
+            &self,
+            _pack_id: &PackId,
+            _version: &Version,
+        ) -> Result<Vec<u8>, DistributorError> {
+            self.wait()?;
+            self.bytes
+                .map(<[u8]>::to_vec)
+                .ok_or(DistributorError::NotFound)
+        }
+
+        fn fetch_component(
+            &self,
+            _component_id: &ComponentId,
+            _version: &Version,
+        ) -> Result<Vec<u8>, DistributorError> {
+            Err(DistributorError::NotFound)
+        }
+    }
+
+    #[test]
+    fn parallel_priority_prefers_higher_priority_success() {
+        let version = Version::parse("1.0.0").unwrap();
+        let pack_id = PackId::try_from("pack.race").unwrap();
+        let chained = ChainedDistributorSource::new(vec![
+            Box::new(DelayedSource::serving(150, b"primary")),
+            Box::new(DelayedSource::serving(0, b"secondary")),
+        ])
+        .with_strategy(ChainStrategy::ParallelPriority);
+
+        let bytes = chained.fetch_pack(&pack_id, &version).unwrap();
+        assert_eq!(bytes, b"primary");
+    }
+
+    #[test]
+    fn parallel_first_returns_fastest_success() {
+        let version = Version::parse("1.0.0").unwrap();
+        let pack_id = PackId::try_from("pack.race").unwrap();
+        let chained = ChainedDistributorSource::new(vec![
+            Box::new(DelayedSource::serving(150, b"primary")),
+            Box::new(DelayedSource::serving(0, b"secondary")),
+        ])
+        .with_strategy(ChainStrategy::ParallelFirst);
+
+        let bytes = chained.fetch_pack(&pack_id, &version).unwrap();
+        assert_eq!(bytes, b"secondary");
+    }
+
+    #[test]
+    fn parallel_priority_does_not_wait_for_lower_priority_sources() {
+        let version = Version::parse("1.0.0").unwrap();
+        let pack_id = PackId::try_from("pack.race").unwrap();
+        let chained = ChainedDistributorSource::new(vec![
+            Box::new(DelayedSource::missing(0)),
+            Box::new(DelayedSource::serving(20, b"fallback")),
+            Box::new(DelayedSource::serving(5_000, b"slow")),
+        ])
+        .with_strategy(ChainStrategy::ParallelPriority);
+
+        let started = Instant::now();
+        let bytes = chained.fetch_pack(&pack_id, &version).unwrap();
+        assert_eq!(bytes, b"fallback");
+        assert!(started.elapsed() < Duration::from_secs(2));
+    }
+
+    #[test]
+    fn parallel_lookups_cancel_losing_sources() {
+        let version = Version::parse("1.0.0").unwrap();
+        let pack_id = PackId::try_from("pack.race").unwrap();
+        let slow = DelayedSource::serving(5_000, b"slow");
+        let slow_cancelled = Arc::clone(&slow.cancelled);
+        let nested = DelayedSource::serving(5_000, b"nested");
+        let nested_cancelled = Arc::clone(&nested.cancelled);
+        let chained = ChainedDistributorSource::new(vec![
+            Box::new(DelayedSource::serving(0, b"fast")),
+            Box::new(slow),
+            Box::new(
+                ChainedDistributorSource::new(vec![Box::new(nested)])
+                    .with_strategy(ChainStrategy::ParallelFirst),
+            ),
+        ])
+        .with_strategy(ChainStrategy::ParallelFirst);
+
+        let started = Instant::now();
+        assert_eq!(chained.fetch_pack(&pack_id, &version).unwrap(), b"fast");
+        assert!(started.elapsed() < Duration::from_secs(2));
+        // Losers run detached; give them a moment to notice the flag.
+        let deadline = Instant::now() + Duration::from_secs(2);
+        while !(slow_cancelled.load(Ordering::SeqCst) && nested_cancelled.load(Ordering::SeqCst))
+            && Instant::now() < deadline
+        {
+            std::thread::sleep(Duration::from_millis(5));
+        }
+        assert!(slow_cancelled.load(Ordering::SeqCst));
+        assert!(nested_cancelled.load(Ordering::SeqCst));
+        assert!(LookupCancellation::current().is_none());
+    }
+
+    /// Ignores cancellation entirely and blocks until the test drops its sender.
+    struct StubbornSource(Mutex<mpsc::Receiver<()>>);
+
+    impl DistributorSource for StubbornSource {
+        fn fetch_pack(
+            &self,
+            _pack_id: &PackId,
+            _version: &Version,
+        ) -> Result<Vec<u8>, DistributorError> {
+            let _ = self.0.lock().unwrap().recv_timeout(Duration::from_secs(10));
+            Ok(b"stubborn".to_vec())
+        }
+
+        fn fetch_component(
+            &self,
+            _component_id: &ComponentId,
+            _version: &Version,
+        ) -> Result<Vec<u8>, DistributorError> {
+            Err(DistributorError::NotFound)
+        }
+    }
+
+    #[test]
+    fn parallel_winner_does_not_wait_for_sources_ignoring_cancellation() {
+        let version = Version::parse("1.0.0").unwrap();
+        let pack_id = PackId::try_from("pack.race").unwrap();
+        let (release, blocked) = mpsc::channel();
+        let chained = ChainedDistributorSource::new(vec![
+            Box::new(DelayedSource::serving(0, b"fast")),
+            Box::new(StubbornSource(Mutex::new(blocked))),
+        ])
+        .with_strategy(ChainStrategy::ParallelFirst);
+
+        let started = Instant::now();
+        assert_eq!(chained.fetch_pack(&pack_id, &version).unwrap(), b"fast");
+        assert!(started.elapsed() < Duration::from_secs(2));
+        drop(release);
+    }
+
+    #[test]
+    fn parallel_reports_all_misses_in_priority_order() {
+        let version = Version::parse("1.0.0").unwrap();
+        let pack_id = PackId::try_from("pack.race").unwrap();
+        let chained = ChainedDistributorSource::new(vec![
+            Box::new(NamedSource::new("slow", DelayedSource::missing(50))),
+            Box::new(NamedSource::new("fast", DelayedSource::missing(0))),
+        ])
+        .with_strategy(ChainStrategy::ParallelFirst)
+        .with_miss_diagnostics(true);
+
+        let err = chained.fetch_pack(&pack_id, &version).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "no source could serve the request: slow: missing; fast: missing"
+        );
+    }
+
+    #[test]
+    fn chained_open_streams_from_serving_source() {
+        let version = Version::parse("1.0.0").unwrap();
+        let component_id = ComponentId::try_from("component.stream").unwrap();
+        let mut fallback = MemorySource::new();
+        fallback.components.insert(
+            (component_id.clone(), version.clone()),
+            b"streamed".to_vec(),
+        );
+        let chained =
+            ChainedDistributorSource::new(vec![Box::new(MemorySource::new()), Box::new(fallback)]);
+
+        let mut stream = chained.open_component(&component_id, &version).unwrap();
+        assert_eq!(stream.size, Some(8));
+        let mut buf = Vec::new();
+        stream.read_to_end(&mut buf).unwrap();
+        assert_eq!(buf, b"streamed");
+    }
+
+    #[test]
+    fn hashing_reader_verifies_while_streaming() {
+        let expected = sha256_digest(b"streamed");
+        let mut reader = ArtifactStream::from_bytes(b"streamed".to_vec())
+            .with_digest(expected.trim_start_matches("sha256:").to_uppercase())
+            .hashing();
+        let mut out = Vec::new();
+        io::copy(&mut reader, &mut out).unwrap();
+        assert_eq!(out, b"streamed");
+        assert_eq!(reader.bytes_read(), 8);
+        assert_eq!(reader.finish().unwrap(), expected);
+
+        let mut tampered = ArtifactStream::from_bytes(b"tampered".to_vec())
+            .with_digest(expected)
+            .hashing();
+        io::copy(&mut tampered, &mut io::sink()).unwrap();
+        assert!(matches!(
+            tampered.finish(),
+            Err(DistributorError::DigestMismatch { .. })
+        ));
+    }
+
+    #[cfg(feature = "http-runtime")]
+    #[tokio::test]
+    async fn chained_async_open_streams_and_hashes() {
+        let version = Version::parse("1.0.0").unwrap();
+        let component_id = ComponentId::try_from("component.stream").unwrap();
+        let mut fallback = MemorySource::new();
+        fallback.components.insert(
+            (component_id.clone(), version.clone()),
+            b"streamed".to_vec(),
+        );
+        let chained =
+            ChainedDistributorSource::new(vec![Box::new(MemorySource::new()), Box::new(fallback)]);
+
+        let stream = chained
+            .open_component_async(&component_id, &version)
+            .unwrap();
+        assert_eq!(stream.size, Some(8));
+        let mut reader = stream.hashing();
+        let mut out = Vec::new();
+        tokio::io::copy(&mut reader, &mut out).await.unwrap();
+        assert_eq!(out, b"streamed");
+        assert_eq!(reader.finish().unwrap(), sha256_digest(b"streamed"));
+    }
+
+    #[test]
+    fn caching_source_serves_repeat_fetches_from_disk() {
+        let temp = tempfile::tempdir().unwrap();
+        let version = Version::parse("1.0.0").unwrap();
+        let pack_id = PackId::try_from("pack.cached").unwrap();
+        let mut remote = MemorySource::new();
+        remote
+            .packs
+            .insert((pack_id.clone(), version.clone()), b"remote".to_vec());
+
+        let cached = CachingSource::new(temp.path(), remote);
+        assert_eq!(cached.fetch_pack(&pack_id, &version).unwrap(), b"remote");
+        let stored = temp.path().join("packs/pack.cached/1.0.0/pack.gtpack");
+        assert_eq!(fs::read(&stored).unwrap(), b"remote");
+
+        let offline = CachingSource::new(temp.path(), MemorySource::with_error("offline"));
+        assert_eq!(offline.fetch_pack(&pack_id, &version).unwrap(), b"remote");
+    }
+
+    #[test]
+    fn caching_source_revalidates_entries_older_than_max_age() {
+        let temp = tempfile::tempdir().unwrap();
+        let version = Version::parse("1.0.0").unwrap();
+        let pack_id = PackId::try_from("pack.moved").unwrap();
+        let remote = |bytes: &[u8]| {
+            let mut source = MemorySource::new();
+            source
+                .packs
+                .insert((pack_id.clone(), version.clone()), bytes.to_vec());
+            source
+        };
+        let fetch = |source: MemorySource, max_age: Option<Duration>| {
+            let cached = CachingSource::new(temp.path(), source);
+            match max_age {
+                Some(max_age) => cached.with_max_age(max_age),
+                None => cached,
+            }
+            .fetch_pack(&pack_id, &version)
+        };
+
+        assert_eq!(fetch(remote(b"old"), None).unwrap(), b"old");
+        // Without a max age the republished artifact is never seen.
+        assert_eq!(fetch(remote(b"new"), None).unwrap(), b"old");
+        let fresh = Some(Duration::from_secs(3600));
+        assert_eq!(fetch(remote(b"new"), fresh).unwrap(), b"old");
+
+        let expired = Some(Duration::ZERO);
+        assert_eq!(fetch(remote(b"new"), expired).unwrap(), b"new");
+        assert_eq!(fetch(remote(b"other"), None).unwrap(), b"new");
+        // Failing upstreams fall back to the stale copy; a missing artifact does not.
+        let offline = MemorySource::with_error("offline");
+        assert_eq!(fetch(offline, expired).unwrap(), b"new");
+        assert!(
+            fetch(MemorySource::new(), expired)
+                .unwrap_err()
+                .is_not_found()
+        );
+    }
+
+    #[test]
+    fn artifact_fetch_reports_serving_source_and_digest() {
+        let version = Version::parse("1.0.0").unwrap();
+        let pack_id = PackId::try_from("pack.meta").unwrap();
+        let mut fallback = MemorySource::new();
+        fallback
+            .packs
+            .insert((pack_id.clone(), version.clone()), b"meta".to_vec());
+        let chained = ChainedDistributorSource::new(vec![
+            Box::new(NamedSource::new("dev", MemorySource::new())),
+            Box::new(NamedSource::new("mirror", fallback)),
+        ]);
+
+        let artifact = chained
+            .fetch_pack_artifact(&pack_id, &version, None)
+            .unwrap();
+        assert_eq!(artifact.bytes, b"meta");
+        assert_eq!(artifact.source_name, "mirror");
+        assert_eq!(artifact.sha256, sha256_digest(b"meta"));
+
+        let hex = artifact.sha256.trim_start_matches("sha256:").to_string();
+        let verified = chained
+            .fetch_pack_artifact(&pack_id, &version, Some(&hex))
+            .unwrap();
+        assert_eq!(verified.sha256, artifact.sha256);
+    }
+
+    #[test]
+    fn artifact_fetch_rejects_digest_mismatch() {
+        let version = Version::parse("1.0.0").unwrap();
+        let component_id = ComponentId::try_from("component.tampered").unwrap();
+        let mut source = MemorySource::new();
+        source.components.insert(
+            (component_id.clone(), version.clone()),
+            b"tampered".to_vec(),
+        );
+        let chained = ChainedDistributorSource::new(vec![Box::new(source)]);
+
+        let expected = sha256_digest(b"original");
+        let err = chained
+            .fetch_component_artifact(&component_id, &version, Some(&expected))
+            .unwrap_err();
+        assert!(
+            matches!(err, DistributorError::DigestMismatch { expected: e, .. } if e == expected)
+        );
+    }
 }
@@ -0,0 +1,482 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use greentic_config_types::{GreenticConfig, PackSourceConfig};
+use serde::{Deserialize, Serialize};
+
+use crate::source::{
+    CachingSource, ChainErrorPolicy, ChainStrategy, ChainedDistributorSource, DistributorSource,
+    NamedSource,
+};
+use crate::{DistributorError, Version};
+
+/// Declarative description of a [`ChainedDistributorSource`], loadable from TOML or JSON.
+///
+/// ```toml
+/// strategy = "sequential"
+/// on_error = "continue_on_error"
+///
+/// [[sources]]
+/// kind = "dev"
+/// root_dir = ".greentic/dev"
+/// layout = "by_id_and_version"
+///
+/// [[sources]]
+/// kind = "cache"
+/// dir = "/var/cache/greentic/sources"
+/// source = { kind = "oci", pack_template = "ghcr.io/acme/packs/{id}:{version}" }
+/// ```
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SourceChainConfig {
+    #[serde(default)]
+    pub strategy: ChainStrategy,
+    #[serde(default)]
+    pub on_error: ChainErrorPolicy,
+    /// List every probed source when all of them miss instead of returning `NotFound`.
+    #[serde(default)]
+    pub miss_diagnostics: bool,
+    #[serde(default)]
+    pub sources: Vec<SourceSpec>,
+}
+
+/// One entry of a [`SourceChainConfig`].
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum SourceSpec {
+    /// Local directory served by `greentic-distributor-dev`.
+    Dev(DevSourceSpec),
+    /// OCI registry addressed through reference templates.
+    Oci(OciSourceSpec),
+    /// Static HTTP(S) tree addressed through path templates.
+    Http(HttpSourceSpec),
+    /// Write-through disk cache wrapped around another source.
+    Cache(CacheSourceSpec),
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DevSourceSpec {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    pub root_dir: PathBuf,
+    #[serde(default = "default_packs_dir")]
+    pub packs_dir: String,
+    #[serde(default = "default_components_dir")]
+    pub components_dir: String,
+    #[serde(default)]
+    pub layout: DevLayoutSpec,
+}
+
+/// Serializable mirror of the dev source layouts.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DevLayoutSpec {
+    #[default]
+    Flat,
+    ByIdAndVersion,
+    /// Paths relative to the dev root; see `expand_template`, plus `{ext}`.
+    Template {
+        pack: String,
+        component: String,
+    },
+}
+
+/// OCI source; templates accept `{id}` and `{version}` (plus `{major}`, `{minor}`, `{patch}`).
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct OciSourceSpec {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pack_template: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub component_template: Option<String>,
+}
+
+/// HTTP tree source; paths are templates relative to `base_url`.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct HttpSourceSpec {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    pub base_url: String,
+    #[serde(default = "default_http_pack_path")]
+    pub pack_path: String,
+    #[serde(default = "default_http_component_path")]
+    pub component_path: String,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CacheSourceSpec {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    pub dir: PathBuf,
+    /// Refetch entries older than this many seconds; unset keeps entries forever.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_age_secs: Option<u64>,
+    pub source: Box<SourceSpec>,
+}
+
+fn default_packs_dir() -> String {
+    "packs".into()
+}
+
+fn default_components_dir() -> String {
+    "components".into()
+}
+
+fn default_http_pack_path() -> String {
+    "packs/{id}/{version}/pack.gtpack".into()
+}
+
+fn default_http_component_path() -> String {
+    "components/{id}/{version}/component.wasm".into()
+}
+
+impl SourceChainConfig {
+    #[cfg(feature = "toml-config")]
+    pub fn from_toml_str(data: &str) -> Result<Self, DistributorError> {
+        toml::from_str(data).map_err(|err| DistributorError::InvalidConfig(err.to_string()))
+    }
+
+    pub fn from_json_str(data: &str) -> Result<Self, DistributorError> {
+        serde_json::from_str(data).map_err(|err| DistributorError::InvalidConfig(err.to_string()))
+    }
+
+    /// Loads a config file, treating `.json` files as JSON and everything else as TOML.
+    ///
+    /// TOML files require the `toml-config` feature.
+    pub fn load(path: &Path) -> Result<Self, DistributorError> {
+        let data = fs::read_to_string(path)?;
+        match path.extension().and_then(|ext| ext.to_str()) {
+            Some("json") => Self::from_json_str(&data),
+            #[cfg(feature = "toml-config")]
+            _ => Self::from_toml_str(&data),
+            #[cfg(not(feature = "toml-config"))]
+            _ => Err(DistributorError::InvalidConfig(format!(
+                "{} is TOML, which requires the `toml-config` feature",
+                path.display()
+            ))),
+        }
+    }
+
+    /// Derives the default chain from a host-resolved GreenticConfig.
+    ///
+    /// The dev directory under `greentic_root` comes first, followed by the configured
+    /// pack source (if any) behind a cache in `packs.cache_dir`.
+    pub fn from_greentic(cfg: &GreenticConfig) -> Self {
+        let mut sources = vec![SourceSpec::Dev(DevSourceSpec {
+            name: Some("dev".into()),
+            root_dir: cfg.paths.greentic_root.join(".greentic").join("dev"),
+            packs_dir: default_packs_dir(),
+            components_dir: default_components_dir(),
+            layout: DevLayoutSpec::default(),
+        })];
+        if let Some(packs) = &cfg.packs {
+            let remote = match &packs.source {
+                PackSourceConfig::LocalIndex { path } => SourceSpec::Dev(DevSourceSpec {
+                    name: Some("local".into()),
+                    root_dir: path.clone(),
+                    packs_dir: default_packs_dir(),
+                    components_dir: default_components_dir(),
+                    layout: DevLayoutSpec::ByIdAndVersion,
+                }),
+                PackSourceConfig::HttpIndex { url } => SourceSpec::Http(HttpSourceSpec {
+                    name: Some("http".into()),
+                    base_url: url.clone(),
+                    pack_path: default_http_pack_path(),
+                    component_path: default_http_component_path(),
+                }),
+                PackSourceConfig::OciRegistry { reference } => {
+                    let base = reference.trim_end_matches('/');
+                    SourceSpec::Oci(OciSourceSpec {
+                        name: Some("oci".into()),
+                        pack_template: Some(format!("{base}/{{id}}:{{version}}")),
+                        component_template: None,
+                    })
+                }
+            };
+            let remote = match remote {
+                local @ SourceSpec::Dev(_) => local,
+                remote => SourceSpec::Cache(CacheSourceSpec {
+                    name: None,
+                    dir: packs.cache_dir.clone(),
+                    max_age_secs: None,
+                    source: Box::new(remote),
+                }),
+            };
+            sources.push(remote);
+        }
+        Self {
+            sources,
+            ..Default::default()
+        }
+    }
+}
+
+type DevFactory = Box<dyn Fn(&DevSourceSpec) -> Box<dyn DistributorSource>>;
+
+/// Builds a [`ChainedDistributorSource`] from a [`SourceChainConfig`].
+///
+/// Dev sources live in the `greentic-distributor-dev` crate, which registers itself
+/// through [`SourceChainBuilder::with_dev_factory`]; remote sources require the
+/// `dist-client` feature.
+#[derive(Default)]
+pub struct SourceChainBuilder {
+    dev_factory: Option<DevFactory>,
+}
+
+impl SourceChainBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_dev_factory(
+        mut self,
+        factory: impl Fn(&DevSourceSpec) -> Box<dyn DistributorSource> + 'static,
+    ) -> Self {
+        self.dev_factory = Some(Box::new(factory));
+        self
+    }
+
+    pub fn build(
+        &self,
+        config: &SourceChainConfig,
+    ) -> Result<ChainedDistributorSource, DistributorError> {
+        let sources = config
+            .sources
+            .iter()
+            .map(|spec| self.build_source(spec))
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(ChainedDistributorSource::new(sources)
+            .with_policy(config.on_error)
+            .with_strategy(config.strategy)
+            .with_miss_diagnostics(config.miss_diagnostics))
+    }
+
+    fn build_source(
+        &self,
+        spec: &SourceSpec,
+    ) -> Result<Box<dyn DistributorSource>, DistributorError> {
+        let (name, source): (&Option<String>, Box<dyn DistributorSource>) = match spec {
+            SourceSpec::Dev(dev) => {
+                let factory = self.dev_factory.as_ref().ok_or_else(|| {
+                    DistributorError::InvalidConfig(
+                        "dev sources require greentic-distributor-dev".into(),
+                    )
+                })?;
+                (&dev.name, factory(dev))
+            }
+            SourceSpec::Oci(oci) => (&oci.name, build_oci(oci)?),
+            SourceSpec::Http(http) => (&http.name, build_http(http)?),
+            SourceSpec::Cache(cache) => {
+                let cached =
+                    CachingSource::new(cache.dir.clone(), self.build_source(&cache.source)?);
+                let cached = match cache.max_age_secs {
+                    Some(secs) => cached.with_max_age(Duration::from_secs(secs)),
+                    None => cached,
+                };
+                (&cache.name, Box::new(cached))
+            }
+        };
+        Ok(match name {
+            Some(name) => Box::new(NamedSource::new(name.clone(), source)),
+            None => source,
+        })
+    }
+}
+
+#[cfg(feature = "dist-client")]
+fn build_oci(spec: &OciSourceSpec) -> Result<Box<dyn DistributorSource>, DistributorError> {
+    Ok(Box::new(crate::remote_source::OciTemplateSource::new(
+        spec.pack_template.clone(),
+        spec.component_template.clone(),
+    )))
+}
+
+#[cfg(not(feature = "dist-client"))]
+fn build_oci(_spec: &OciSourceSpec) -> Result<Box<dyn DistributorSource>, DistributorError> {
+    Err(DistributorError::InvalidConfig(
+        "oci sources require the `dist-client` feature".into(),
+    ))
+}
+
+#[cfg(feature = "dist-client")]
+fn build_http(spec: &HttpSourceSpec) -> Result<Box<dyn DistributorSource>, DistributorError> {
+    Ok(Box::new(crate::remote_source::HttpTreeSource::new(
+        spec.base_url.clone(),
+        spec.pack_path.clone(),
+        spec.component_path.clone(),
+    )?))
+}
+
+#[cfg(not(feature = "dist-client"))]
+fn build_http(_spec: &HttpSourceSpec) -> Result<Box<dyn DistributorSource>, DistributorError> {
+    Err(DistributorError::InvalidConfig(
+        "http sources require the `dist-client` feature".into(),
+    ))
+}
+
+/// Expands `{id}`, `{version}`, `{major}`, `{minor}` and `{patch}` in a source template.
+pub fn expand_template(template: &str, id: &str, version: &Version) -> String {
+    template
+        .replace("{id}", id)
+        .replace("{version}", &version.to_string())
+        .replace("{major}", &version.major.to_string())
+        .replace("{minor}", &version.minor.to_string())
+        .replace("{patch}", &version.patch.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(feature = "toml-config")]
+    #[test]
+    fn parses_toml_chain() {
+        let cfg = SourceChainConfig::from_toml_str(
+            r#"
+strategy = "parallel_priority"
+on_error = "continue_on_error"
+
+[[sources]]
+kind = "dev"
+name = "dev"
+root_dir = ".greentic/dev"
+layout = "by_id_and_version"
+
+[[sources]]
+kind = "cache"
+dir = "/tmp/greentic-sources"
+source = { kind = "oci", pack_template = "ghcr.io/acme/packs/{id}:{version}" }
+"#,
+        )
+        .unwrap();
+
+        assert_eq!(cfg.strategy, ChainStrategy::ParallelPriority);
+        assert_eq!(cfg.on_error, ChainErrorPolicy::ContinueOnError);
+        let SourceSpec::Dev(dev) = &cfg.sources[0] else {
+            panic!("expected dev source");
+        };
+        assert_eq!(dev.layout, DevLayoutSpec::ByIdAndVersion);
+        assert_eq!(dev.packs_dir, "packs");
+        let SourceSpec::Cache(cache) = &cfg.sources[1] else {
+            panic!("expected cache source");
+        };
+        assert!(matches!(cache.source.as_ref(), SourceSpec::Oci(_)));
+    }
+
+    #[cfg(feature = "toml-config")]
+    #[test]
+    fn parses_template_layout() {
+        let cfg = SourceChainConfig::from_toml_str(
+            r#"
+[[sources]]
+kind = "dev"
+root_dir = "."
+layout = { template = { pack = "dist/{id}/{version}/{id}.{ext}", component = "target/wasm32-wasip2/release/{id}.{ext}" } }
+"#,
+        )
+        .unwrap();
+        let SourceSpec::Dev(dev) = &cfg.sources[0] else {
+            panic!("expected dev source");
+        };
+        assert_eq!(
+            dev.layout,
+            DevLayoutSpec::Template {
+                pack: "dist/{id}/{version}/{id}.{ext}".into(),
+                component: "target/wasm32-wasip2/release/{id}.{ext}".into(),
+            }
+        );
+    }
+
+    #[cfg(feature = "toml-config")]
+    #[test]
+    fn json_and_toml_agree() {
+        let json = SourceChainConfig::from_json_str(
+            r#"{"sources":[{"kind":"http","base_url":"https://packs.example.com"}]}"#,
+        )
+        .unwrap();
+        let toml = SourceChainConfig::from_toml_str(
+            "[[sources]]\nkind = \"http\"\nbase_url = \"https://packs.example.com\"\n",
+        )
+        .unwrap();
+        assert_eq!(json, toml);
+        assert_eq!(json.strategy, ChainStrategy::Sequential);
+    }
+
+    #[test]
+    fn dev_sources_need_a_factory() {
+        let cfg = SourceChainConfig::from_json_str(
+            r#"{"sources":[{"kind":"dev","root_dir":".greentic/dev"}]}"#,
+        )
+        .unwrap();
+        let err = SourceChainBuilder::new().build(&cfg).err().unwrap();
+        assert!(matches!(err, DistributorError::InvalidConfig(_)));
+    }
+
+    #[test]
+    fn maps_greentic_paths_and_pack_source() {
+        use greentic_config_types::{
+            ConfigVersion, EnvironmentConfig, NetworkConfig, PacksConfig, PathsConfig,
+            RuntimeConfig, SecretsBackendRefConfig, TelemetryConfig,
+        };
+        use greentic_types::EnvId;
+
+        let cfg = GreenticConfig {
+            schema_version: ConfigVersion::default(),
+            environment: EnvironmentConfig {
+                env_id: EnvId::try_from("dev").unwrap(),
+                deployment: None,
+                connection: None,
+                region: None,
+            },
+            paths: PathsConfig {
+                greentic_root: PathBuf::from("/workspace"),
+                state_dir: PathBuf::from("/workspace/state"),
+                cache_dir: PathBuf::from("/workspace/cache"),
+                logs_dir: PathBuf::from("/workspace/logs"),
+            },
+            packs: Some(PacksConfig {
+                source: PackSourceConfig::OciRegistry {
+                    reference: "ghcr.io/acme/packs/".into(),
+                },
+                cache_dir: PathBuf::from("/workspace/cache/packs"),
+                index_cache_ttl_secs: None,
+                trust: None,
+            }),
+            services: None,
+            events: None,
+            runtime: RuntimeConfig::default(),
+            telemetry: TelemetryConfig::default(),
+            network: NetworkConfig::default(),
+            deployer: None,
+            secrets: SecretsBackendRefConfig::default(),
+            dev: None,
+        };
+
+        let chain = SourceChainConfig::from_greentic(&cfg);
+        let SourceSpec::Dev(dev) = &chain.sources[0] else {
+            panic!("expected dev source first");
+        };
+        assert_eq!(dev.root_dir, PathBuf::from("/workspace/.greentic/dev"));
+        let SourceSpec::Cache(cache) = &chain.sources[1] else {
+            panic!("expected cached remote source");
+        };
+        assert_eq!(cache.dir, PathBuf::from("/workspace/cache/packs"));
+        let SourceSpec::Oci(oci) = cache.source.as_ref() else {
+            panic!("expected oci source");
+        };
+        assert_eq!(
+            oci.pack_template.as_deref(),
+            Some("ghcr.io/acme/packs/{id}:{version}")
+        );
+    }
+
+    #[test]
+    fn expands_version_placeholders() {
+        let version = Version::parse("1.4.2").unwrap();
+        assert_eq!(
+            expand_template("repo/{id}:{major}.{minor}-{version}", "hello", &version),
+            "repo/hello:1.4-1.4.2"
+        );
+    }
+}
@@ -0,0 +1,366 @@
+use std::collections::BTreeSet;
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+use std::sync::Once;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::cache_fs::{CacheLock, write_atomic};
+#[cfg(feature = "dist-client")]
+use crate::cache_policy::PruneReport;
+use crate::cache_policy::{self, CachePolicy};
+
+/// Environment variable overriding the store root shared by every fetcher.
+pub const STORE_DIR_ENV: &str = "GREENTIC_DIST_CACHE_DIR";
+
+/// Pack cache root used before the store was unified. It no longer relocates the store;
+/// pack fetches only import digest hits from it.
+pub const LEGACY_PACK_DIR_ENV: &str = "GREENTIC_PACK_CACHE_DIR";
+
+const METADATA_FILENAME: &str = "metadata.json";
+#[cfg(feature = "dist-client")]
+const LEGACY_COMPONENT_FILENAME: &str = "component.wasm";
+
+/// Root of the shared store: `GREENTIC_DIST_CACHE_DIR`, else
+/// `<cache dir>/greentic/components`, else `$GREENTIC_HOME/cache/components`.
+///
+/// The legacy `GREENTIC_PACK_CACHE_DIR` is ignored here (with a deprecation warning); see
+/// [`LEGACY_PACK_DIR_ENV`].
+pub fn default_store_root() -> PathBuf {
+    if let Ok(root) = std::env::var(STORE_DIR_ENV) {
+        return PathBuf::from(root);
+    }
+    if std::env::var_os(LEGACY_PACK_DIR_ENV).is_some() {
+        static WARNED: Once = Once::new();
+        WARNED.call_once(|| {
+            if std::env::var("GREENTIC_SILENCE_DEPRECATION_WARNINGS") != Ok("1".into()) {
+                eprintln!(
+                    "warning: {LEGACY_PACK_DIR_ENV} is deprecated and only read to import old pack cache entries; set {STORE_DIR_ENV} to relocate the store"
+                );
+            }
+        });
+    }
+    if let Some(cache) = dirs_next::cache_dir() {
+        return cache.join("greentic").join("components");
+    }
+    if let Ok(root) = std::env::var("GREENTIC_HOME") {
+        return PathBuf::from(root).join("cache").join("components");
+    }
+    PathBuf::from(".greentic").join("cache").join("components")
+}
+
+/// Root of the pack cache written before the store was unified, which pack fetches
+/// still import entries from.
+#[cfg(feature = "pack-fetch")]
+pub(crate) fn legacy_pack_cache_root() -> PathBuf {
+    if let Ok(root) = std::env::var(LEGACY_PACK_DIR_ENV) {
+        return PathBuf::from(root);
+    }
+    if let Some(cache) = dirs_next::cache_dir() {
+        return cache.join("greentic").join("packs");
+    }
+    if let Ok(root) = std::env::var("GREENTIC_HOME") {
+        return PathBuf::from(root).join("cache").join("packs");
+    }
+    PathBuf::from(".greentic").join("cache").join("packs")
+}
+
+pub(crate) fn compute_digest(bytes: &[u8]) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(bytes);
+    format!("sha256:{:x}", hasher.finalize())
+}
+
+pub(crate) fn normalize_digest(digest: &str) -> String {
+    if digest.starts_with("sha256:") {
+        digest.to_string()
+    } else {
+        format!("sha256:{digest}")
+    }
+}
+
+/// Whether `digest` is `sha256:` followed by 64 lowercase hex characters.
+pub(crate) fn is_sha256_digest(digest: &str) -> bool {
+    digest.strip_prefix("sha256:").is_some_and(|hex| {
+        hex.len() == 64 && hex.bytes().all(|b| matches!(b, b'0'..=b'9' | b'a'..=b'f'))
+    })
+}
+
+pub(crate) fn trim_digest_prefix(digest: &str) -> &str {
+    digest
+        .strip_prefix("sha256:")
+        .unwrap_or_else(|| digest.trim_start_matches('@'))
+}
+
+/// What an entry holds; decides the artifact file name inside the entry.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum ArtifactKind {
+    Component,
+    Pack,
+    Blob,
+}
+
+/// `metadata.json` of a store entry, written last as the entry's commit marker.
+///
+/// Reads the per-cache layouts written before the store was unified: digest blobs
+/// used `digest` and had no reference or kind.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub(crate) struct StoreMetadata {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub kind: Option<ArtifactKind>,
+    #[serde(default)]
+    pub original_reference: String,
+    #[serde(alias = "digest")]
+    pub resolved_digest: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub media_type: Option<String>,
+    #[serde(default)]
+    pub fetched_at_unix_seconds: u64,
+    #[serde(default)]
+    pub size_bytes: u64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub manifest_digest: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub manifest_wasm_name: Option<String>,
+    /// sha256 of the artifact file when the entry is keyed by a manifest digest.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub content_digest: Option<String>,
+    /// Every reference that resolved to this digest (the ref index).
+    #[serde(default, skip_serializing_if = "BTreeSet::is_empty")]
+    pub references: BTreeSet<String>,
+}
+
+impl StoreMetadata {
+    pub(crate) fn new(kind: ArtifactKind, reference: &str, digest: &str, size_bytes: u64) -> Self {
+        Self {
+            kind: Some(kind),
+            original_reference: reference.to_string(),
+            resolved_digest: digest.to_string(),
+            fetched_at_unix_seconds: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_secs(),
+            size_bytes,
+            ..Self::default()
+        }
+    }
+}
+
+/// Content-addressed store shared by `DistClient`, `OciComponentResolver`,
+/// `OciPackFetcher` and `DigestFetcher`.
+///
+/// Each digest owns one `<root>/<sha256 hex>/` entry holding its artifact files and a
+/// `metadata.json`, so an artifact fetched through any API is stored once and is visible
+/// to `cache ls`/`gc`/`prune`.
+#[derive(Clone, Debug)]
+pub(crate) struct BlobStore {
+    root: PathBuf,
+}
+
+impl BlobStore {
+    pub(crate) fn new(root: PathBuf) -> Self {
+        Self { root }
+    }
+
+    /// Entry directory for `digest`; anything but a sha256 digest is refused so it can
+    /// never name a path outside the store.
+    pub(crate) fn entry_dir(&self, digest: &str) -> io::Result<PathBuf> {
+        let digest = normalize_digest(digest);
+        if !is_sha256_digest(&digest) {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("`{digest}` is not a sha256 digest"),
+            ));
+        }
+        Ok(self.root.join(trim_digest_prefix(&digest)))
+    }
+
+    /// Exclusive lock on the entry; hold it from the cache re-check through `commit`.
+    pub(crate) fn lock(&self, digest: &str) -> io::Result<CacheLock> {
+        CacheLock::acquire(&self.entry_dir(digest)?)
+    }
+
+    /// Committed metadata for `digest`; `None` means the entry is missing or incomplete.
+    pub(crate) fn metadata(&self, digest: &str) -> Option<StoreMetadata> {
+        let bytes = fs::read(self.entry_dir(digest).ok()?.join(METADATA_FILENAME)).ok()?;
+        serde_json::from_slice(&bytes).ok()
+    }
+
+    /// Atomically writes one artifact file into the entry.
+    pub(crate) fn write_file(&self, digest: &str, name: &str, data: &[u8]) -> io::Result<PathBuf> {
+        let dir = self.entry_dir(digest)?;
+        fs::create_dir_all(&dir)?;
+        let path = dir.join(name);
+        write_atomic(&path, data)?;
+        Ok(path)
+    }
+
+    /// Writes `metadata` as the commit marker, keeping references recorded by earlier commits.
+    pub(crate) fn commit(&self, digest: &str, mut metadata: StoreMetadata) -> io::Result<()> {
+        if let Some(existing) = self.metadata(digest) {
+            metadata.references.extend(existing.references);
+        }
+        if !metadata.original_reference.is_empty() {
+            metadata
+                .references
+                .insert(metadata.original_reference.clone());
+        }
+        let bytes = serde_json::to_vec_pretty(&metadata).map_err(io::Error::other)?;
+        let dir = self.entry_dir(digest)?;
+        fs::create_dir_all(&dir)?;
+        write_atomic(&dir.join(METADATA_FILENAME), &bytes)?;
+        cache_policy::touch(&dir);
+        Ok(())
+    }
+
+    /// Records an access for LRU eviction.
+    pub(crate) fn touch(&self, digest: &str) {
+        if let Ok(dir) = self.entry_dir(digest) {
+            cache_policy::touch(&dir);
+        }
+    }
+
+    /// Best-effort eviction after a write; the entry just written is kept.
+    pub(crate) fn enforce(&self, policy: &CachePolicy, written: &str) {
+        if !policy.is_unbounded() {
+            let _ = cache_policy::prune(&self.root, policy, Some(written));
+        }
+    }
+}
+
+/// Cache management used by `greentic-dist cache`.
+#[cfg(feature = "dist-client")]
+impl BlobStore {
+    pub(crate) fn prune(&self, policy: &CachePolicy) -> io::Result<PruneReport> {
+        cache_policy::prune(&self.root, policy, None)
+    }
+
+    /// Digests of every entry, whatever fetcher wrote it.
+    pub(crate) fn list(&self) -> Vec<String> {
+        let mut digests = Vec::new();
+        if let Ok(entries) = fs::read_dir(&self.root) {
+            for entry in entries.flatten() {
+                if let Ok(meta) = entry.metadata()
+                    && meta.is_dir()
+                    && let Some(name) = entry.file_name().to_str()
+                {
+                    digests.push(format!("sha256:{name}"));
+                }
+            }
+        }
+        digests.sort();
+        digests
+    }
+
+    pub(crate) fn remove(&self, digest: &str) -> io::Result<()> {
+        match fs::remove_dir_all(self.entry_dir(digest)?) {
+            Err(err) if err.kind() != io::ErrorKind::NotFound => Err(err),
+            _ => Ok(()),
+        }
+    }
+
+    /// Removes entries that never committed (no metadata and no legacy `component.wasm`),
+    /// skipping entries a fetch is still writing.
+    pub(crate) fn gc(&self) -> io::Result<Vec<String>> {
+        let mut removed = Vec::new();
+        for digest in self.list() {
+            let Ok(dir) = self.entry_dir(&digest) else {
+                continue;
+            };
+            if self.metadata(&digest).is_some() || dir.join(LEGACY_COMPONENT_FILENAME).exists() {
+                continue;
+            }
+            if cache_policy::remove_unlocked(&dir)? {
+                removed.push(digest);
+            }
+        }
+        Ok(removed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DIGEST: &str = "sha256:0123456789abcdef0123456789abcdef0123456789abcdef0123456789abcdef";
+
+    #[test]
+    fn commit_indexes_references_and_reads_legacy_metadata() {
+        let temp = tempfile::tempdir().unwrap();
+        let store = BlobStore::new(temp.path().to_path_buf());
+        store.write_file(DIGEST, "pack.gtpack", b"pack").unwrap();
+        assert!(store.metadata(DIGEST).is_none());
+
+        store
+            .commit(
+                DIGEST,
+                StoreMetadata::new(ArtifactKind::Pack, "ghcr.io/acme/pack:1", DIGEST, 4),
+            )
+            .unwrap();
+        store
+            .commit(
+                DIGEST,
+                StoreMetadata::new(ArtifactKind::Pack, "ghcr.io/acme/pack:latest", DIGEST, 4),
+            )
+            .unwrap();
+        let metadata = store.metadata(DIGEST).unwrap();
+        assert_eq!(metadata.kind, Some(ArtifactKind::Pack));
+        assert_eq!(
+            metadata.references.into_iter().collect::<Vec<_>>(),
+            vec!["ghcr.io/acme/pack:1", "ghcr.io/acme/pack:latest"]
+        );
+
+        // Digest blobs written by older releases.
+        fs::write(
+            store.entry_dir(DIGEST).unwrap().join(METADATA_FILENAME),
+            format!(r#"{{"digest":"{DIGEST}","size_bytes":4,"fetched_at_unix_seconds":1}}"#),
+        )
+        .unwrap();
+        let legacy = store.metadata(DIGEST).unwrap();
+        assert_eq!(legacy.resolved_digest, DIGEST);
+        assert_eq!(legacy.kind, None);
+    }
+
+    #[test]
+    fn entry_dir_refuses_non_digests() {
+        let temp = tempfile::tempdir().unwrap();
+        let store = BlobStore::new(temp.path().to_path_buf());
+        assert!(store.entry_dir(DIGEST).unwrap().starts_with(temp.path()));
+        for digest in ["sha256:../../../somewhere", "sha256:ABCD", "../x", ""] {
+            let err = store.entry_dir(digest).unwrap_err();
+            assert_eq!(err.kind(), io::ErrorKind::InvalidInput, "{digest}");
+        }
+        assert!(store.write_file("sha256:../escape", "x", b"x").is_err());
+    }
+
+    #[cfg(feature = "dist-client")]
+    #[test]
+    fn gc_removes_only_uncommitted_entries() {
+        let temp = tempfile::tempdir().unwrap();
+        let store = BlobStore::new(temp.path().to_path_buf());
+        let committed = "sha256:aaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaa";
+        let legacy = "sha256:bbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbb";
+        let broken = "sha256:cccccccccccccccccccccccccccccccccccccccccccccccccccccccccccccccc";
+        store.write_file(committed, "blob.bin", b"blob").unwrap();
+        store
+            .commit(
+                committed,
+                StoreMetadata::new(ArtifactKind::Blob, "", committed, 4),
+            )
+            .unwrap();
+        store
+            .write_file(legacy, LEGACY_COMPONENT_FILENAME, b"wasm")
+            .unwrap();
+        store.write_file(broken, "pack.gtpack", b"partial").unwrap();
+
+        assert_eq!(store.gc().unwrap(), vec![broken.to_string()]);
+        assert_eq!(
+            store.list(),
+            vec![committed.to_string(), legacy.to_string()]
+        );
+    }
+}
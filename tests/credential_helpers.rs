@@ -0,0 +1,96 @@
+#![cfg(all(unix, feature = "dist-client"))]
+
+use std::fs;
+use std::os::unix::fs::PermissionsExt;
+use std::time::{Duration, Instant};
+
+use greentic_distributor_client::{RegistryAuthOptions, RegistryCredentials};
+
+const FAKE_HELPER: &str = r#"#!/bin/sh
+[ "$1" = "get" ] || exit 2
+read server
+case "$server" in
+  ghcr.io) echo '{"ServerURL":"ghcr.io","Username":"helper-user","Secret":"helper-pass"}' ;;
+  https://index.docker.io/v1/) echo '{"ServerURL":"https://index.docker.io/v1/","Username":"<token>","Secret":"hub-token"}' ;;
+  *) echo "credentials not found in native keychain"; exit 1 ;;
+esac
+"#;
+
+#[test]
+fn resolves_credentials_through_docker_credential_helpers() {
+    let temp = tempfile::tempdir().unwrap();
+    let bin = temp.path().join("bin");
+    fs::create_dir_all(&bin).unwrap();
+    for name in ["docker-credential-fake", "docker-credential-desktop"] {
+        let helper = bin.join(name);
+        fs::write(&helper, FAKE_HELPER).unwrap();
+        fs::set_permissions(&helper, fs::Permissions::from_mode(0o755)).unwrap();
+    }
+    let config = temp.path().join("config.json");
+    fs::write(
+        &config,
+        r#"{
+            "auths": {"quay.io": {"auth": "cXVheTpmcm9tLWF1dGhz"}, "ghcr.io": {}},
+            "credHelpers": {"ghcr.io": "fake", "registry.internal": "missing"},
+            "credsStore": "desktop"
+        }"#,
+    )
+    .unwrap();
+    let opts = RegistryAuthOptions {
+        use_env: false,
+        docker_config: Some(config),
+        helper_path: Some(bin.into_os_string()),
+        ..RegistryAuthOptions::default()
+    };
+
+    // credHelpers entry for the registry.
+    assert_eq!(
+        opts.resolve("ghcr.io"),
+        RegistryCredentials::Basic {
+            username: "helper-user".into(),
+            password: "helper-pass".into(),
+        }
+    );
+    // credsStore fallback; `<token>` usernames are identity tokens.
+    assert_eq!(
+        opts.resolve("docker.io"),
+        RegistryCredentials::IdentityToken("hub-token".into())
+    );
+    // Inline auths still win over credsStore.
+    assert_eq!(
+        opts.resolve("quay.io"),
+        RegistryCredentials::Basic {
+            username: "quay".into(),
+            password: "from-auths".into(),
+        }
+    );
+    // Missing helper binaries and "not found" answers fall back to anonymous.
+    assert_eq!(
+        opts.resolve("registry.internal"),
+        RegistryCredentials::Anonymous
+    );
+    assert_eq!(opts.resolve("example.com"), RegistryCredentials::Anonymous);
+}
+
+#[test]
+fn kills_credential_helpers_that_do_not_answer() {
+    let temp = tempfile::tempdir().unwrap();
+    let bin = temp.path().join("bin");
+    fs::create_dir_all(&bin).unwrap();
+    let helper = bin.join("docker-credential-stuck");
+    fs::write(&helper, "#!/bin/sh\nexec sleep 30\n").unwrap();
+    fs::set_permissions(&helper, fs::Permissions::from_mode(0o755)).unwrap();
+    let config = temp.path().join("config.json");
+    fs::write(&config, r#"{"credsStore": "stuck"}"#).unwrap();
+    let opts = RegistryAuthOptions {
+        use_env: false,
+        docker_config: Some(config),
+        helper_path: Some(bin.into_os_string()),
+        helper_timeout: Duration::from_millis(200),
+        ..RegistryAuthOptions::default()
+    };
+
+    let started = Instant::now();
+    assert_eq!(opts.resolve("ghcr.io"), RegistryCredentials::Anonymous);
+    assert!(started.elapsed() < Duration::from_secs(5));
+}
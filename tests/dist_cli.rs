@@ -55,3 +55,419 @@ fn cache_ls_rm_gc_json() {
     // After explicit rm, GC should be a no-op.
     assert!(removed.is_empty());
 }
+
+#[test]
+fn legacy_pack_cache_env_does_not_relocate_the_store() {
+    let legacy = tempfile::tempdir().unwrap();
+    let xdg = tempfile::tempdir().unwrap();
+    let dir = legacy
+        .path()
+        .join("0123456789abcdef0123456789abcdef0123456789abcdef0123456789abcdef");
+    fs::create_dir_all(&dir).unwrap();
+    fs::write(dir.join("pack.gtpack"), b"cached").unwrap();
+
+    let output = Command::new(assert_cmd::cargo::cargo_bin!("greentic-dist"))
+        .env_remove("GREENTIC_DIST_CACHE_DIR")
+        .env("GREENTIC_PACK_CACHE_DIR", legacy.path())
+        .env("XDG_CACHE_HOME", xdg.path())
+        .args(["cache", "ls", "--json"])
+        .assert()
+        .success()
+        .get_output()
+        .clone();
+    let listed: Vec<String> = serde_json::from_slice(&output.stdout).unwrap();
+    assert!(listed.is_empty(), "{listed:?}");
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(
+        stderr.contains("GREENTIC_PACK_CACHE_DIR is deprecated"),
+        "{stderr}"
+    );
+}
+
+#[test]
+fn cache_prune_keeps_pinned_and_recent_entries() {
+    let temp = tempfile::tempdir().unwrap();
+    let dist = |args: &[&str]| {
+        let mut cmd = Command::new(assert_cmd::cargo::cargo_bin!("greentic-dist"));
+        cmd.args(["--cache-dir", temp.path().to_str().unwrap()]);
+        cmd.args(args);
+        for (k, v) in cache_env(&temp) {
+            cmd.env(k, v);
+        }
+        cmd
+    };
+    // Three 1 KiB components, pulled oldest first.
+    let mut digests = Vec::new();
+    for name in ["one", "two", "three"] {
+        let path = temp.path().join(format!("{name}.wasm"));
+        fs::write(&path, format!("{name:>1024}")).unwrap();
+        let out = dist(&["resolve", path.to_str().unwrap(), "--json"])
+            .assert()
+            .success()
+            .get_output()
+            .stdout
+            .clone();
+        let out: serde_json::Value = serde_json::from_slice(&out).unwrap();
+        digests.push(out["digest"].as_str().unwrap().to_string());
+        std::thread::sleep(std::time::Duration::from_millis(20));
+    }
+    dist(&["cache", "pin", &digests[0]]).assert().success();
+    dist(&["cache", "pin", "sha256:missing"]).assert().code(3);
+
+    // Entries also hold their metadata, so each is a little over 1 KiB.
+    let out = dist(&["cache", "prune", "--max-size", "3K", "--json"])
+        .assert()
+        .success()
+        .get_output()
+        .stdout
+        .clone();
+    let report: serde_json::Value = serde_json::from_slice(&out).unwrap();
+    assert_eq!(report["removed"], serde_json::json!([digests[1]]));
+    assert!(report["freed_bytes"].as_u64().unwrap() >= 1024);
+
+    dist(&["cache", "unpin", &digests[0]]).assert().success();
+    dist(&["cache", "prune", "--max-size", "2K"])
+        .assert()
+        .success();
+    let out = dist(&["cache", "ls", "--json"])
+        .assert()
+        .success()
+        .get_output()
+        .stdout
+        .clone();
+    let listed: Vec<String> = serde_json::from_slice(&out).unwrap();
+    assert_eq!(listed, vec![digests[2].clone()]);
+}
+
+#[test]
+fn resolve_reports_override() {
+    let temp = tempfile::tempdir().unwrap();
+    fs::write(temp.path().join("echo.wasm"), b"local echo").unwrap();
+    let overrides = temp.path().join("overrides.json");
+    fs::write(&overrides, br#"{"overrides": {"echo": "echo.wasm"}}"#).unwrap();
+
+    let mut cmd = Command::new(assert_cmd::cargo::cargo_bin!("greentic-dist"));
+    cmd.args(["--cache-dir", temp.path().to_str().unwrap()]);
+    cmd.args(["resolve", "ghcr.io/greentic/echo:1.0.0", "--json"]);
+    cmd.args(["--overrides", overrides.to_str().unwrap(), "--offline"]);
+    for (k, v) in cache_env(&temp) {
+        cmd.env(k, v);
+    }
+    let output = cmd.assert().success().get_output().clone();
+    let out: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(
+        out["overridden"],
+        temp.path().join("echo.wasm").to_str().unwrap()
+    );
+    assert!(String::from_utf8_lossy(&output.stderr).contains("overridden by local file"));
+
+    let mut strict = Command::new(assert_cmd::cargo::cargo_bin!("greentic-dist"));
+    strict.args(["resolve", "ghcr.io/greentic/echo:1.0.0", "--strict"]);
+    strict.args(["--overrides", overrides.to_str().unwrap()]);
+    for (k, v) in cache_env(&temp) {
+        strict.env(k, v);
+    }
+    strict.assert().code(2);
+}
+
+#[test]
+fn auth_login_status_logout() {
+    let temp = tempfile::tempdir().unwrap();
+    let credentials = temp.path().join("greentic/credentials.json");
+    let server = std::panic::catch_unwind(httpmock::MockServer::start).ok();
+    let dist = |args: &[&str]| {
+        let mut cmd = assert_cmd::Command::new(assert_cmd::cargo::cargo_bin!("greentic-dist"));
+        cmd.args(args)
+            .env("GREENTIC_DIST_CREDENTIALS", &credentials);
+        for (k, v) in cache_env(&temp) {
+            cmd.env(k, v);
+        }
+        if let Some(server) = &server {
+            cmd.env("GREENTIC_DIST_STORE_URL", server.base_url())
+                .env("GREENTIC_DIST_ALLOW_INSECURE_LOCAL_HTTP", "1");
+        }
+        cmd
+    };
+
+    if let Some(server) = &server {
+        server.mock(|when, then| {
+            when.method(httpmock::Method::GET)
+                .path("/v1/components/acme/echo/versions/1.0.0");
+            then.status(401);
+        });
+        let denied = dist(&["resolve", "store://acme/echo@1.0.0"])
+            .assert()
+            .code(5)
+            .get_output()
+            .stderr
+            .clone();
+        assert!(String::from_utf8_lossy(&denied).contains("greentic-dist auth login"));
+    }
+
+    dist(&[
+        "auth",
+        "login",
+        "ghcr.io",
+        "--username",
+        "robot",
+        "--password-stdin",
+    ])
+    .write_stdin("s3cret\n")
+    .assert()
+    .success();
+    dist(&["auth", "login", "store://acme", "--token", "store-token"])
+        .assert()
+        .success();
+    dist(&["auth", "login", "quay.io"]).assert().code(2);
+
+    let saved: serde_json::Value =
+        serde_json::from_slice(&fs::read(&credentials).unwrap()).unwrap();
+    // Docker-compatible `auth` entry: base64("robot:s3cret").
+    assert_eq!(saved["auths"]["ghcr.io"]["auth"], "cm9ib3Q6czNjcmV0");
+    assert_eq!(
+        saved["auths"]["store://acme"]["identitytoken"],
+        "store-token"
+    );
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        let mode = fs::metadata(&credentials).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o600);
+    }
+
+    let status = dist(&["auth", "status", "--json"])
+        .assert()
+        .success()
+        .get_output()
+        .stdout
+        .clone();
+    let status: serde_json::Value = serde_json::from_slice(&status).unwrap();
+    assert_eq!(
+        status,
+        serde_json::json!([
+            {"target": "ghcr.io", "kind": "basic", "username": "robot"},
+            {"target": "store://acme", "kind": "token"}
+        ])
+    );
+    assert!(
+        !String::from_utf8_lossy(&dist(&["auth", "status"]).output().unwrap().stdout)
+            .contains("s3cret")
+    );
+
+    // Registry targets are matched by host, however the login spelled them.
+    dist(&["auth", "logout", "https://ghcr.io/"])
+        .assert()
+        .success();
+    let status = dist(&["auth", "status", "--json"])
+        .assert()
+        .success()
+        .get_output()
+        .stdout
+        .clone();
+    let status: Vec<serde_json::Value> = serde_json::from_slice(&status).unwrap();
+    assert_eq!(status.len(), 1);
+}
+
+#[test]
+fn lock_writes_lockfile_from_pack_manifest() {
+    let temp = tempfile::tempdir().unwrap();
+    let component = temp.path().join("echo.wasm");
+    fs::write(&component, b"echo").unwrap();
+    let manifest = temp.path().join("pack.json");
+    let manifest_json = serde_json::json!({
+        "extensions": {
+            "greentic.components": { "refs": [component.to_str().unwrap()] }
+        }
+    });
+    fs::write(&manifest, manifest_json.to_string()).unwrap();
+    let lock_path = temp.path().join("pack.lock.json");
+
+    let mut lock = Command::new(assert_cmd::cargo::cargo_bin!("greentic-dist"));
+    lock.args(["--cache-dir", temp.path().to_str().unwrap(), "lock"]);
+    lock.args(["--manifest", manifest.to_str().unwrap()]);
+    lock.args(["--out", lock_path.to_str().unwrap()]);
+    for (k, v) in cache_env(&temp) {
+        lock.env(k, v);
+    }
+    lock.assert().success();
+
+    let written: serde_json::Value =
+        serde_json::from_slice(&fs::read(&lock_path).unwrap()).unwrap();
+    assert_eq!(written["schema_version"], 1);
+    let entry = &written["components"][0];
+    assert_eq!(entry["reference"], component.to_str().unwrap());
+    assert_eq!(entry["size_bytes"], 4);
+    assert_eq!(entry["source"], "file");
+    let digest = entry["digest"].as_str().unwrap();
+
+    let mut pull = Command::new(assert_cmd::cargo::cargo_bin!("greentic-dist"));
+    pull.args(["--cache-dir", temp.path().to_str().unwrap(), "--offline"]);
+    pull.args(["pull", "--lock", lock_path.to_str().unwrap()]);
+    for (k, v) in cache_env(&temp) {
+        pull.env(k, v);
+    }
+    let out = pull.assert().success().get_output().stdout.clone();
+    assert!(String::from_utf8_lossy(&out).starts_with(digest));
+
+    let mut pull_json = Command::new(assert_cmd::cargo::cargo_bin!("greentic-dist"));
+    pull_json.args(["--cache-dir", temp.path().to_str().unwrap()]);
+    pull_json.args(["pull", "--lock", lock_path.to_str().unwrap(), "--json"]);
+    for (k, v) in cache_env(&temp) {
+        pull_json.env(k, v);
+    }
+    let out = pull_json.assert().success().get_output().stdout.clone();
+    let events: Vec<serde_json::Value> = String::from_utf8_lossy(&out)
+        .lines()
+        .map(|line| serde_json::from_str(line).unwrap())
+        .collect();
+    assert_eq!(events.first().unwrap()["event"], "started");
+    let last = events.last().unwrap();
+    assert_eq!(last["event"], "finished");
+    assert_eq!(last["index"], 0);
+    assert_eq!(last["digest"], digest);
+
+    let mut empty = Command::new(assert_cmd::cargo::cargo_bin!("greentic-dist"));
+    empty.args(["--cache-dir", temp.path().to_str().unwrap(), "lock"]);
+    for (k, v) in cache_env(&temp) {
+        empty.env(k, v);
+    }
+    empty.assert().code(2);
+}
+
+#[test]
+fn verify_lock_fails_on_corrupted_cache() {
+    let temp = tempfile::tempdir().unwrap();
+    let component = temp.path().join("echo.wasm");
+    fs::write(&component, b"echo").unwrap();
+    let lock_path = temp.path().join("pack.lock.json");
+    let run = |args: &[&str]| {
+        let mut cmd = Command::new(assert_cmd::cargo::cargo_bin!("greentic-dist"));
+        cmd.args(["--cache-dir", temp.path().to_str().unwrap()]);
+        cmd.args(args);
+        for (k, v) in cache_env(&temp) {
+            cmd.env(k, v);
+        }
+        cmd.assert()
+    };
+    run(&[
+        "lock",
+        component.to_str().unwrap(),
+        "--out",
+        lock_path.to_str().unwrap(),
+    ])
+    .success();
+    let lock = lock_path.to_str().unwrap();
+    run(&["pull", "--frozen", "--lock", lock]).success();
+
+    let out = run(&["verify", "--lock", lock, "--json"])
+        .success()
+        .get_output()
+        .stdout
+        .clone();
+    let report: serde_json::Value = serde_json::from_slice(&out).unwrap();
+    assert_eq!(report["verified"].as_array().unwrap().len(), 1);
+
+    let digest = report["verified"][0].as_str().unwrap();
+    let cached = temp
+        .path()
+        .join(digest.trim_start_matches("sha256:"))
+        .join("component.wasm");
+    fs::write(cached, b"tampered").unwrap();
+    let out = run(&["verify", "--lock", lock, "--json"])
+        .code(6)
+        .get_output()
+        .stdout
+        .clone();
+    let report: serde_json::Value = serde_json::from_slice(&out).unwrap();
+    assert_eq!(report["mismatched"][0]["digest"], digest);
+}
+
+/// Copies `greentic-dist` into `dir`, so the forwarding only sees what the test put there.
+fn install_dist(dir: &std::path::Path) -> std::path::PathBuf {
+    let exe = |name: &str| format!("{name}{}", std::env::consts::EXE_SUFFIX);
+    let dist = std::path::Path::new(assert_cmd::cargo::cargo_bin!("greentic-dist"));
+    let installed = dir.join(exe("greentic-dist"));
+    fs::copy(dist, &installed).unwrap();
+    installed
+}
+
+/// Writes a stub `greentic-dist-dev` into `dir` that echoes its arguments one per line
+/// and fails with exit code 3 when the last one is `missing`.
+#[cfg(unix)]
+fn install_stub_helper(dir: &std::path::Path) {
+    use std::os::unix::fs::PermissionsExt;
+    let helper = dir.join("greentic-dist-dev");
+    fs::write(
+        &helper,
+        r#"#!/bin/sh
+for arg in "$@"; do echo "$arg"; done
+for last in "$@"; do :; done
+if [ "$last" = missing ]; then echo "not found" >&2; exit 3; fi
+"#,
+    )
+    .unwrap();
+    fs::set_permissions(&helper, fs::Permissions::from_mode(0o755)).unwrap();
+}
+
+#[cfg(unix)]
+#[test]
+fn dev_forwards_to_helper_next_to_the_binary() {
+    let temp = tempfile::tempdir().unwrap();
+    let bin = temp.path().join("bin");
+    fs::create_dir_all(&bin).unwrap();
+    let dist = install_dist(&bin);
+    install_stub_helper(&bin);
+    let dev = |args: &[&str]| {
+        Command::new(&dist)
+            // No helper on PATH: only the one next to the binary can answer.
+            .env("PATH", temp.path())
+            .arg("dev")
+            .args(args)
+            .assert()
+    };
+
+    let args = [
+        "--root",
+        "dev root",
+        "publish",
+        "component",
+        "echo",
+        "0.1.0",
+    ];
+    let out = dev(&args).success().get_output().stdout.clone();
+    assert_eq!(
+        String::from_utf8(out).unwrap().lines().collect::<Vec<_>>(),
+        args
+    );
+
+    // The helper's own exit code and message come through unchanged.
+    let out = dev(&["remove", "component", "echo", "missing"])
+        .code(3)
+        .get_output()
+        .clone();
+    assert_eq!(String::from_utf8(out.stderr).unwrap(), "not found\n");
+}
+
+#[test]
+fn dev_reports_a_missing_helper() {
+    let temp = tempfile::tempdir().unwrap();
+    let dist = install_dist(temp.path());
+    let out = Command::new(&dist)
+        .env("PATH", temp.path())
+        .args(["dev", "index"])
+        .assert()
+        .code(10)
+        .get_output()
+        .stderr
+        .clone();
+    let stderr = String::from_utf8(out).unwrap();
+    assert!(
+        stderr.contains("failed to run greentic-dist-dev"),
+        "{stderr}"
+    );
+    assert!(
+        stderr.contains("needs the greentic-dist-dev helper"),
+        "{stderr}"
+    );
+    assert!(stderr.contains("cargo install greentic-distributor-dev"));
+}
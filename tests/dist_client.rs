@@ -1,8 +1,12 @@
 #![cfg(feature = "dist-client")]
 
-use greentic_distributor_client::dist::{DistClient, DistOptions};
+use greentic_distributor_client::dist::{
+    ArtifactSource, DistClient, DistError, DistOptions, LOCKFILE_SCHEMA_VERSION, LockSource,
+    PullEvent,
+};
 use sha2::{Digest, Sha256};
 use std::fs;
+use std::sync::{Arc, Mutex};
 use tempfile::TempDir;
 
 fn digest_for(bytes: &[u8]) -> String {
@@ -17,6 +21,15 @@ fn options(dir: &TempDir) -> DistOptions {
         allow_tags: true,
         offline: false,
         allow_insecure_local_http: true,
+        overrides_file: None,
+        strict: false,
+        credentials_file: None,
+        store_url: None,
+        repo_url: None,
+        cache_policy: Default::default(),
+        frozen: false,
+        pull_concurrency: 8,
+        require_integrity: false,
     }
 }
 
@@ -67,6 +80,85 @@ async fn caches_http_download() {
     mock.assert_async().await;
 }
 
+#[tokio::test]
+async fn verifies_http_integrity_before_caching() {
+    let server = match std::panic::catch_unwind(httpmock::MockServer::start) {
+        Ok(s) => s,
+        Err(_) => {
+            eprintln!(
+                "skipping http integrity test: unable to bind mock server in this environment"
+            );
+            return;
+        }
+    };
+    let mock = server.mock(|when, then| {
+        when.method(httpmock::Method::GET).path("/component.wasm");
+        then.status(200).body("from-http");
+    });
+    let url = format!("{}/component.wasm", server.base_url());
+    let expected = digest_for(b"from-http");
+    let hex = expected.trim_start_matches("sha256:");
+    let stale = digest_for(b"older build");
+
+    let temp = tempfile::tempdir().unwrap();
+    let strict = DistClient::new(DistOptions {
+        require_integrity: true,
+        ..options(&temp)
+    });
+    let err = strict.ensure_cached(&url).await.unwrap_err();
+    assert!(
+        matches!(err, DistError::IntegrityRequired { .. }),
+        "{err:?}"
+    );
+    assert_eq!(err.exit_code(), 2);
+
+    let tampered = format!("{url}#sha256={}", stale.trim_start_matches("sha256:"));
+    let err = strict.ensure_cached(&tampered).await.unwrap_err();
+    assert!(matches!(err, DistError::DigestMismatch { .. }), "{err:?}");
+    assert!(
+        strict.list_cache().is_empty(),
+        "mismatched bytes must not be cached"
+    );
+
+    let err = strict
+        .ensure_cached(&format!("{url}#sha256=not-hex"))
+        .await
+        .unwrap_err();
+    assert!(matches!(err, DistError::InvalidReference { .. }), "{err:?}");
+
+    let resolved = strict
+        .ensure_cached(&format!("{url}#sha256={hex}"))
+        .await
+        .unwrap();
+    assert_eq!(resolved.digest, expected);
+
+    // A lock digest is an integrity hint too, even when the pull is not frozen.
+    let other = tempfile::tempdir().unwrap();
+    let lock_path = other.path().join("pack.lock.json");
+    let client = DistClient::new(DistOptions {
+        require_integrity: true,
+        ..options(&other)
+    });
+    fs::write(
+        &lock_path,
+        serde_json::to_vec(&serde_json::json!([{ "reference": url, "digest": stale }])).unwrap(),
+    )
+    .unwrap();
+    let err = client.pull_lock(&lock_path).await.unwrap_err();
+    assert!(matches!(err, DistError::DigestMismatch { .. }), "{err:?}");
+    assert!(client.list_cache().is_empty());
+    fs::write(
+        &lock_path,
+        serde_json::to_vec(&serde_json::json!([{ "reference": url, "digest": expected }])).unwrap(),
+    )
+    .unwrap();
+    assert_eq!(
+        client.pull_lock(&lock_path).await.unwrap()[0].digest,
+        expected
+    );
+    mock.assert_calls_async(4).await;
+}
+
 #[tokio::test]
 async fn pulls_lockfile_entries() {
     let temp = tempfile::tempdir().unwrap();
@@ -119,6 +211,533 @@ async fn respects_canonical_lockfile_with_schema_version() {
     assert!(resolved[0].cache_path.as_ref().unwrap().exists());
 }
 
+#[tokio::test]
+async fn lock_pins_references_and_round_trips_through_pull_lock() {
+    let server = match std::panic::catch_unwind(httpmock::MockServer::start) {
+        Ok(s) => s,
+        Err(_) => {
+            eprintln!("skipping lock test: unable to bind mock server in this environment");
+            return;
+        }
+    };
+    server.mock(|when, then| {
+        when.method(httpmock::Method::GET).path("/remote.wasm");
+        then.status(200).body("remote-bytes");
+    });
+    let temp = tempfile::tempdir().unwrap();
+    let file = temp.path().join("local.wasm");
+    fs::write(&file, b"local").unwrap();
+    let url = format!("{}/remote.wasm", server.base_url());
+    let refs = vec![file.to_str().unwrap().to_string(), url.clone()];
+
+    let client = DistClient::new(options(&temp));
+    let lockfile = client.lock(&refs).await.unwrap();
+    assert_eq!(lockfile.schema_version, LOCKFILE_SCHEMA_VERSION);
+    let [local, remote] = lockfile.components.as_slice() else {
+        panic!("expected two entries: {lockfile:?}");
+    };
+    assert_eq!(local.reference, refs[0]);
+    assert_eq!(local.digest, digest_for(b"local"));
+    assert_eq!(local.size_bytes, 5);
+    assert_eq!(local.source, LockSource::File);
+    assert_eq!(remote.reference, url);
+    assert_eq!(remote.digest, digest_for(b"remote-bytes"));
+    assert_eq!(remote.media_type, "application/wasm");
+    assert_eq!(remote.source, LockSource::Http);
+
+    // Every written field is documented by the published schema.
+    let schema: serde_json::Value =
+        serde_json::from_str(include_str!("../docs/lockfile.schema.json")).unwrap();
+    let written = serde_json::to_value(&lockfile).unwrap();
+    let documented = &schema["$defs"]["component"]["properties"];
+    for key in written["components"][0].as_object().unwrap().keys() {
+        assert!(documented.get(key).is_some(), "`{key}` missing from schema");
+    }
+
+    let lock_path = temp.path().join("pack.lock.json");
+    fs::write(&lock_path, serde_json::to_vec(&lockfile).unwrap()).unwrap();
+    let offline = DistClient::new(DistOptions {
+        offline: true,
+        ..options(&temp)
+    });
+    let pulled = offline.pull_lock(&lock_path).await.unwrap();
+    let digests: Vec<_> = pulled.iter().map(|item| item.digest.clone()).collect();
+    assert_eq!(digests, vec![local.digest.clone(), remote.digest.clone()]);
+
+    let newer =
+        serde_json::json!({ "schema_version": LOCKFILE_SCHEMA_VERSION + 1, "components": [] });
+    fs::write(&lock_path, serde_json::to_vec(&newer).unwrap()).unwrap();
+    let err = offline.pull_lock(&lock_path).await.unwrap_err();
+    assert!(matches!(err, DistError::InvalidInput(_)), "{err:?}");
+}
+
+#[tokio::test]
+async fn verify_lock_reports_missing_mismatched_and_extra_entries() {
+    let temp = tempfile::tempdir().unwrap();
+    let client = DistClient::new(options(&temp));
+    let mut cached = Vec::new();
+    for name in ["good", "corrupt", "extra"] {
+        let file = temp.path().join(format!("{name}.wasm"));
+        fs::write(&file, name).unwrap();
+        cached.push(client.ensure_cached(file.to_str().unwrap()).await.unwrap());
+    }
+    fs::write(cached[1].cache_path.as_ref().unwrap(), b"tampered").unwrap();
+    let absent = digest_for(b"never cached");
+
+    let lock_path = temp.path().join("pack.lock.json");
+    let lock_contents = serde_json::json!({
+        "schema_version": 1,
+        "components": [
+            { "reference": "good.wasm", "digest": cached[0].digest },
+            { "reference": "corrupt.wasm", "digest": cached[1].digest },
+            { "reference": "absent.wasm", "digest": absent },
+            "ghcr.io/greentic/echo:1.0.0"
+        ]
+    });
+    fs::write(&lock_path, serde_json::to_vec(&lock_contents).unwrap()).unwrap();
+
+    let report = client.verify_lock(&lock_path).unwrap();
+    assert!(!report.is_ok());
+    assert_eq!(report.verified, vec![cached[0].digest.clone()]);
+    assert_eq!(report.mismatched.len(), 1);
+    assert_eq!(report.mismatched[0].digest, cached[1].digest);
+    assert_eq!(
+        report.mismatched[0].actual.as_deref(),
+        Some(digest_for(b"tampered").as_str())
+    );
+    assert_eq!(report.missing.len(), 1);
+    assert_eq!(report.missing[0].digest, absent);
+    assert_eq!(report.unpinned, vec!["ghcr.io/greentic/echo:1.0.0"]);
+    assert_eq!(report.extra, vec![cached[2].digest.clone()]);
+}
+
+#[tokio::test]
+async fn lockfile_rejects_digests_that_escape_the_store() {
+    let temp = tempfile::tempdir().unwrap();
+    let cache = temp.path().join("cache");
+    // A file the traversal digest would resolve to if it were joined onto the store root.
+    let outside = temp.path().join("somewhere");
+    fs::create_dir_all(&outside).unwrap();
+    fs::write(outside.join("component.wasm"), b"not from the store").unwrap();
+    let lock_path = temp.path().join("pack.lock.json");
+    let lock = serde_json::json!([{
+        "reference": "ghcr.io/greentic/echo:1.0.0",
+        "digest": "sha256:../somewhere",
+    }]);
+    fs::write(&lock_path, serde_json::to_vec(&lock).unwrap()).unwrap();
+    let client = DistClient::new(DistOptions {
+        cache_dir: cache,
+        ..options(&temp)
+    });
+
+    let err = client.verify_lock(&lock_path).unwrap_err();
+    assert!(matches!(err, DistError::InvalidInput(_)), "{err:?}");
+    let err = client.pull_lock(&lock_path).await.unwrap_err();
+    assert!(matches!(err, DistError::InvalidInput(_)), "{err:?}");
+}
+
+#[tokio::test]
+async fn frozen_pull_lock_requires_digests_and_checks_fetches() {
+    let temp = tempfile::tempdir().unwrap();
+    let file = temp.path().join("echo.wasm");
+    fs::write(&file, b"echo").unwrap();
+    let reference = file.to_str().unwrap();
+    let lock_path = temp.path().join("pack.lock.json");
+    let write_lock = |components: serde_json::Value| {
+        let lock = serde_json::json!({ "schema_version": 1, "components": components });
+        fs::write(&lock_path, serde_json::to_vec(&lock).unwrap()).unwrap();
+    };
+    let client = DistClient::new(DistOptions {
+        frozen: true,
+        ..options(&temp)
+    });
+
+    write_lock(serde_json::json!([reference]));
+    let err = client.pull_lock(&lock_path).await.unwrap_err();
+    assert!(matches!(err, DistError::NotPinned { .. }), "{err:?}");
+    assert_eq!(err.exit_code(), 2);
+
+    let stale = digest_for(b"older echo");
+    write_lock(serde_json::json!([{ "reference": reference, "digest": stale }]));
+    let err = client.pull_lock(&lock_path).await.unwrap_err();
+    assert!(matches!(err, DistError::DigestMismatch { .. }), "{err:?}");
+
+    write_lock(serde_json::json!([{ "reference": reference, "digest": digest_for(b"echo") }]));
+    let resolved = client.pull_lock(&lock_path).await.unwrap();
+    assert_eq!(resolved[0].digest, digest_for(b"echo"));
+    assert!(resolved[0].cache_path.as_ref().unwrap().exists());
+}
+
+#[tokio::test]
+async fn version_constrained_entries_pull_by_repository_digest() {
+    let temp = tempfile::tempdir().unwrap();
+    let file = temp.path().join("echo.wasm");
+    fs::write(&file, b"echo 1.4.2").unwrap();
+    let digest = DistClient::new(options(&temp))
+        .ensure_cached(file.to_str().unwrap())
+        .await
+        .unwrap()
+        .digest;
+    let lock_path = temp.path().join("pack.lock.json");
+    let write_lock = |components: serde_json::Value| {
+        let lock = serde_json::json!({ "schema_version": 1, "components": components });
+        fs::write(&lock_path, serde_json::to_vec(&lock).unwrap()).unwrap();
+    };
+    let offline = DistClient::new(DistOptions {
+        offline: true,
+        ..options(&temp)
+    });
+
+    // Pinned constraint entries are fetched as `repository@digest`, no tag listing needed.
+    let pinned = serde_json::json!({
+        "name": "echo",
+        "repository": "ghcr.io/greentic/echo",
+        "version": "^1.4",
+        "digest": digest,
+    });
+    write_lock(serde_json::json!([pinned]));
+    let resolved = offline.pull_lock(&lock_path).await.unwrap();
+    assert_eq!(resolved[0].digest, digest);
+    assert!(!resolved[0].fetched);
+
+    let unpinned = serde_json::json!({ "repository": "ghcr.io/greentic/echo", "version": "^2" });
+    write_lock(serde_json::json!([pinned, unpinned]));
+    let report = offline.verify_lock(&lock_path).unwrap();
+    assert_eq!(report.verified, vec![digest.clone()]);
+    assert_eq!(report.unpinned, vec!["ghcr.io/greentic/echo@^2"]);
+    let err = offline.pull_lock(&lock_path).await.unwrap_err();
+    assert!(matches!(err, DistError::Offline { .. }), "{err:?}");
+    let err = offline.outdated(&lock_path).await.unwrap_err();
+    assert!(matches!(err, DistError::Offline { .. }), "{err:?}");
+
+    let frozen = DistClient::new(DistOptions {
+        frozen: true,
+        ..options(&temp)
+    });
+    let err = frozen.pull_lock(&lock_path).await.unwrap_err();
+    assert!(matches!(err, DistError::NotPinned { .. }), "{err:?}");
+
+    write_lock(serde_json::json!([
+        { "repository": "ghcr.io/greentic/echo", "version": "one point four" }
+    ]));
+    let err = offline.pull_lock(&lock_path).await.unwrap_err();
+    assert!(matches!(err, DistError::InvalidInput(_)), "{err:?}");
+}
+
+#[tokio::test]
+async fn pull_lock_fetches_concurrently_once_per_digest_with_progress() {
+    let server = match std::panic::catch_unwind(httpmock::MockServer::start) {
+        Ok(s) => s,
+        Err(_) => {
+            eprintln!(
+                "skipping concurrent pull test: unable to bind mock server in this environment"
+            );
+            return;
+        }
+    };
+    let mocks: Vec<_> = (0..4)
+        .map(|i| {
+            server.mock(|when, then| {
+                when.method(httpmock::Method::GET)
+                    .path(format!("/c{i}.wasm"));
+                then.status(200)
+                    .delay(std::time::Duration::from_millis(50))
+                    .body(format!("component-{i}"));
+            })
+        })
+        .collect();
+    let url = |i: usize| format!("{}/c{i}.wasm", server.base_url());
+
+    let temp = tempfile::tempdir().unwrap();
+    let lock_path = temp.path().join("pack.lock.json");
+    let lock_contents = serde_json::json!({
+        "components": [
+            url(0),
+            { "reference": url(1), "digest": digest_for(b"component-1") },
+            url(2),
+            url(0),
+            // Same digest as entry 1 under another reference: fetched once.
+            { "reference": url(3), "digest": digest_for(b"component-1") },
+            url(3)
+        ]
+    });
+    fs::write(&lock_path, serde_json::to_vec(&lock_contents).unwrap()).unwrap();
+
+    let client = Arc::new(DistClient::new(DistOptions {
+        pull_concurrency: 2,
+        ..options(&temp)
+    }));
+    let events = Arc::new(Mutex::new(Vec::new()));
+    let (task_client, task_events) = (client.clone(), events.clone());
+    // Spawning proves the pull future is `Send`.
+    let resolved = tokio::spawn(async move {
+        task_client
+            .pull_lock_with_progress(&lock_path, &move |event| {
+                task_events.lock().unwrap().push(event)
+            })
+            .await
+    })
+    .await
+    .unwrap()
+    .unwrap();
+
+    let digests: Vec<_> = resolved.iter().map(|r| r.digest.clone()).collect();
+    let expected: Vec<_> = [0, 1, 2, 0, 1, 3]
+        .iter()
+        .map(|i| digest_for(format!("component-{i}").as_bytes()))
+        .collect();
+    assert_eq!(digests, expected);
+    mocks[0].assert_calls(1);
+    mocks[1].assert_calls(1);
+    mocks[2].assert_calls(1);
+    mocks[3].assert_calls(1);
+
+    let events = events.lock().unwrap();
+    let mut in_flight = 0;
+    let mut started = Vec::new();
+    for event in events.iter() {
+        match event {
+            PullEvent::Started { index, .. } => {
+                in_flight += 1;
+                assert!(in_flight <= 2, "concurrency bound exceeded: {events:?}");
+                started.push(*index);
+            }
+            PullEvent::Finished { .. } => in_flight -= 1,
+            PullEvent::Bytes {
+                received, total, ..
+            } => assert!(total.is_none_or(|total| *received <= total)),
+            PullEvent::Failed { .. } => panic!("unexpected failure: {events:?}"),
+        }
+    }
+    started.sort();
+    assert_eq!(started, vec![0, 1, 2, 5]);
+    assert!(events.iter().any(|event| matches!(
+        event,
+        PullEvent::Bytes {
+            index: 0,
+            received: 11,
+            ..
+        }
+    )));
+}
+
+#[tokio::test]
+async fn pull_lock_attempts_every_entry_and_reports_failures() {
+    let temp = tempfile::tempdir().unwrap();
+    let file = temp.path().join("ok.wasm");
+    fs::write(&file, b"ok").unwrap();
+    let missing = temp.path().join("missing.wasm");
+    let lock_path = temp.path().join("pack.lock.json");
+    let lock_contents = serde_json::json!([missing.to_str().unwrap(), file.to_str().unwrap()]);
+    fs::write(&lock_path, serde_json::to_vec(&lock_contents).unwrap()).unwrap();
+
+    let client = DistClient::new(options(&temp));
+    let events = Mutex::new(Vec::new());
+    let err = client
+        .pull_lock_with_progress(&lock_path, &|event| events.lock().unwrap().push(event))
+        .await
+        .unwrap_err();
+    assert!(matches!(err, DistError::InvalidReference { .. }), "{err:?}");
+    let events = events.into_inner().unwrap();
+    assert!(
+        events
+            .iter()
+            .any(|event| matches!(event, PullEvent::Failed { index: 0, .. }))
+    );
+    assert!(
+        events
+            .iter()
+            .any(|event| matches!(event, PullEvent::Finished { index: 1, .. }))
+    );
+}
+
+#[tokio::test]
+async fn pull_lock_reports_unresolvable_ranges_as_failed_entries() {
+    let temp = tempfile::tempdir().unwrap();
+    let file = temp.path().join("ok.wasm");
+    fs::write(&file, b"ok").unwrap();
+    let lock_path = temp.path().join("pack.lock.json");
+    let lock = serde_json::json!({
+        "schema_version": 1,
+        "components": [
+            { "repository": "ghcr.io/greentic/echo", "version": "one point four" },
+            { "reference": file.to_str().unwrap() },
+        ],
+    });
+    fs::write(&lock_path, serde_json::to_vec(&lock).unwrap()).unwrap();
+
+    let client = DistClient::new(options(&temp));
+    let events = Mutex::new(Vec::new());
+    let err = client
+        .pull_lock_with_progress(&lock_path, &|event| events.lock().unwrap().push(event))
+        .await
+        .unwrap_err();
+    assert!(matches!(err, DistError::InvalidInput(_)), "{err:?}");
+    let events = events.into_inner().unwrap();
+    assert!(events.contains(&PullEvent::Started {
+        index: 0,
+        reference: "ghcr.io/greentic/echo@one point four".into(),
+    }));
+    assert!(events.iter().any(|event| matches!(
+        event,
+        PullEvent::Failed { index: 0, reference, .. } if reference == "ghcr.io/greentic/echo@one point four"
+    )));
+    assert!(
+        events
+            .iter()
+            .any(|event| matches!(event, PullEvent::Finished { index: 1, .. })),
+        "{events:?}"
+    );
+}
+
+#[tokio::test]
+async fn outdated_and_update_repin_moved_store_versions() {
+    let server = match std::panic::catch_unwind(httpmock::MockServer::start) {
+        Ok(s) => s,
+        Err(_) => {
+            eprintln!("skipping outdated test: unable to bind mock server in this environment");
+            return;
+        }
+    };
+    for (component, body) in [("echo", "echo v2"), ("other", "other v1")] {
+        let digest = digest_for(body.as_bytes());
+        server.mock(|when, then| {
+            when.method(httpmock::Method::GET)
+                .path(format!("/v1/components/acme/{component}/versions/latest"));
+            then.status(200).json_body(serde_json::json!({
+                "digest": digest,
+                "location": format!("{}/blobs/{component}.wasm", server.base_url()),
+            }));
+        });
+        server.mock(|when, then| {
+            when.method(httpmock::Method::GET)
+                .path(format!("/blobs/{component}.wasm"));
+            then.status(200).body(body);
+        });
+    }
+
+    let temp = tempfile::tempdir().unwrap();
+    let pinned_file = temp.path().join("pinned.wasm");
+    fs::write(&pinned_file, b"pinned").unwrap();
+    let lock_path = temp.path().join("pack.lock.json");
+    let original = format!(
+        r#"{{
+  "schema_version": 1,
+  "components": [
+    {{
+      "name": "echo",
+      "reference": "store://acme/echo",
+      "digest": "{}",
+      "media_type": "application/wasm",
+      "size_bytes": 3,
+      "source": "store",
+      "note": "kept"
+    }},
+    {{
+      "reference": "store://acme/other",
+      "digest": "{}"
+    }},
+    {{
+      "reference": "{}",
+      "digest": "{}"
+    }}
+  ]
+}}
+"#,
+        digest_for(b"echo v1"),
+        digest_for(b"other v1"),
+        pinned_file.display(),
+        digest_for(b"pinned"),
+    );
+    fs::write(&lock_path, &original).unwrap();
+    let client = DistClient::new(DistOptions {
+        store_url: Some(server.base_url()),
+        ..options(&temp)
+    });
+
+    let drift = client.outdated(&lock_path).await.unwrap();
+    assert_eq!(drift.len(), 2, "file refs are not tracked: {drift:?}");
+    assert!(drift[0].moved);
+    assert_eq!(drift[0].name.as_deref(), Some("echo"));
+    assert_eq!(drift[0].current, digest_for(b"echo v2"));
+    assert!(!drift[1].moved);
+
+    let err = client
+        .update_lock(&lock_path, Some("missing"))
+        .await
+        .unwrap_err();
+    assert!(matches!(err, DistError::InvalidInput(_)), "{err:?}");
+    assert!(
+        client
+            .update_lock(&lock_path, Some("store://acme/other"))
+            .await
+            .unwrap()
+            .is_empty()
+    );
+    assert_eq!(fs::read_to_string(&lock_path).unwrap(), original);
+
+    let updated = client.update_lock(&lock_path, None).await.unwrap();
+    assert_eq!(updated.len(), 1);
+    assert_eq!(updated[0].index, 0);
+    let expected = original
+        .replace(&digest_for(b"echo v1"), &digest_for(b"echo v2"))
+        .replace(r#""size_bytes": 3"#, r#""size_bytes": 7"#);
+    assert_eq!(fs::read_to_string(&lock_path).unwrap(), expected);
+    assert!(
+        client
+            .outdated(&lock_path)
+            .await
+            .unwrap()
+            .iter()
+            .all(|entry| !entry.moved)
+    );
+}
+
+#[tokio::test]
+async fn pull_lock_refuses_a_tag_that_moved_since_locking() {
+    let server = match std::panic::catch_unwind(httpmock::MockServer::start) {
+        Ok(s) => s,
+        Err(_) => {
+            eprintln!("skipping moved tag test: unable to bind mock server in this environment");
+            return;
+        }
+    };
+    // `latest` now points at v2, but the lock was written against v1.
+    server.mock(|when, then| {
+        when.method(httpmock::Method::GET)
+            .path("/v1/components/acme/echo/versions/latest");
+        then.status(200).json_body(serde_json::json!({
+            "digest": digest_for(b"echo v2"),
+            "location": format!("{}/blobs/echo.wasm", server.base_url()),
+        }));
+    });
+    server.mock(|when, then| {
+        when.method(httpmock::Method::GET).path("/blobs/echo.wasm");
+        then.status(200).body("echo v2");
+    });
+
+    let temp = tempfile::tempdir().unwrap();
+    let lock_path = temp.path().join("pack.lock.json");
+    let locked = digest_for(b"echo v1");
+    let lock = serde_json::json!([{ "reference": "store://acme/echo", "digest": locked }]);
+    fs::write(&lock_path, serde_json::to_vec(&lock).unwrap()).unwrap();
+    let client = DistClient::new(DistOptions {
+        store_url: Some(server.base_url()),
+        ..options(&temp)
+    });
+
+    let err = client.pull_lock(&lock_path).await.unwrap_err();
+    match err {
+        DistError::DigestMismatch {
+            expected, actual, ..
+        } => {
+            assert_eq!(expected, locked);
+            assert_eq!(actual, digest_for(b"echo v2"));
+        }
+        other => panic!("expected a digest mismatch, got {other:?}"),
+    }
+}
+
 #[tokio::test]
 async fn offline_mode_blocks_http_fetch() {
     let temp = tempfile::tempdir().unwrap();
@@ -144,3 +763,217 @@ async fn rejects_insecure_http_fetch() {
     let msg = format!("{err}");
     assert!(msg.contains("insecure url"), "unexpected error: {msg}");
 }
+
+#[tokio::test]
+async fn applies_overrides_file_unless_strict() {
+    let temp = tempfile::tempdir().unwrap();
+    fs::write(temp.path().join("echo.wasm"), b"local echo").unwrap();
+    let overrides = temp.path().join("overrides.json");
+    fs::write(
+        &overrides,
+        br#"{"overrides": {"ghcr.io/greentic/echo": "echo.wasm"}}"#,
+    )
+    .unwrap();
+    let reference = format!("oci://ghcr.io/greentic/echo@{}", digest_for(b"published"));
+
+    let mut opts = options(&temp);
+    opts.offline = true;
+    opts.overrides_file = Some(overrides);
+    let client = DistClient::new(opts.clone());
+    let resolved = client.resolve_ref(&reference).await.unwrap();
+    assert_eq!(resolved.digest, digest_for(b"local echo"));
+    assert!(!resolved.fetched);
+    match resolved.source {
+        ArtifactSource::Overridden { reference: r, path } => {
+            assert_eq!(r, reference);
+            assert_eq!(path, temp.path().join("echo.wasm"));
+        }
+        other => panic!("expected override, got {other:?}"),
+    }
+
+    // Unmatched references resolve as usual.
+    let err = client
+        .resolve_ref("ghcr.io/greentic/other:1.0.0")
+        .await
+        .unwrap_err();
+    assert!(matches!(err, DistError::Offline { .. }));
+
+    opts.strict = true;
+    let err = DistClient::new(opts)
+        .resolve_ref(&reference)
+        .await
+        .unwrap_err();
+    assert!(matches!(err, DistError::OverrideRefused { .. }));
+    assert_eq!(err.exit_code(), 2);
+}
+
+#[tokio::test]
+async fn resolves_store_reference_through_store_api() {
+    let server = match std::panic::catch_unwind(httpmock::MockServer::start) {
+        Ok(s) => s,
+        Err(_) => {
+            eprintln!("skipping store test: unable to bind mock server in this environment");
+            return;
+        }
+    };
+    let digest = digest_for(b"from-store");
+    let lookup = server.mock(|when, then| {
+        when.method(httpmock::Method::GET)
+            .path("/v1/components/acme/echo/versions/1.2.0")
+            .header("authorization", "Bearer store-token");
+        then.status(200).json_body(serde_json::json!({
+            "digest": digest,
+            "location": format!("{}/blobs/echo.wasm", server.base_url()),
+        }));
+    });
+    let blob = server.mock(|when, then| {
+        when.method(httpmock::Method::GET).path("/blobs/echo.wasm");
+        then.status(200).body("from-store");
+    });
+    server.mock(|when, then| {
+        when.method(httpmock::Method::GET)
+            .path("/v1/components/acme/tampered/versions/latest");
+        then.status(200).json_body(serde_json::json!({
+            "digest": digest_for(b"something else"),
+            "location": format!("{}/blobs/echo.wasm", server.base_url()),
+        }));
+    });
+
+    let temp = tempfile::tempdir().unwrap();
+    let credentials = temp.path().join("credentials.json");
+    fs::write(
+        &credentials,
+        r#"{"auths": {"store://acme": {"identitytoken": "store-token"}}}"#,
+    )
+    .unwrap();
+    let client = DistClient::new(DistOptions {
+        credentials_file: Some(credentials),
+        store_url: Some(server.base_url()),
+        ..options(&temp)
+    });
+
+    let resolved = client
+        .ensure_cached("store://acme/echo@1.2.0")
+        .await
+        .unwrap();
+    assert_eq!(resolved.digest, digest);
+    assert!(matches!(resolved.source, ArtifactSource::Store(_)));
+    assert_eq!(
+        fs::read(resolved.cache_path.unwrap()).unwrap(),
+        b"from-store"
+    );
+
+    // Already-cached digests are served without fetching the location again.
+    let again = client
+        .ensure_cached("store://acme/echo@1.2.0")
+        .await
+        .unwrap();
+    assert!(!again.fetched);
+    lookup.assert_calls(2);
+    blob.assert_calls(1);
+
+    let err = client
+        .ensure_cached("store://acme/tampered")
+        .await
+        .unwrap_err();
+    assert!(matches!(err, DistError::DigestMismatch { .. }));
+}
+
+#[tokio::test]
+async fn repo_reference_rejects_non_hex_digests() {
+    let server = match std::panic::catch_unwind(httpmock::MockServer::start) {
+        Ok(s) => s,
+        Err(_) => {
+            eprintln!("skipping repo test: unable to bind mock server in this environment");
+            return;
+        }
+    };
+    server.mock(|when, then| {
+        when.method(httpmock::Method::GET)
+            .path("/v1/components/acme/echo/versions/latest");
+        then.status(200).json_body(serde_json::json!({
+            "digest": format!("sha256:{}", "../".repeat(21) + "x"),
+            "location": format!("{}/blobs/echo.wasm", server.base_url()),
+        }));
+    });
+
+    let temp = tempfile::tempdir().unwrap();
+    let client = DistClient::new(DistOptions {
+        repo_url: Some(server.base_url()),
+        ..options(&temp)
+    });
+    let err = client.ensure_cached("repo://acme/echo").await.unwrap_err();
+    assert!(matches!(err, DistError::InvalidInput(_)), "{err:?}");
+}
+
+#[tokio::test]
+async fn store_reference_rejects_dot_segments_before_any_request() {
+    let temp = tempfile::tempdir().unwrap();
+    let credentials = temp.path().join("credentials.json");
+    fs::write(
+        &credentials,
+        r#"{"auths": {"store://": {"identitytoken": "store-token"}}}"#,
+    )
+    .unwrap();
+    // Nothing listens here: a request that got through would fail with an HTTP error instead.
+    let client = DistClient::new(DistOptions {
+        credentials_file: Some(credentials),
+        store_url: Some("https://127.0.0.1:9/api".into()),
+        ..options(&temp)
+    });
+    for reference in [
+        "store://../x",
+        "store://acme/..",
+        "store://acme/./echo",
+        "store://acme/echo@..",
+    ] {
+        let err = client.ensure_cached(reference).await.unwrap_err();
+        assert!(
+            matches!(err, DistError::InvalidReference { .. }),
+            "{reference}: {err:?}"
+        );
+    }
+}
+
+#[tokio::test]
+async fn store_reference_requires_login_when_rejected() {
+    let server = match std::panic::catch_unwind(httpmock::MockServer::start) {
+        Ok(s) => s,
+        Err(_) => {
+            eprintln!("skipping store test: unable to bind mock server in this environment");
+            return;
+        }
+    };
+    server.mock(|when, then| {
+        when.method(httpmock::Method::GET)
+            .path("/v1/components/acme/private/versions/latest");
+        then.status(401);
+    });
+    server.mock(|when, then| {
+        when.method(httpmock::Method::GET)
+            .path("/v1/components/acme/missing/versions/latest");
+        then.status(404);
+    });
+
+    let temp = tempfile::tempdir().unwrap();
+    let client = DistClient::new(DistOptions {
+        repo_url: Some(server.base_url()),
+        ..options(&temp)
+    });
+
+    let err = client
+        .ensure_cached("repo://acme/private")
+        .await
+        .unwrap_err();
+    assert!(matches!(err, DistError::AuthRequired { .. }));
+    assert_eq!(err.exit_code(), 5);
+    let err = client
+        .ensure_cached("repo://acme/missing")
+        .await
+        .unwrap_err();
+    assert!(matches!(err, DistError::NotFound { .. }));
+
+    // No endpoint configured for store:// references.
+    let err = client.ensure_cached("store://acme/echo").await.unwrap_err();
+    assert!(matches!(err, DistError::InvalidInput(_)));
+}
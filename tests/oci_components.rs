@@ -6,12 +6,16 @@ use std::sync::{
     atomic::{AtomicUsize, Ordering},
 };
 
+use greentic_distributor_client::RegistryCredentials;
 use greentic_distributor_client::oci_components::{
-    ComponentResolveOptions, ComponentsExtension, ComponentsMode, OciComponentError,
-    OciComponentResolver, PulledImage, PulledLayer, RegistryClient, ResolvedComponent,
+    ComponentResolveOptions, ComponentsExtension, ComponentsMode, DefaultRegistryClient,
+    OciComponentError, OciComponentResolver, PulledImage, PulledLayer, RegistryClient,
+    ResolvedComponent,
 };
 use oci_distribution::Reference;
+use oci_distribution::client::ClientProtocol;
 use oci_distribution::errors::OciDistributionError;
+use semver::VersionReq;
 use sha2::{Digest, Sha256};
 use tempfile::TempDir;
 
@@ -19,6 +23,7 @@ use tempfile::TempDir;
 struct MockRegistryClient {
     pulls: Arc<AtomicUsize>,
     images: Arc<Mutex<HashMap<String, PulledImage>>>,
+    tags: Arc<Mutex<HashMap<String, Vec<String>>>>,
 }
 
 impl MockRegistryClient {
@@ -32,6 +37,14 @@ impl MockRegistryClient {
         client
     }
 
+    fn with_tags(self, repository: &str, tags: &[&str]) -> Self {
+        self.tags.lock().unwrap().insert(
+            repository.to_string(),
+            tags.iter().map(|tag| tag.to_string()).collect(),
+        );
+        self
+    }
+
     fn pulls(&self) -> usize {
         self.pulls.load(Ordering::SeqCst)
     }
@@ -49,6 +62,8 @@ impl RegistryClient for MockRegistryClient {
         _accepted_manifest_types: &[&str],
     ) -> Result<PulledImage, OciDistributionError> {
         self.pulls.fetch_add(1, Ordering::SeqCst);
+        // Suspend like a real network pull so concurrent fetches interleave.
+        tokio::task::yield_now().await;
         let key = reference.whole();
         self.images
             .lock()
@@ -57,6 +72,21 @@ impl RegistryClient for MockRegistryClient {
             .cloned()
             .ok_or_else(|| OciDistributionError::GenericError(Some("not found".into())))
     }
+
+    async fn list_tags(
+        &self,
+        reference: &Reference,
+        _credentials: &RegistryCredentials,
+    ) -> Result<Vec<String>, OciDistributionError> {
+        let key = format!("{}/{}", reference.registry(), reference.repository());
+        Ok(self
+            .tags
+            .lock()
+            .unwrap()
+            .get(&key)
+            .cloned()
+            .unwrap_or_default())
+    }
 }
 
 fn options(temp: &TempDir) -> ComponentResolveOptions {
@@ -136,6 +166,23 @@ async fn resolves_digest_pinned_and_caches() {
     assert_eq!(mock.pulls(), 1);
 }
 
+#[tokio::test(flavor = "current_thread")]
+async fn concurrent_fetches_of_one_digest_share_a_single_thread() {
+    let temp = tempfile::tempdir().unwrap();
+    let data = b"wasm-bytes";
+    let digest = digest_for(data);
+    let reference = format!("ghcr.io/greentic/components@{digest}");
+    let mock =
+        MockRegistryClient::with_image(&reference, pulled_image(data, "application/wasm", &digest));
+    let resolver = OciComponentResolver::with_client(mock, options(&temp));
+
+    // Holding the entry lock across the pull would block the only worker thread here.
+    let refs = extension(vec![&reference]);
+    let (first, second) = tokio::join!(resolver.resolve_refs(&refs), resolver.resolve_refs(&refs));
+    assert_eq!(first.unwrap()[0].resolved_digest, digest);
+    assert_eq!(second.unwrap()[0].resolved_digest, digest);
+}
+
 #[tokio::test]
 async fn tag_refs_rejected_without_opt_in() {
     let temp = tempfile::tempdir().unwrap();
@@ -186,6 +233,116 @@ async fn allows_tag_refs_when_opted_in() {
     assert_eq!(mock.pulls(), 1);
 }
 
+#[tokio::test]
+async fn resolve_digest_reads_tag_without_caching() {
+    let temp = tempfile::tempdir().unwrap();
+    let data = b"tagged component";
+    let digest = digest_for(data);
+    let reference = "ghcr.io/greentic/components:1";
+
+    let mut opts = options(&temp);
+    opts.allow_tags = true;
+    let mock =
+        MockRegistryClient::with_image(reference, pulled_image(data, "application/wasm", &digest));
+    let resolver = OciComponentResolver::with_client(mock.clone(), opts.clone());
+    assert_eq!(resolver.resolve_digest(reference).await.unwrap(), digest);
+    assert_eq!(
+        std::fs::read_dir(temp.path()).unwrap().count(),
+        0,
+        "resolve_digest must not cache"
+    );
+
+    let pinned = format!("ghcr.io/greentic/components@{digest}");
+    assert_eq!(resolver.resolve_digest(&pinned).await.unwrap(), digest);
+    assert_eq!(mock.pulls(), 1);
+
+    opts.offline = true;
+    let offline = OciComponentResolver::with_client(mock, opts);
+    let err = offline.resolve_digest(reference).await.unwrap_err();
+    assert!(matches!(
+        err,
+        OciComponentError::OfflineTaggedReference { .. }
+    ));
+}
+
+#[tokio::test]
+async fn resolve_version_picks_highest_matching_semver_tag() {
+    let temp = tempfile::tempdir().unwrap();
+    let repository = "ghcr.io/greentic/components";
+    let mut opts = options(&temp);
+    opts.allow_tags = true;
+    let mock = MockRegistryClient::default().with_tags(
+        repository,
+        &["1.3.9", "v1.4.0", "1.4.2", "1.5.0-rc.1", "latest", "2.0.0"],
+    );
+    let resolver = OciComponentResolver::with_client(mock.clone(), opts.clone());
+
+    let requirement = VersionReq::parse("^1.4").unwrap();
+    assert_eq!(
+        resolver
+            .resolve_version(repository, &requirement)
+            .await
+            .unwrap(),
+        "ghcr.io/greentic/components:1.4.2"
+    );
+    let requirement = VersionReq::parse("~1.4.0, <1.4.1").unwrap();
+    assert_eq!(
+        resolver
+            .resolve_version(repository, &requirement)
+            .await
+            .unwrap(),
+        "ghcr.io/greentic/components:v1.4.0"
+    );
+    let err = resolver
+        .resolve_version(repository, &VersionReq::parse("^3").unwrap())
+        .await
+        .unwrap_err();
+    assert!(matches!(err, OciComponentError::NoMatchingVersion { .. }));
+    assert_eq!(mock.pulls(), 0);
+
+    opts.allow_tags = false;
+    let pinned_only = OciComponentResolver::with_client(mock, opts);
+    let err = pinned_only
+        .resolve_version(repository, &requirement)
+        .await
+        .unwrap_err();
+    assert!(matches!(err, OciComponentError::DigestRequired { .. }));
+}
+
+#[tokio::test]
+async fn overrides_replace_registry_pulls() {
+    let temp = tempfile::tempdir().unwrap();
+    let local = temp.path().join("echo.wasm");
+    std::fs::write(&local, b"local build").unwrap();
+    let reference = format!(
+        "ghcr.io/greentic/echo@{}",
+        digest_for(b"published component")
+    );
+
+    let mut opts = options(&temp);
+    opts.offline = true;
+    opts.overrides.insert("echo", &local);
+    let mock = MockRegistryClient::default();
+    let resolver = OciComponentResolver::with_client(mock.clone(), opts.clone());
+
+    let results = resolver
+        .resolve_refs(&extension(vec![&reference]))
+        .await
+        .unwrap();
+    assert!(results[0].overridden);
+    assert_eq!(results[0].path, local);
+    assert_eq!(results[0].resolved_digest, digest_for(b"local build"));
+    assert_eq!(mock.pulls(), 0);
+
+    opts.strict = true;
+    let strict = OciComponentResolver::with_client(mock, opts);
+    let err = strict
+        .resolve_refs(&extension(vec![&reference]))
+        .await
+        .unwrap_err();
+    assert!(matches!(err, OciComponentError::OverrideRefused { .. }));
+}
+
 #[tokio::test]
 async fn caches_manifest_and_wasm_with_expected_filenames() {
     let temp = tempfile::tempdir().unwrap();
@@ -384,3 +541,54 @@ async fn invalid_reference_surfaces_error() {
         .unwrap_err();
     assert!(matches!(err, OciComponentError::InvalidReference { .. }));
 }
+
+#[tokio::test]
+async fn list_tags_follows_short_pages_until_exhausted() {
+    let server = match std::panic::catch_unwind(httpmock::MockServer::start) {
+        Ok(s) => s,
+        Err(_) => {
+            eprintln!("skipping tag listing test: unable to bind mock server in this environment");
+            return;
+        }
+    };
+    server.mock(|when, then| {
+        when.method(httpmock::Method::GET).path("/v2/");
+        then.status(200);
+    });
+    let page = |last: Option<&str>, tags: &[&str]| {
+        server.mock(|when, then| {
+            let when = when
+                .method(httpmock::Method::GET)
+                .path("/v2/greentic/echo/tags/list")
+                .query_param("n", "1000");
+            match last {
+                Some(last) => when.query_param("last", last),
+                None => when.query_param_missing("last"),
+            };
+            then.status(200).json_body(serde_json::json!({
+                "name": "greentic/echo",
+                "tags": tags,
+            }));
+        })
+    };
+    // The registry caps pages at two tags, far below the requested 1000.
+    let first = page(None, &["1.0.0", "1.1.0"]);
+    let second = page(Some("1.1.0"), &["1.2.0", "2.0.0"]);
+    let last = page(Some("2.0.0"), &[]);
+
+    let client = DefaultRegistryClient::with_protocol(ClientProtocol::HttpsExcept(vec![
+        server.address().to_string(),
+    ]));
+    let reference: Reference = format!("{}/greentic/echo:latest", server.address())
+        .parse()
+        .unwrap();
+    let tags = client
+        .list_tags(&reference, &RegistryCredentials::Anonymous)
+        .await
+        .unwrap();
+
+    assert_eq!(tags, vec!["1.0.0", "1.1.0", "1.2.0", "2.0.0"]);
+    first.assert_calls(1);
+    second.assert_calls(1);
+    last.assert_calls(1);
+}
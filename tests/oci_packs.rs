@@ -118,6 +118,80 @@ async fn resolves_digest_pinned_and_caches() {
     assert_eq!(mock.pulls(), 1);
 }
 
+#[cfg(feature = "dist-client")]
+#[tokio::test]
+async fn packs_share_the_dist_store() {
+    use greentic_distributor_client::dist::{DistClient, DistOptions};
+
+    let temp = tempfile::tempdir().unwrap();
+    let data = b"shared pack";
+    let digest = digest_for(data);
+    let reference = format!("ghcr.io/greentic-ai/greentic-packs/shared@{digest}");
+    let mock = MockRegistryClient::with_image(
+        &reference,
+        pulled_image(data, "application/octet-stream", &digest),
+    );
+    let fetcher = OciPackFetcher::with_client(mock, options(&temp));
+    fetcher.fetch_pack_to_cache(&reference).await.unwrap();
+
+    let component = temp.path().join("component.wasm");
+    std::fs::write(&component, b"component").unwrap();
+    let client = DistClient::new(DistOptions {
+        cache_dir: temp.path().to_path_buf(),
+        ..DistOptions::default()
+    });
+    let resolved = client
+        .ensure_cached(component.to_str().unwrap())
+        .await
+        .unwrap();
+
+    let mut expected = vec![digest, resolved.digest];
+    expected.sort();
+    assert_eq!(client.list_cache(), expected);
+    assert!(client.gc().unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn imports_packs_from_the_legacy_pack_cache() {
+    let temp = tempfile::tempdir().unwrap();
+    let legacy = tempfile::tempdir().unwrap();
+    let data = b"pack cached before the store";
+    let digest = digest_for(data);
+    let reference = format!("ghcr.io/greentic-ai/greentic-packs/old@{digest}");
+    // Layout and metadata written by the pre-store pack cache.
+    let entry = legacy.path().join(digest.trim_start_matches("sha256:"));
+    std::fs::create_dir_all(&entry).unwrap();
+    std::fs::write(entry.join("pack.gtpack"), data).unwrap();
+    let metadata = serde_json::json!({
+        "original_reference": reference,
+        "resolved_digest": digest,
+        "media_type": "application/vnd.greentic.gtpack.v1+zip",
+        "fetched_at_unix_seconds": 1,
+        "size_bytes": data.len(),
+        "manifest_digest": null,
+    });
+    std::fs::write(entry.join("metadata.json"), metadata.to_string()).unwrap();
+
+    let mut opts = options(&temp);
+    opts.offline = true;
+    opts.legacy_cache_dir = Some(legacy.path().to_path_buf());
+    let fetcher = OciPackFetcher::with_client(MockRegistryClient::default(), opts);
+
+    let resolved = fetcher.fetch_pack_to_cache(&reference).await.unwrap();
+    assert!(!resolved.fetched_from_network);
+    assert_eq!(
+        resolved.media_type,
+        "application/vnd.greentic.gtpack.v1+zip"
+    );
+    assert!(resolved.path.starts_with(temp.path()));
+    assert_eq!(std::fs::read(&resolved.path).unwrap(), data);
+
+    // The store now holds its own copy.
+    std::fs::remove_dir_all(&entry).unwrap();
+    let again = fetcher.fetch_pack_to_cache(&reference).await.unwrap();
+    assert_eq!(again.path, resolved.path);
+}
+
 #[tokio::test]
 async fn tag_refs_rejected_without_opt_in() {
     let temp = tempfile::tempdir().unwrap();
@@ -0,0 +1,319 @@
+#![cfg(feature = "dist-client")]
+
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD;
+use greentic_distributor_client::oci_components::{
+    self, ComponentResolveOptions, ComponentsExtension, ComponentsMode, OciComponentResolver,
+};
+use greentic_distributor_client::oci_packs::{self, OciPackFetcher, PackFetchOptions};
+use greentic_distributor_client::{RegistryAuthOptions, RegistryCredentials};
+use httpmock::{Method::GET, MockServer};
+use oci_distribution::client::ClientProtocol;
+use sha2::{Digest, Sha256};
+use std::fs;
+
+fn digest_for(bytes: &[u8]) -> String {
+    format!("sha256:{:x}", Sha256::digest(bytes))
+}
+
+fn start_server() -> Option<MockServer> {
+    match std::panic::catch_unwind(MockServer::start) {
+        Ok(server) => Some(server),
+        Err(_) => {
+            eprintln!(
+                "skipping registry auth test: unable to bind mock server in this environment"
+            );
+            None
+        }
+    }
+}
+
+fn registry_host(server: &MockServer) -> String {
+    server.address().to_string()
+}
+
+/// Serves `repo:tag` (one layer of `media_type`) only to requests carrying `authorization`.
+fn mount_image(
+    server: &MockServer,
+    repo: &str,
+    tag: &str,
+    media_type: &str,
+    data: &[u8],
+    authorization: &str,
+) -> String {
+    let config = b"{}";
+    let layer_digest = digest_for(data);
+    let manifest = serde_json::json!({
+        "schemaVersion": 2,
+        "mediaType": "application/vnd.oci.image.manifest.v1+json",
+        "config": {
+            "mediaType": "application/vnd.oci.image.config.v1+json",
+            "digest": digest_for(config),
+            "size": config.len(),
+        },
+        "layers": [{
+            "mediaType": media_type,
+            "digest": layer_digest,
+            "size": data.len(),
+        }],
+    })
+    .to_string();
+    let manifest_digest = digest_for(manifest.as_bytes());
+
+    for reference in [tag.to_string(), layer_digest.clone()] {
+        let manifest = manifest.clone();
+        let manifest_digest = manifest_digest.clone();
+        server.mock(|when, then| {
+            when.method(GET)
+                .path(format!("/v2/{repo}/manifests/{reference}"))
+                .header("authorization", authorization);
+            then.status(200)
+                .header("content-type", "application/vnd.oci.image.manifest.v1+json")
+                .header("docker-content-digest", manifest_digest)
+                .body(manifest);
+        });
+    }
+    for (digest, body) in [
+        (digest_for(config), config.to_vec()),
+        (layer_digest, data.to_vec()),
+    ] {
+        server.mock(|when, then| {
+            when.method(GET)
+                .path(format!("/v2/{repo}/blobs/{digest}"))
+                .header("authorization", authorization);
+            then.status(200).body(body);
+        });
+    }
+    manifest_digest
+}
+
+fn basic_challenge(server: &MockServer) {
+    server.mock(|when, then| {
+        when.method(GET).path("/v2/");
+        then.status(401)
+            .header("www-authenticate", r#"Basic realm="greentic""#);
+    });
+}
+
+fn basic_header(username: &str, password: &str) -> String {
+    format!(
+        "Basic {}",
+        STANDARD.encode(format!("{username}:{password}"))
+    )
+}
+
+fn component_resolver(
+    server: &MockServer,
+    cache: &std::path::Path,
+    auth: RegistryAuthOptions,
+) -> OciComponentResolver {
+    let client =
+        oci_components::DefaultRegistryClient::with_protocol(ClientProtocol::HttpsExcept(vec![
+            registry_host(server),
+        ]));
+    OciComponentResolver::with_client(
+        client,
+        ComponentResolveOptions {
+            allow_tags: true,
+            cache_dir: cache.to_path_buf(),
+            auth,
+            ..ComponentResolveOptions::default()
+        },
+    )
+}
+
+#[tokio::test]
+async fn component_pull_uses_docker_config_auths() {
+    let Some(server) = start_server() else {
+        return;
+    };
+    basic_challenge(&server);
+    let manifest_digest = mount_image(
+        &server,
+        "greentic/echo",
+        "1.0.0",
+        "application/wasm",
+        b"private wasm",
+        &basic_header("robot", "s3cret"),
+    );
+    let temp = tempfile::tempdir().unwrap();
+    let docker_config = temp.path().join("config.json");
+    fs::write(
+        &docker_config,
+        serde_json::json!({
+            "auths": {
+                format!("http://{}", registry_host(&server)): {
+                    "auth": STANDARD.encode("robot:s3cret")
+                }
+            }
+        })
+        .to_string(),
+    )
+    .unwrap();
+    let reference = format!("{}/greentic/echo:1.0.0", registry_host(&server));
+    let extension = ComponentsExtension {
+        refs: vec![reference],
+        mode: ComponentsMode::Eager,
+    };
+
+    let anonymous = component_resolver(
+        &server,
+        &temp.path().join("anon"),
+        RegistryAuthOptions::anonymous(),
+    );
+    assert!(anonymous.resolve_refs(&extension).await.is_err());
+
+    let resolver = component_resolver(
+        &server,
+        &temp.path().join("cache"),
+        RegistryAuthOptions {
+            use_env: false,
+            docker_config: Some(docker_config),
+            ..RegistryAuthOptions::default()
+        },
+    );
+    let resolved = resolver.resolve_refs(&extension).await.unwrap();
+    assert_eq!(resolved[0].resolved_digest, manifest_digest);
+    assert_eq!(fs::read(&resolved[0].path).unwrap(), b"private wasm");
+}
+
+#[tokio::test]
+async fn component_pull_exchanges_bearer_token() {
+    let Some(server) = start_server() else {
+        return;
+    };
+    let realm = format!("{}/token", server.base_url());
+    server.mock(|when, then| {
+        when.method(GET).path("/v2/");
+        then.status(401).header(
+            "www-authenticate",
+            format!(r#"Bearer realm="{realm}",service="mock-registry""#),
+        );
+    });
+    let token = server.mock(|when, then| {
+        when.method(GET)
+            .path("/token")
+            .query_param("scope", "repository:greentic/echo:pull")
+            .header("authorization", basic_header("<token>", "ghp_secret"));
+        then.status(200)
+            .json_body(serde_json::json!({ "token": "registry-token" }));
+    });
+    mount_image(
+        &server,
+        "greentic/echo",
+        "1.0.0",
+        "application/wasm",
+        b"private wasm",
+        "Bearer registry-token",
+    );
+    let temp = tempfile::tempdir().unwrap();
+    let resolver = component_resolver(
+        &server,
+        temp.path(),
+        RegistryAuthOptions::anonymous().with_credentials(
+            registry_host(&server),
+            RegistryCredentials::IdentityToken("ghp_secret".into()),
+        ),
+    );
+
+    let resolved = resolver
+        .resolve_refs(&ComponentsExtension {
+            refs: vec![format!("{}/greentic/echo:1.0.0", registry_host(&server))],
+            mode: ComponentsMode::Eager,
+        })
+        .await
+        .unwrap();
+    assert_eq!(fs::read(&resolved[0].path).unwrap(), b"private wasm");
+    assert!(token.calls() >= 1);
+}
+
+#[tokio::test]
+async fn pack_fetch_uses_explicit_basic_credentials() {
+    let Some(server) = start_server() else {
+        return;
+    };
+    basic_challenge(&server);
+    mount_image(
+        &server,
+        "greentic/packs/hello",
+        "0.1.0",
+        "application/vnd.greentic.gtpack.v1+zip",
+        b"pack bytes",
+        &basic_header("harbor", "robot-token"),
+    );
+    let temp = tempfile::tempdir().unwrap();
+    let fetcher = OciPackFetcher::with_client(
+        oci_packs::DefaultRegistryClient::with_protocol(ClientProtocol::HttpsExcept(vec![
+            registry_host(&server),
+        ])),
+        PackFetchOptions {
+            allow_tags: true,
+            cache_dir: temp.path().to_path_buf(),
+            auth: RegistryAuthOptions::anonymous().with_credentials(
+                registry_host(&server),
+                RegistryCredentials::Basic {
+                    username: "harbor".into(),
+                    password: "robot-token".into(),
+                },
+            ),
+            ..PackFetchOptions::default()
+        },
+    );
+
+    let bytes = fetcher
+        .fetch_pack(&format!(
+            "{}/greentic/packs/hello:0.1.0",
+            registry_host(&server)
+        ))
+        .await
+        .unwrap();
+    assert_eq!(bytes, b"pack bytes");
+}
+
+#[cfg(feature = "runner-api")]
+#[tokio::test]
+async fn digest_fetch_uses_explicit_basic_credentials() {
+    use greentic_distributor_client::runner_api::{self, DigestFetchOptions, DigestFetcher};
+
+    let Some(server) = start_server() else {
+        return;
+    };
+    basic_challenge(&server);
+    // `DigestFetcher` filters layers by its accepted manifest types.
+    mount_image(
+        &server,
+        "greentic/blobs",
+        "latest",
+        "application/vnd.oci.image.manifest.v1+json",
+        b"blob bytes",
+        &basic_header("ci", "pull-only"),
+    );
+    let temp = tempfile::tempdir().unwrap();
+    let fetcher = DigestFetcher::with_client(
+        runner_api::DefaultRegistryClient::with_protocol(ClientProtocol::HttpsExcept(vec![
+            registry_host(&server),
+        ])),
+        DigestFetchOptions {
+            cache_dir: temp.path().to_path_buf(),
+            auth: RegistryAuthOptions::anonymous().with_credentials(
+                registry_host(&server),
+                RegistryCredentials::Basic {
+                    username: "ci".into(),
+                    password: "pull-only".into(),
+                },
+            ),
+            ..DigestFetchOptions::default()
+        },
+    );
+
+    let (bytes, metadata) = fetcher
+        .fetch_by_digest(format!(
+            "{}/greentic/blobs@{}",
+            registry_host(&server),
+            digest_for(b"blob bytes")
+        ))
+        .await
+        .unwrap();
+    assert_eq!(bytes, b"blob bytes");
+    assert!(!metadata.cache.hit);
+}
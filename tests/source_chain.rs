@@ -0,0 +1,64 @@
+#![cfg(feature = "dist-client")]
+
+use greentic_distributor_client::{
+    DistributorError, DistributorSource, PackId, SourceChainBuilder, SourceChainConfig, Version,
+};
+
+#[test]
+fn http_tree_behind_cache_serves_and_persists() {
+    let server = match std::panic::catch_unwind(httpmock::MockServer::start) {
+        Ok(s) => s,
+        Err(_) => {
+            eprintln!("skipping http tree test: unable to bind mock server in this environment");
+            return;
+        }
+    };
+    let mock = server.mock(|when, then| {
+        when.method(httpmock::Method::GET)
+            .path("/packs/demo.pack/1.2.0/pack.gtpack");
+        then.status(200).body("pack-over-http");
+    });
+    let missing = server.mock(|when, then| {
+        when.method(httpmock::Method::GET)
+            .path("/packs/demo.absent/1.2.0/pack.gtpack");
+        then.status(404);
+    });
+
+    let cache = tempfile::tempdir().unwrap();
+    let config = SourceChainConfig::from_json_str(
+        &serde_json::json!({
+            "sources": [{
+                "kind": "cache",
+                "dir": cache.path(),
+                "source": { "kind": "http", "name": "http", "base_url": server.base_url() }
+            }]
+        })
+        .to_string(),
+    )
+    .unwrap();
+    let chain = SourceChainBuilder::new().build(&config).unwrap();
+    let version = Version::parse("1.2.0").unwrap();
+
+    let pack_id = PackId::try_from("demo.pack").unwrap();
+    assert_eq!(
+        chain.fetch_pack(&pack_id, &version).unwrap(),
+        b"pack-over-http"
+    );
+    assert_eq!(
+        chain.fetch_pack(&pack_id, &version).unwrap(),
+        b"pack-over-http"
+    );
+    mock.assert_calls(1);
+    assert!(
+        cache
+            .path()
+            .join("packs/demo.pack/1.2.0/pack.gtpack")
+            .exists()
+    );
+
+    let absent = PackId::try_from("demo.absent").unwrap();
+    let err = chain.fetch_pack(&absent, &version).unwrap_err();
+    assert!(err.is_not_found());
+    assert!(matches!(err, DistributorError::NotFound));
+    missing.assert();
+}